@@ -1,8 +1,14 @@
 use std::{future::Future, time::Duration};
 
 use defer_lite::defer;
-use math::{get_time, Controller, State};
-use nalgebra::Vector3;
+use math::{
+    geometry::{Pose2d, Rotation2d},
+    get_time,
+    interpolation::Interpolate,
+    kinematics::ChassisSpeeds,
+    units::{AngularVelocity, LinearVelocity},
+    Controller, State,
+};
 
 pub use choreo_macros::choreo;
 
@@ -13,7 +19,7 @@ pub struct Path<'a> {
 }
 
 /// One singular point along a [Path]
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct TrajectoryPoint {
     pub x: f32,
     pub y: f32,
@@ -24,48 +30,55 @@ pub struct TrajectoryPoint {
     pub timestamp: Duration,
 }
 
-fn linear_interpolate(
-    first: f32,
-    first_time: f32,
-    second_time: f32,
-    second: f32,
-    time: f32,
-) -> f32 {
-    (first * (second_time - time) + second * (first_time - time)) / (second_time - first_time)
+impl TrajectoryPoint {
+    /// Get the position and heading of the robot at this point
+    pub fn pose(&self) -> Pose2d {
+        Pose2d::new(self.x, self.y, Rotation2d::from_radians(self.heading))
+    }
+
+    /// Get the speeds of the robot at this point, relative to the field
+    pub fn field_speeds(&self) -> ChassisSpeeds {
+        ChassisSpeeds::new(
+            LinearVelocity::from_meters_per_second(self.velocity_x),
+            LinearVelocity::from_meters_per_second(self.velocity_y),
+            AngularVelocity::from_radians_per_second(self.angular_velocity),
+        )
+    }
+}
+
+impl Interpolate for TrajectoryPoint {
+    fn interpolate(&self, end: &Self, t: f32) -> Self {
+        TrajectoryPoint {
+            x: self.x.interpolate(&end.x, t),
+            y: self.y.interpolate(&end.y, t),
+            // Interpolate along the shortest arc so headings either side of ±π don't spin the
+            // long way around
+            heading: Rotation2d::from_radians(self.heading)
+                .interpolate(&Rotation2d::from_radians(end.heading), t)
+                .radians(),
+            angular_velocity: self.angular_velocity.interpolate(&end.angular_velocity, t),
+            velocity_x: self.velocity_x.interpolate(&end.velocity_x, t),
+            velocity_y: self.velocity_y.interpolate(&end.velocity_y, t),
+            timestamp: self.timestamp
+                + end
+                    .timestamp
+                    .saturating_sub(self.timestamp)
+                    .mul_f32(t.clamp(0.0, 1.0)),
+        }
+    }
 }
 
 fn interpolate_point(a: &TrajectoryPoint, b: &TrajectoryPoint, time: Duration) -> TrajectoryPoint {
-    let time_secs = time.as_secs_f32();
-    let first_time = a.timestamp.as_secs_f32();
-    let second_time = b.timestamp.as_secs_f32();
-
-    TrajectoryPoint {
-        x: linear_interpolate(a.x, first_time, second_time, b.x, time_secs),
-        y: linear_interpolate(a.y, first_time, second_time, b.y, time_secs),
-        heading: linear_interpolate(a.heading, first_time, second_time, b.heading, time_secs),
-        angular_velocity: linear_interpolate(
-            a.angular_velocity,
-            first_time,
-            second_time,
-            b.angular_velocity,
-            time_secs,
-        ),
-        velocity_x: linear_interpolate(
-            a.velocity_x,
-            first_time,
-            second_time,
-            b.velocity_x,
-            time_secs,
-        ),
-        velocity_y: linear_interpolate(
-            a.velocity_y,
-            first_time,
-            second_time,
-            b.velocity_y,
-            time_secs,
-        ),
-        timestamp: time,
+    let duration = b.timestamp.saturating_sub(a.timestamp);
+
+    // Samples at the same time would divide by zero
+    if duration.is_zero() {
+        return *a;
     }
+
+    let t = time.saturating_sub(a.timestamp).as_secs_f32() / duration.as_secs_f32();
+
+    a.interpolate(b, t)
 }
 
 /// Follow the given path and pass the points into a closure. Consumer gets called every loop,
@@ -197,7 +210,7 @@ fn deriver(mut initial: Option<f32>) -> impl FnMut(f32) -> f32 {
 /// This function takes in a closure that returns the robot's current pose, 3 controllers for each
 /// axis, and a closure that consumes the final 3 control outputs. See [follow_path] for example.
 pub fn simple_controller<E, O1, O2, O3>(
-    mut pose: impl FnMut() -> Vector3<f32>,
+    mut pose: impl FnMut() -> Pose2d,
     mut x_controller: impl Controller<State = State, Output = O1>,
     mut y_controller: impl Controller<State = State, Output = O2>,
     mut angle_controller: impl Controller<State = State, Output = O3>,
@@ -209,9 +222,9 @@ pub fn simple_controller<E, O1, O2, O3>(
 
     move |point| {
         let current_pose = pose();
-        let x = current_pose.x;
-        let y = current_pose.y;
-        let heading = current_pose.z;
+        let x = current_pose.x();
+        let y = current_pose.y();
+        let heading = current_pose.rotation.radians();
 
         let x_vel = x_velocity(x);
         let y_vel = y_velocity(x);
@@ -251,3 +264,79 @@ pub fn simple_controller<E, O1, O2, O3>(
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::*;
+
+    const EPSILON: f32 = 1e-4;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < EPSILON,
+            "{actual} != {expected}"
+        );
+    }
+
+    fn point(x: f32, heading: f32, millis: u64) -> TrajectoryPoint {
+        TrajectoryPoint {
+            x,
+            y: 0.0,
+            heading,
+            angular_velocity: 0.0,
+            velocity_x: 0.0,
+            velocity_y: 0.0,
+            timestamp: Duration::from_millis(millis),
+        }
+    }
+
+    #[test]
+    fn interpolates_between_points() {
+        let point = interpolate_point(
+            &point(0.0, 0.0, 1000),
+            &point(2.0, 1.0, 1200),
+            Duration::from_millis(1050),
+        );
+
+        assert_close(point.x, 0.5);
+        assert_close(point.heading, 0.25);
+        assert_close(point.timestamp.as_secs_f32(), 1.05);
+    }
+
+    #[test]
+    fn times_before_the_first_point_are_clamped() {
+        let point = interpolate_point(
+            &point(0.0, 0.0, 1000),
+            &point(2.0, 0.0, 1200),
+            Duration::from_millis(500),
+        );
+
+        assert_close(point.x, 0.0);
+        assert_eq!(point.timestamp, Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn points_at_the_same_time() {
+        let point = interpolate_point(
+            &point(1.0, 0.0, 1000),
+            &point(2.0, 0.0, 1000),
+            Duration::from_millis(1000),
+        );
+
+        assert_close(point.x, 1.0);
+        assert!(!point.heading.is_nan());
+    }
+
+    #[test]
+    fn heading_takes_the_shortest_arc() {
+        let point = interpolate_point(
+            &point(0.0, PI - 0.1, 0),
+            &point(0.0, -PI + 0.1, 100),
+            Duration::from_millis(50),
+        );
+
+        assert_close(point.heading, PI);
+    }
+}
@@ -2,6 +2,7 @@ use std::ffi::c_void;
 
 use robotrs::{
     control::ControlSafe,
+    math::units::Voltage,
     motor::{MotorController, SetIdleMode},
 };
 
@@ -91,7 +92,7 @@ impl MotorController for VictorSPX {
         self.set_percent_ctre(value as f64)
     }
 
-    fn set_voltage(&mut self, _value: f32) -> Result<(), Self::Error> {
+    fn set_voltage(&mut self, _value: Voltage) -> Result<(), Self::Error> {
         unimplemented!()
     }
 }
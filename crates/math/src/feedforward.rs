@@ -1,36 +1,80 @@
-use super::{Controller, Derive, Gain, State, Velocity as VelocityExtractor};
+use std::time::Duration;
+
+use super::{
+    units::Voltage, AtGoal, Controller, Derive, Gain, State, Velocity as VelocityExtractor,
+};
+
+/// Treat the output of a controller as volts so that it can be combined with the feedforward
+/// controllers in a tuple
+///
+/// # Example
+///
+/// ```rust
+/// # use math::{feedforward::{FullArm, Volts}, ConstFloat, Gain, Position, PID};
+/// # const KS: Gain = ConstFloat::new(0.1);
+/// # const KG: Gain = ConstFloat::new(0.4);
+/// # const KV: Gain = ConstFloat::new(1.2);
+/// # const KA: Gain = ConstFloat::new(0.05);
+/// # const KP: Gain = ConstFloat::new(2.0);
+/// # const KI: Gain = ConstFloat::new(0.0);
+/// # const KD: Gain = ConstFloat::new(0.1);
+/// type ArmController = (FullArm<KS, KG, KV, KA>, Volts<Position<PID<KP, KI, KD>>>);
+/// ```
+pub struct Volts<C>(pub C);
+
+impl<C: Controller<Output = f32>> Controller for Volts<C> {
+    type State = C::State;
+    type Output = Voltage;
+
+    #[inline]
+    fn calculate_with_time(
+        &mut self,
+        current: &Self::State,
+        target: &Self::State,
+        time: Duration,
+    ) -> Voltage {
+        Voltage::from_volts(self.0.calculate_with_time(current, target, time))
+    }
+}
+
+impl<C: AtGoal> AtGoal for Volts<C> {
+    fn at_goal(&self) -> bool {
+        self.0.at_goal()
+    }
+}
+
+impl<C: Default> Default for Volts<C> {
+    fn default() -> Self {
+        Self(Default::default())
+    }
+}
 
 pub struct Static<const K: Gain>;
 
 impl<const K: Gain> Controller for Static<K> {
     type State = State;
-    type Output = f32;
+    type Output = Voltage;
 
     #[inline]
     fn calculate_with_time(
         &mut self,
         _current: &State,
         target: &State,
-        _time: std::time::Duration,
-    ) -> f32 {
-        target.velocity.signum() * K.get()
+        _time: Duration,
+    ) -> Voltage {
+        Voltage::from_volts(target.velocity.signum() * K.get())
     }
 }
 
 pub struct TargetProportional<const K: Gain>;
 
 impl<const K: Gain> Controller for TargetProportional<K> {
-    type Output = f32;
+    type Output = Voltage;
     type State = f32;
 
     #[inline]
-    fn calculate_with_time(
-        &mut self,
-        _current: &f32,
-        target: &f32,
-        _time: std::time::Duration,
-    ) -> f32 {
-        target * K.get()
+    fn calculate_with_time(&mut self, _current: &f32, target: &f32, _time: Duration) -> Voltage {
+        Voltage::from_volts(target * K.get())
     }
 }
 
@@ -53,7 +97,7 @@ pub type Acceleration<const K: Gain> = VelocityExtractor<Derive<TargetProportion
 pub struct Elevator<const K: Gain>;
 
 impl<const K: Gain> Controller for Elevator<K> {
-    type Output = f32;
+    type Output = Voltage;
     type State = State;
 
     #[inline]
@@ -61,16 +105,16 @@ impl<const K: Gain> Controller for Elevator<K> {
         &mut self,
         _current: &State,
         _target: &State,
-        _time: std::time::Duration,
-    ) -> f32 {
-        K.get()
+        _time: Duration,
+    ) -> Voltage {
+        Voltage::from_volts(K.get())
     }
 }
 
 pub struct Arm<const K: Gain>;
 
 impl<const K: Gain> Controller for Arm<K> {
-    type Output = f32;
+    type Output = Voltage;
     type State = State;
 
     #[inline]
@@ -78,9 +122,9 @@ impl<const K: Gain> Controller for Arm<K> {
         &mut self,
         _current: &State,
         target: &State,
-        _time: std::time::Duration,
-    ) -> f32 {
-        K.get() * target.position.cos()
+        _time: Duration,
+    ) -> Voltage {
+        Voltage::from_volts(K.get() * target.position.cos())
     }
 }
 
@@ -0,0 +1,20 @@
+//! Poses, rotations and transforms in the WPILib field and robot coordinate systems
+//! ([Link to WPILib docs](https://docs.wpilib.org/en/stable/docs/software/basic-programming/coordinate-system.html)).
+//! All distances are in meters and all angles are in radians.
+//!
+//! ```rust
+//! # use math::geometry::{Pose2d, Rotation2d, Translation2d, Twist2d};
+//! let start = Pose2d::new(1.0, 2.0, Rotation2d::from_degrees(90.0));
+//! let end = start.exp(Twist2d::new(1.0, 0.0, 0.0));
+//!
+//! let relative = end.relative_to(&start);
+//!
+//! assert!(relative.translation.distance(&Translation2d::new(1.0, 0.0)) < 1e-6);
+//! assert_eq!(relative.rotation, Rotation2d::IDENTITY);
+//! ```
+
+mod geometry2d;
+mod geometry3d;
+
+pub use geometry2d::{Pose2d, Rotation2d, Transform2d, Translation2d, Twist2d};
+pub use geometry3d::{Pose3d, Rotation3d, Transform3d, Translation3d, Twist3d};
@@ -0,0 +1,675 @@
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use nalgebra::{Vector2, Vector3};
+
+use crate::units::{Angle, Length};
+
+/// A rotation in 2d space. Positive rotations are counterclockwise
+#[derive(Clone, Copy, Debug)]
+pub struct Rotation2d {
+    radians: f32,
+    cos: f32,
+    sin: f32,
+}
+
+impl Rotation2d {
+    pub const IDENTITY: Self = Self {
+        radians: 0.0,
+        cos: 1.0,
+        sin: 0.0,
+    };
+
+    pub fn new(angle: Angle) -> Self {
+        Self::from_radians(angle.radians())
+    }
+
+    pub fn from_radians(radians: f32) -> Self {
+        Self {
+            radians,
+            cos: radians.cos(),
+            sin: radians.sin(),
+        }
+    }
+
+    pub fn from_degrees(degrees: f32) -> Self {
+        Self::new(Angle::from_degrees(degrees))
+    }
+
+    /// Create the rotation that points along the vector (x, y). If the vector has no length the
+    /// rotation is zero
+    pub fn from_vector(x: f32, y: f32) -> Self {
+        let magnitude = x.hypot(y);
+
+        if magnitude > f32::EPSILON {
+            Self {
+                radians: y.atan2(x),
+                cos: x / magnitude,
+                sin: y / magnitude,
+            }
+        } else {
+            Self::IDENTITY
+        }
+    }
+
+    pub fn angle(&self) -> Angle {
+        Angle::from_radians(self.radians)
+    }
+
+    pub fn radians(&self) -> f32 {
+        self.radians
+    }
+
+    pub fn degrees(&self) -> f32 {
+        self.angle().degrees()
+    }
+
+    pub fn cos(&self) -> f32 {
+        self.cos
+    }
+
+    pub fn sin(&self) -> f32 {
+        self.sin
+    }
+
+    pub fn tan(&self) -> f32 {
+        self.sin / self.cos
+    }
+
+    /// Add two rotations together
+    pub fn rotate_by(&self, other: &Rotation2d) -> Self {
+        let cos = self.cos * other.cos - self.sin * other.sin;
+        let sin = self.cos * other.sin + self.sin * other.cos;
+
+        // Normalize to keep rounding errors from building up over many compositions
+        let magnitude = cos.hypot(sin);
+
+        Self {
+            radians: self.radians + other.radians,
+            cos: cos / magnitude,
+            sin: sin / magnitude,
+        }
+    }
+
+    pub fn inverse(&self) -> Self {
+        Self {
+            radians: -self.radians,
+            cos: self.cos,
+            sin: -self.sin,
+        }
+    }
+
+    /// Interpolate between two rotations along the shortest path. `t` is clamped between 0 and 1
+    pub fn interpolate(&self, end: &Rotation2d, t: f32) -> Self {
+        let difference = end.rotate_by(&self.inverse());
+        let shortest = difference.sin.atan2(difference.cos);
+
+        self.rotate_by(&Self::from_radians(shortest * t.clamp(0.0, 1.0)))
+    }
+}
+
+impl Default for Rotation2d {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// Rotations are equal if they point in the same direction, so 0 and 360 degrees are equal
+impl PartialEq for Rotation2d {
+    fn eq(&self, other: &Self) -> bool {
+        (self.cos - other.cos).hypot(self.sin - other.sin) < 1e-6
+    }
+}
+
+impl From<Angle> for Rotation2d {
+    fn from(value: Angle) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<Rotation2d> for Angle {
+    fn from(value: Rotation2d) -> Self {
+        value.angle()
+    }
+}
+
+impl Add for Rotation2d {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        self.rotate_by(&rhs)
+    }
+}
+
+impl Sub for Rotation2d {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        self.rotate_by(&rhs.inverse())
+    }
+}
+
+impl Neg for Rotation2d {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        self.inverse()
+    }
+}
+
+impl Mul<f32> for Rotation2d {
+    type Output = Self;
+
+    fn mul(self, rhs: f32) -> Self {
+        Self::from_radians(self.radians * rhs)
+    }
+}
+
+/// A position in 2d space, in meters
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Translation2d {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Translation2d {
+    pub const ZERO: Self = Self { x: 0.0, y: 0.0 };
+
+    pub fn new(x: f32, y: f32) -> Self {
+        Self { x, y }
+    }
+
+    pub fn from_lengths(x: Length, y: Length) -> Self {
+        Self::new(x.meters(), y.meters())
+    }
+
+    /// Create a translation `distance` meters away from the origin in the direction of `angle`
+    pub fn from_polar(distance: f32, angle: Rotation2d) -> Self {
+        Self::new(distance * angle.cos(), distance * angle.sin())
+    }
+
+    pub fn x_length(&self) -> Length {
+        Length::from_meters(self.x)
+    }
+
+    pub fn y_length(&self) -> Length {
+        Length::from_meters(self.y)
+    }
+
+    /// Get the distance from the origin
+    pub fn norm(&self) -> f32 {
+        self.x.hypot(self.y)
+    }
+
+    /// Get the direction of the translation from the origin
+    pub fn angle(&self) -> Rotation2d {
+        Rotation2d::from_vector(self.x, self.y)
+    }
+
+    pub fn distance(&self, other: &Translation2d) -> f32 {
+        (*other - *self).norm()
+    }
+
+    /// Rotate the translation counterclockwise around the origin
+    pub fn rotate_by(&self, rotation: &Rotation2d) -> Self {
+        Self::new(
+            self.x * rotation.cos() - self.y * rotation.sin(),
+            self.x * rotation.sin() + self.y * rotation.cos(),
+        )
+    }
+
+    /// Linearly interpolate between two translations. `t` is clamped between 0 and 1
+    pub fn interpolate(&self, end: &Translation2d, t: f32) -> Self {
+        *self + (*end - *self) * t.clamp(0.0, 1.0)
+    }
+}
+
+impl From<Vector2<f32>> for Translation2d {
+    fn from(value: Vector2<f32>) -> Self {
+        Self::new(value.x, value.y)
+    }
+}
+
+impl From<Translation2d> for Vector2<f32> {
+    fn from(value: Translation2d) -> Self {
+        Vector2::new(value.x, value.y)
+    }
+}
+
+impl Add for Translation2d {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl Sub for Translation2d {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl Neg for Translation2d {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.x, -self.y)
+    }
+}
+
+impl Mul<f32> for Translation2d {
+    type Output = Self;
+
+    fn mul(self, rhs: f32) -> Self {
+        Self::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+impl Div<f32> for Translation2d {
+    type Output = Self;
+
+    fn div(self, rhs: f32) -> Self {
+        Self::new(self.x / rhs, self.y / rhs)
+    }
+}
+
+/// A change in position and rotation, relative to the starting pose
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Transform2d {
+    pub translation: Translation2d,
+    pub rotation: Rotation2d,
+}
+
+impl Transform2d {
+    pub fn new(translation: Translation2d, rotation: Rotation2d) -> Self {
+        Self {
+            translation,
+            rotation,
+        }
+    }
+
+    /// Get the transform that maps `initial` to `last`
+    pub fn between(initial: &Pose2d, last: &Pose2d) -> Self {
+        Self {
+            translation: (last.translation - initial.translation)
+                .rotate_by(&initial.rotation.inverse()),
+            rotation: last.rotation - initial.rotation,
+        }
+    }
+
+    /// Get the transform that undoes this transform
+    pub fn inverse(&self) -> Self {
+        Self {
+            translation: (-self.translation).rotate_by(&self.rotation.inverse()),
+            rotation: self.rotation.inverse(),
+        }
+    }
+}
+
+/// Apply `rhs` after this transform
+impl Add for Transform2d {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::between(
+            &Pose2d::default(),
+            &Pose2d::default().transform_by(&self).transform_by(&rhs),
+        )
+    }
+}
+
+impl Mul<f32> for Transform2d {
+    type Output = Self;
+
+    fn mul(self, rhs: f32) -> Self {
+        Self::new(self.translation * rhs, self.rotation * rhs)
+    }
+}
+
+/// A movement along an arc, relative to the starting pose. Driving with a constant
+/// [ChassisSpeeds](crate::kinematics::ChassisSpeeds) for some amount of time results in a twist
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Twist2d {
+    /// Forward distance
+    pub dx: f32,
+    /// Distance to the left
+    pub dy: f32,
+    /// Counterclockwise rotation in radians
+    pub dtheta: f32,
+}
+
+impl Twist2d {
+    pub fn new(dx: f32, dy: f32, dtheta: f32) -> Self {
+        Self { dx, dy, dtheta }
+    }
+}
+
+impl Mul<f32> for Twist2d {
+    type Output = Self;
+
+    fn mul(self, rhs: f32) -> Self {
+        Self::new(self.dx * rhs, self.dy * rhs, self.dtheta * rhs)
+    }
+}
+
+/// The position and heading of the robot on the field
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Pose2d {
+    pub translation: Translation2d,
+    pub rotation: Rotation2d,
+}
+
+impl Pose2d {
+    pub fn new(x: f32, y: f32, rotation: Rotation2d) -> Self {
+        Self::from_parts(Translation2d::new(x, y), rotation)
+    }
+
+    pub fn from_parts(translation: Translation2d, rotation: Rotation2d) -> Self {
+        Self {
+            translation,
+            rotation,
+        }
+    }
+
+    pub fn x(&self) -> f32 {
+        self.translation.x
+    }
+
+    pub fn y(&self) -> f32 {
+        self.translation.y
+    }
+
+    /// Apply a transform relative to this pose
+    pub fn transform_by(&self, transform: &Transform2d) -> Self {
+        Self {
+            translation: self.translation + transform.translation.rotate_by(&self.rotation),
+            rotation: self.rotation + transform.rotation,
+        }
+    }
+
+    /// Get this pose in the coordinate frame of `other`. For example, the pose of a game piece
+    /// relative to the robot
+    pub fn relative_to(&self, other: &Pose2d) -> Self {
+        let transform = Transform2d::between(other, self);
+
+        Self::from_parts(transform.translation, transform.rotation)
+    }
+
+    /// Get the pose reached by driving along the arc described by `twist`, starting at this pose.
+    /// This is more accurate than adding the twist as a straight line when the robot is rotating
+    pub fn exp(&self, twist: Twist2d) -> Self {
+        let dtheta = twist.dtheta;
+
+        let (s, c) = if dtheta.abs() > f32::EPSILON {
+            (dtheta.sin() / dtheta, (1.0 - dtheta.cos()) / dtheta)
+        } else {
+            (1.0 - dtheta.powi(2) / 6.0, dtheta / 2.0)
+        };
+
+        self.transform_by(&Transform2d::new(
+            Translation2d::new(twist.dx * s - twist.dy * c, twist.dx * c + twist.dy * s),
+            Rotation2d::from_radians(dtheta),
+        ))
+    }
+
+    /// Get the twist that moves this pose to `end`. This is the inverse of [Pose2d::exp]
+    pub fn log(&self, end: &Pose2d) -> Twist2d {
+        let transform = end.relative_to(self);
+        let dtheta = transform.rotation.sin().atan2(transform.rotation.cos());
+        let half_dtheta = dtheta / 2.0;
+
+        let half_theta_by_tan_of_half_dtheta = if dtheta.abs() > 1e-3 {
+            half_dtheta / half_dtheta.tan()
+        } else {
+            1.0 - dtheta.powi(2) / 12.0
+        };
+
+        let translation = transform.translation.rotate_by(&Rotation2d::from_vector(
+            half_theta_by_tan_of_half_dtheta,
+            -half_dtheta,
+        )) * half_theta_by_tan_of_half_dtheta.hypot(half_dtheta);
+
+        Twist2d::new(translation.x, translation.y, dtheta)
+    }
+
+    /// Interpolate between two poses along the twist that connects them. `t` is clamped between
+    /// 0 and 1
+    pub fn interpolate(&self, end: &Pose2d, t: f32) -> Self {
+        if t <= 0.0 {
+            *self
+        } else if t >= 1.0 {
+            *end
+        } else {
+            self.exp(self.log(end) * t)
+        }
+    }
+}
+
+/// Convert from a vector of x, y, rotation
+impl From<Vector3<f32>> for Pose2d {
+    fn from(value: Vector3<f32>) -> Self {
+        Self::new(value.x, value.y, Rotation2d::from_radians(value.z))
+    }
+}
+
+/// Convert to a vector of x, y, rotation
+impl From<Pose2d> for Vector3<f32> {
+    fn from(value: Pose2d) -> Self {
+        Vector3::new(value.x(), value.y(), value.rotation.radians())
+    }
+}
+
+impl Add<Transform2d> for Pose2d {
+    type Output = Self;
+
+    fn add(self, rhs: Transform2d) -> Self {
+        self.transform_by(&rhs)
+    }
+}
+
+/// Get the transform from `rhs` to this pose
+impl Sub for Pose2d {
+    type Output = Transform2d;
+
+    fn sub(self, rhs: Self) -> Transform2d {
+        Transform2d::between(&rhs, &self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_PI_2, PI, SQRT_2};
+
+    use super::*;
+
+    const EPSILON: f32 = 1e-4;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < EPSILON,
+            "{actual} is not close to {expected}"
+        );
+    }
+
+    fn assert_rotation(actual: Rotation2d, degrees: f32) {
+        let expected = Rotation2d::from_degrees(degrees);
+
+        assert!(
+            (actual.cos() - expected.cos()).hypot(actual.sin() - expected.sin()) < EPSILON,
+            "{} degrees is not close to {degrees} degrees",
+            actual.degrees()
+        );
+    }
+
+    fn assert_pose(actual: Pose2d, x: f32, y: f32, degrees: f32) {
+        assert_close(actual.x(), x);
+        assert_close(actual.y(), y);
+        assert_rotation(actual.rotation, degrees);
+    }
+
+    #[test]
+    fn rotation_units() {
+        assert_close(Rotation2d::from_radians(PI / 3.0).degrees(), 60.0);
+        assert_close(Rotation2d::from_degrees(45.0).radians(), PI / 4.0);
+        assert_close(Rotation2d::from_vector(1.0, 1.0).degrees(), 45.0);
+    }
+
+    #[test]
+    fn rotation_rotate_by() {
+        assert_rotation(
+            Rotation2d::IDENTITY.rotate_by(&Rotation2d::from_degrees(90.0)),
+            90.0,
+        );
+        assert_rotation(
+            Rotation2d::from_degrees(90.0).rotate_by(&Rotation2d::from_degrees(30.0)),
+            120.0,
+        );
+        assert_rotation(
+            Rotation2d::from_degrees(70.0) - Rotation2d::from_degrees(30.0),
+            40.0,
+        );
+        assert_rotation(-Rotation2d::from_degrees(30.0), -30.0);
+    }
+
+    #[test]
+    fn rotation_equality_wraps() {
+        assert_eq!(
+            Rotation2d::from_degrees(0.0),
+            Rotation2d::from_degrees(360.0)
+        );
+        assert_eq!(
+            Rotation2d::from_degrees(-90.0),
+            Rotation2d::from_degrees(270.0)
+        );
+        assert_ne!(Rotation2d::from_degrees(0.0), Rotation2d::from_degrees(1.0));
+    }
+
+    #[test]
+    fn rotation_interpolate_shortest_path() {
+        let start = Rotation2d::from_degrees(50.0);
+        let end = Rotation2d::from_degrees(70.0);
+
+        assert_rotation(start.interpolate(&end, 0.5), 60.0);
+
+        let start = Rotation2d::from_degrees(-160.0);
+        let end = Rotation2d::from_degrees(160.0);
+
+        assert_rotation(start.interpolate(&end, 0.5), 180.0);
+        assert_rotation(start.interpolate(&end, 0.25), -170.0);
+    }
+
+    #[test]
+    fn translation() {
+        let rotated = Translation2d::new(3.0, 0.0).rotate_by(&Rotation2d::from_degrees(90.0));
+
+        assert_close(rotated.x, 0.0);
+        assert_close(rotated.y, 3.0);
+
+        assert_close(
+            Translation2d::new(1.0, 1.0).distance(&Translation2d::new(6.0, 6.0)),
+            5.0 * SQRT_2,
+        );
+        assert_close(Translation2d::new(3.0, 5.0).norm(), 34.0f32.sqrt());
+
+        let polar = Translation2d::from_polar(SQRT_2, Rotation2d::from_degrees(45.0));
+
+        assert_close(polar.x, 1.0);
+        assert_close(polar.y, 1.0);
+    }
+
+    #[test]
+    fn pose_transform_by() {
+        let initial = Pose2d::new(1.0, 2.0, Rotation2d::from_degrees(45.0));
+        let transform =
+            Transform2d::new(Translation2d::new(5.0, 0.0), Rotation2d::from_degrees(5.0));
+
+        assert_pose(
+            initial.transform_by(&transform),
+            1.0 + 5.0 / SQRT_2,
+            2.0 + 5.0 / SQRT_2,
+            50.0,
+        );
+        assert_pose(
+            initial + transform,
+            1.0 + 5.0 / SQRT_2,
+            2.0 + 5.0 / SQRT_2,
+            50.0,
+        );
+    }
+
+    #[test]
+    fn pose_relative_to() {
+        let initial = Pose2d::new(0.0, 0.0, Rotation2d::from_degrees(45.0));
+        let last = Pose2d::new(5.0, 5.0, Rotation2d::from_degrees(45.0));
+
+        assert_pose(last.relative_to(&initial), 5.0 * SQRT_2, 0.0, 0.0);
+
+        let transform = last - initial;
+
+        assert_pose(initial + transform, 5.0, 5.0, 45.0);
+        assert_pose(last + transform.inverse(), 0.0, 0.0, 45.0);
+    }
+
+    #[test]
+    fn pose_exp() {
+        let origin = Pose2d::default();
+
+        assert_pose(origin.exp(Twist2d::new(5.0, 0.0, 0.0)), 5.0, 0.0, 0.0);
+        assert_pose(origin.exp(Twist2d::new(2.0, 2.0, 0.0)), 2.0, 2.0, 0.0);
+        assert_pose(
+            origin.exp(Twist2d::new(5.0 / 2.0 * PI, 0.0, FRAC_PI_2)),
+            5.0,
+            5.0,
+            90.0,
+        );
+    }
+
+    #[test]
+    fn pose_log() {
+        let start = Pose2d::default();
+        let end = Pose2d::new(5.0, 5.0, Rotation2d::from_degrees(90.0));
+
+        let twist = start.log(&end);
+
+        assert_close(twist.dx, 5.0 / 2.0 * PI);
+        assert_close(twist.dy, 0.0);
+        assert_close(twist.dtheta, FRAC_PI_2);
+    }
+
+    #[test]
+    fn pose_exp_log_round_trip() {
+        let start = Pose2d::new(1.0, -2.0, Rotation2d::from_degrees(30.0));
+
+        for end in [
+            Pose2d::new(-3.0, 4.0, Rotation2d::from_degrees(-120.0)),
+            Pose2d::new(2.0, -1.999, Rotation2d::from_degrees(30.01)),
+            Pose2d::new(1.0, -2.0, Rotation2d::from_degrees(210.0)),
+        ] {
+            assert_pose(
+                start.exp(start.log(&end)),
+                end.x(),
+                end.y(),
+                end.rotation.degrees(),
+            );
+        }
+    }
+
+    #[test]
+    fn pose_interpolate() {
+        let start = Pose2d::default();
+        let end = Pose2d::new(5.0, 5.0, Rotation2d::from_degrees(90.0));
+
+        assert_pose(start.interpolate(&end, 0.0), 0.0, 0.0, 0.0);
+        assert_pose(start.interpolate(&end, 1.0), 5.0, 5.0, 90.0);
+
+        // Halfway around the quarter circle
+        assert_pose(
+            start.interpolate(&end, 0.5),
+            5.0 / SQRT_2,
+            5.0 - 5.0 / SQRT_2,
+            45.0,
+        );
+    }
+}
@@ -0,0 +1,671 @@
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use nalgebra::{Matrix3, Quaternion, Unit, UnitQuaternion, Vector3};
+
+use crate::units::Angle;
+
+use super::{Pose2d, Rotation2d, Translation2d};
+
+/// A rotation in 3d space, stored as a quaternion
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Rotation3d {
+    quaternion: UnitQuaternion<f32>,
+}
+
+impl Rotation3d {
+    /// Create a rotation from extrinsic rotations around the x, y and z axes, applied in that
+    /// order. Positive rotations are counterclockwise when looking down the axis
+    pub fn new(roll: Angle, pitch: Angle, yaw: Angle) -> Self {
+        Self::from_quaternion(UnitQuaternion::from_euler_angles(
+            roll.radians(),
+            pitch.radians(),
+            yaw.radians(),
+        ))
+    }
+
+    pub fn from_quaternion(quaternion: UnitQuaternion<f32>) -> Self {
+        Self { quaternion }
+    }
+
+    /// Create a rotation from the components of a quaternion. The quaternion is normalized
+    pub fn from_wxyz(w: f32, x: f32, y: f32, z: f32) -> Self {
+        Self::from_quaternion(UnitQuaternion::from_quaternion(Quaternion::new(w, x, y, z)))
+    }
+
+    /// Create a rotation of `angle` counterclockwise around `axis`
+    pub fn from_axis_angle(axis: Vector3<f32>, angle: Angle) -> Self {
+        Self::from_quaternion(UnitQuaternion::from_axis_angle(
+            &Unit::new_normalize(axis),
+            angle.radians(),
+        ))
+    }
+
+    /// Create a rotation from a vector whose direction is the axis and whose length is the angle
+    /// in radians
+    pub fn from_rotation_vector(vector: Vector3<f32>) -> Self {
+        Self::from_quaternion(UnitQuaternion::from_scaled_axis(vector))
+    }
+
+    pub fn quaternion(&self) -> UnitQuaternion<f32> {
+        self.quaternion
+    }
+
+    /// Get the rotation as a vector whose direction is the axis and whose length is the angle in
+    /// radians
+    pub fn rotation_vector(&self) -> Vector3<f32> {
+        self.quaternion.scaled_axis()
+    }
+
+    /// Counterclockwise rotation around the x axis
+    pub fn roll(&self) -> Angle {
+        Angle::from_radians(self.quaternion.euler_angles().0)
+    }
+
+    /// Counterclockwise rotation around the y axis
+    pub fn pitch(&self) -> Angle {
+        Angle::from_radians(self.quaternion.euler_angles().1)
+    }
+
+    /// Counterclockwise rotation around the z axis
+    pub fn yaw(&self) -> Angle {
+        Angle::from_radians(self.quaternion.euler_angles().2)
+    }
+
+    /// Get the total angle rotated around the axis of rotation
+    pub fn angle(&self) -> Angle {
+        Angle::from_radians(self.quaternion.angle())
+    }
+
+    /// Apply `other` after this rotation
+    pub fn rotate_by(&self, other: &Rotation3d) -> Self {
+        Self::from_quaternion(other.quaternion * self.quaternion)
+    }
+
+    pub fn inverse(&self) -> Self {
+        Self::from_quaternion(self.quaternion.inverse())
+    }
+
+    /// Get the rotation around the z axis
+    pub fn to_rotation2d(&self) -> Rotation2d {
+        Rotation2d::new(self.yaw())
+    }
+
+    /// Interpolate between two rotations along the shortest path. `t` is clamped between 0 and 1
+    pub fn interpolate(&self, end: &Rotation3d, t: f32) -> Self {
+        let difference = self.inverse().rotate_by(end);
+
+        self.rotate_by(&(difference * t.clamp(0.0, 1.0)))
+    }
+}
+
+impl From<Rotation2d> for Rotation3d {
+    fn from(value: Rotation2d) -> Self {
+        Self::new(Angle::ZERO, Angle::ZERO, value.angle())
+    }
+}
+
+impl Add for Rotation3d {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        self.rotate_by(&rhs)
+    }
+}
+
+impl Sub for Rotation3d {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        self.rotate_by(&rhs.inverse())
+    }
+}
+
+impl Neg for Rotation3d {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        self.inverse()
+    }
+}
+
+/// Scale the angle of the rotation, keeping the same axis
+impl Mul<f32> for Rotation3d {
+    type Output = Self;
+
+    fn mul(self, rhs: f32) -> Self {
+        Self::from_rotation_vector(self.rotation_vector() * rhs)
+    }
+}
+
+/// A position in 3d space, in meters
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Translation3d {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+impl Translation3d {
+    pub const ZERO: Self = Self {
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+    };
+
+    pub fn new(x: f32, y: f32, z: f32) -> Self {
+        Self { x, y, z }
+    }
+
+    /// Get the distance from the origin
+    pub fn norm(&self) -> f32 {
+        self.vector().norm()
+    }
+
+    pub fn distance(&self, other: &Translation3d) -> f32 {
+        (*other - *self).norm()
+    }
+
+    /// Rotate the translation around the origin
+    pub fn rotate_by(&self, rotation: &Rotation3d) -> Self {
+        (rotation.quaternion() * self.vector()).into()
+    }
+
+    /// Drop the z component
+    pub fn to_translation2d(&self) -> Translation2d {
+        Translation2d::new(self.x, self.y)
+    }
+
+    /// Linearly interpolate between two translations. `t` is clamped between 0 and 1
+    pub fn interpolate(&self, end: &Translation3d, t: f32) -> Self {
+        *self + (*end - *self) * t.clamp(0.0, 1.0)
+    }
+
+    fn vector(&self) -> Vector3<f32> {
+        Vector3::new(self.x, self.y, self.z)
+    }
+}
+
+impl From<Translation2d> for Translation3d {
+    fn from(value: Translation2d) -> Self {
+        Self::new(value.x, value.y, 0.0)
+    }
+}
+
+impl From<Vector3<f32>> for Translation3d {
+    fn from(value: Vector3<f32>) -> Self {
+        Self::new(value.x, value.y, value.z)
+    }
+}
+
+impl From<Translation3d> for Vector3<f32> {
+    fn from(value: Translation3d) -> Self {
+        value.vector()
+    }
+}
+
+impl Add for Translation3d {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z)
+    }
+}
+
+impl Sub for Translation3d {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
+    }
+}
+
+impl Neg for Translation3d {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.x, -self.y, -self.z)
+    }
+}
+
+impl Mul<f32> for Translation3d {
+    type Output = Self;
+
+    fn mul(self, rhs: f32) -> Self {
+        Self::new(self.x * rhs, self.y * rhs, self.z * rhs)
+    }
+}
+
+impl Div<f32> for Translation3d {
+    type Output = Self;
+
+    fn div(self, rhs: f32) -> Self {
+        Self::new(self.x / rhs, self.y / rhs, self.z / rhs)
+    }
+}
+
+/// A change in position and rotation, relative to the starting pose. For example, the position
+/// of a camera relative to the center of the robot
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Transform3d {
+    pub translation: Translation3d,
+    pub rotation: Rotation3d,
+}
+
+impl Transform3d {
+    pub fn new(translation: Translation3d, rotation: Rotation3d) -> Self {
+        Self {
+            translation,
+            rotation,
+        }
+    }
+
+    /// Get the transform that maps `initial` to `last`
+    pub fn between(initial: &Pose3d, last: &Pose3d) -> Self {
+        Self {
+            translation: (last.translation - initial.translation)
+                .rotate_by(&initial.rotation.inverse()),
+            rotation: last.rotation - initial.rotation,
+        }
+    }
+
+    /// Get the transform that undoes this transform
+    pub fn inverse(&self) -> Self {
+        Self {
+            translation: (-self.translation).rotate_by(&self.rotation.inverse()),
+            rotation: self.rotation.inverse(),
+        }
+    }
+}
+
+/// Apply `rhs` after this transform
+impl Add for Transform3d {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::between(
+            &Pose3d::default(),
+            &Pose3d::default().transform_by(&self).transform_by(&rhs),
+        )
+    }
+}
+
+/// A movement along a helix, relative to the starting pose
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Twist3d {
+    pub dx: f32,
+    pub dy: f32,
+    pub dz: f32,
+    /// Rotation around the x axis in radians
+    pub rx: f32,
+    /// Rotation around the y axis in radians
+    pub ry: f32,
+    /// Rotation around the z axis in radians
+    pub rz: f32,
+}
+
+impl Twist3d {
+    pub fn new(dx: f32, dy: f32, dz: f32, rx: f32, ry: f32, rz: f32) -> Self {
+        Self {
+            dx,
+            dy,
+            dz,
+            rx,
+            ry,
+            rz,
+        }
+    }
+}
+
+impl Mul<f32> for Twist3d {
+    type Output = Self;
+
+    fn mul(self, rhs: f32) -> Self {
+        Self::new(
+            self.dx * rhs,
+            self.dy * rhs,
+            self.dz * rhs,
+            self.rx * rhs,
+            self.ry * rhs,
+            self.rz * rhs,
+        )
+    }
+}
+
+/// A position and orientation in 3d space
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Pose3d {
+    pub translation: Translation3d,
+    pub rotation: Rotation3d,
+}
+
+impl Pose3d {
+    pub fn new(translation: Translation3d, rotation: Rotation3d) -> Self {
+        Self {
+            translation,
+            rotation,
+        }
+    }
+
+    /// Apply a transform relative to this pose
+    pub fn transform_by(&self, transform: &Transform3d) -> Self {
+        Self {
+            translation: self.translation + transform.translation.rotate_by(&self.rotation),
+            rotation: transform.rotation.rotate_by(&self.rotation),
+        }
+    }
+
+    /// Get this pose in the coordinate frame of `other`
+    pub fn relative_to(&self, other: &Pose3d) -> Self {
+        let transform = Transform3d::between(other, self);
+
+        Self::new(transform.translation, transform.rotation)
+    }
+
+    /// Project the pose onto the floor, keeping only the rotation around the z axis
+    pub fn to_pose2d(&self) -> Pose2d {
+        Pose2d::from_parts(
+            self.translation.to_translation2d(),
+            self.rotation.to_rotation2d(),
+        )
+    }
+
+    /// Get the pose reached by following `twist`, starting at this pose
+    pub fn exp(&self, twist: Twist3d) -> Self {
+        let u = Vector3::new(twist.dx, twist.dy, twist.dz);
+        let rvec = Vector3::new(twist.rx, twist.ry, twist.rz);
+        let omega = rvec.cross_matrix();
+        let theta = rvec.norm();
+        let theta_sq = theta * theta;
+
+        let (b, c) = if theta > 1e-3 {
+            (
+                (1.0 - theta.cos()) / theta_sq,
+                (1.0 - theta.sin() / theta) / theta_sq,
+            )
+        } else {
+            (0.5 - theta_sq / 24.0, 1.0 / 6.0 - theta_sq / 120.0)
+        };
+
+        let v = Matrix3::identity() + omega * b + omega * omega * c;
+
+        self.transform_by(&Transform3d::new(
+            (v * u).into(),
+            Rotation3d::from_rotation_vector(rvec),
+        ))
+    }
+
+    /// Get the twist that moves this pose to `end`. This is the inverse of [Pose3d::exp]
+    pub fn log(&self, end: &Pose3d) -> Twist3d {
+        let transform = end.relative_to(self);
+
+        let rvec = transform.rotation.rotation_vector();
+        let omega = rvec.cross_matrix();
+        let theta = rvec.norm();
+        let theta_sq = theta * theta;
+
+        let c = if theta > 1e-3 {
+            let a = theta.sin() / theta;
+            let b = (1.0 - theta.cos()) / theta_sq;
+
+            (1.0 - a / (2.0 * b)) / theta_sq
+        } else {
+            1.0 / 12.0 + theta_sq / 720.0
+        };
+
+        let v_inv = Matrix3::identity() - omega * 0.5 + omega * omega * c;
+        let u = v_inv * Vector3::from(transform.translation);
+
+        Twist3d::new(u.x, u.y, u.z, rvec.x, rvec.y, rvec.z)
+    }
+
+    /// Interpolate between two poses along the twist that connects them. `t` is clamped between
+    /// 0 and 1
+    pub fn interpolate(&self, end: &Pose3d, t: f32) -> Self {
+        if t <= 0.0 {
+            *self
+        } else if t >= 1.0 {
+            *end
+        } else {
+            self.exp(self.log(end) * t)
+        }
+    }
+}
+
+impl From<Pose2d> for Pose3d {
+    fn from(value: Pose2d) -> Self {
+        Self::new(value.translation.into(), value.rotation.into())
+    }
+}
+
+impl Add<Transform3d> for Pose3d {
+    type Output = Self;
+
+    fn add(self, rhs: Transform3d) -> Self {
+        self.transform_by(&rhs)
+    }
+}
+
+/// Get the transform from `rhs` to this pose
+impl Sub for Pose3d {
+    type Output = Transform3d;
+
+    fn sub(self, rhs: Self) -> Transform3d {
+        Transform3d::between(&rhs, &self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    use super::*;
+    use crate::geometry::Twist2d;
+
+    const EPSILON: f32 = 1e-4;
+
+    fn degrees(degrees: f32) -> Angle {
+        Angle::from_degrees(degrees)
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < EPSILON,
+            "{actual} is not close to {expected}"
+        );
+    }
+
+    fn assert_rotation(actual: Rotation3d, expected: Rotation3d) {
+        let actual = actual.quaternion().into_inner().coords;
+        let expected = expected.quaternion().into_inner().coords;
+
+        // q and -q are the same rotation
+        assert!(
+            (actual - expected).norm() < EPSILON || (actual + expected).norm() < EPSILON,
+            "{actual:?} is not close to {expected:?}"
+        );
+    }
+
+    fn assert_pose(actual: Pose3d, expected: Pose3d) {
+        assert_close(actual.translation.x, expected.translation.x);
+        assert_close(actual.translation.y, expected.translation.y);
+        assert_close(actual.translation.z, expected.translation.z);
+        assert_rotation(actual.rotation, expected.rotation);
+    }
+
+    #[test]
+    fn rotation_axis_angle_matches_euler() {
+        assert_rotation(
+            Rotation3d::from_axis_angle(Vector3::x(), degrees(60.0)),
+            Rotation3d::new(degrees(60.0), degrees(0.0), degrees(0.0)),
+        );
+        assert_rotation(
+            Rotation3d::from_axis_angle(Vector3::y(), degrees(60.0)),
+            Rotation3d::new(degrees(0.0), degrees(60.0), degrees(0.0)),
+        );
+        assert_rotation(
+            Rotation3d::from_axis_angle(Vector3::z(), degrees(60.0)),
+            Rotation3d::new(degrees(0.0), degrees(0.0), degrees(60.0)),
+        );
+        assert_rotation(
+            Rotation3d::from_rotation_vector(Vector3::z() * FRAC_PI_2),
+            Rotation3d::new(degrees(0.0), degrees(0.0), degrees(90.0)),
+        );
+    }
+
+    #[test]
+    fn rotation_euler_round_trip() {
+        let rotation = Rotation3d::new(degrees(10.0), degrees(-20.0), degrees(30.0));
+
+        assert_close(rotation.roll().degrees(), 10.0);
+        assert_close(rotation.pitch().degrees(), -20.0);
+        assert_close(rotation.yaw().degrees(), 30.0);
+        assert_close(rotation.to_rotation2d().degrees(), 30.0);
+    }
+
+    #[test]
+    fn rotation_rotate_by() {
+        let about_x = Rotation3d::from_axis_angle(Vector3::x(), degrees(90.0));
+
+        assert_rotation(Rotation3d::default().rotate_by(&about_x), about_x);
+
+        let yaw = Rotation3d::new(degrees(0.0), degrees(0.0), degrees(90.0));
+        let more_yaw = Rotation3d::new(degrees(0.0), degrees(0.0), degrees(30.0));
+
+        assert_rotation(
+            yaw.rotate_by(&more_yaw),
+            Rotation3d::new(degrees(0.0), degrees(0.0), degrees(120.0)),
+        );
+
+        // Rotating the x axis 90 degrees around z then 90 degrees around x points it along z
+        let rotated = Translation3d::new(1.0, 0.0, 0.0).rotate_by(&yaw.rotate_by(&about_x));
+
+        assert_close(rotated.x, 0.0);
+        assert_close(rotated.y, 0.0);
+        assert_close(rotated.z, 1.0);
+
+        assert_rotation(yaw.rotate_by(&yaw.inverse()), Rotation3d::default());
+    }
+
+    #[test]
+    fn rotation_interpolate() {
+        let start = Rotation3d::new(degrees(0.0), degrees(0.0), degrees(-160.0));
+        let end = Rotation3d::new(degrees(0.0), degrees(0.0), degrees(160.0));
+
+        assert_rotation(
+            start.interpolate(&end, 0.5),
+            Rotation3d::new(degrees(0.0), degrees(0.0), degrees(180.0)),
+        );
+
+        let start = Rotation3d::new(degrees(10.0), degrees(20.0), degrees(30.0));
+        let end = Rotation3d::new(degrees(-40.0), degrees(5.0), degrees(100.0));
+
+        assert_rotation(start.interpolate(&end, 0.0), start);
+        assert_rotation(start.interpolate(&end, 1.0), end);
+    }
+
+    #[test]
+    fn pose_transform_round_trip() {
+        let start = Pose3d::new(
+            Translation3d::new(1.0, 2.0, 3.0),
+            Rotation3d::new(degrees(10.0), degrees(20.0), degrees(30.0)),
+        );
+        let end = Pose3d::new(
+            Translation3d::new(-2.0, 0.5, 1.0),
+            Rotation3d::new(degrees(-40.0), degrees(5.0), degrees(100.0)),
+        );
+
+        assert_pose(start + (end - start), end);
+        assert_pose(end + (end - start).inverse(), start);
+
+        let relative = end.relative_to(&start);
+
+        assert_pose(
+            start.transform_by(&Transform3d::new(relative.translation, relative.rotation)),
+            end,
+        );
+    }
+
+    #[test]
+    fn pose_exp_quarter_circle() {
+        let end =
+            Pose3d::default().exp(Twist3d::new(5.0 / 2.0 * PI, 0.0, 0.0, 0.0, 0.0, FRAC_PI_2));
+
+        assert_pose(
+            end,
+            Pose3d::new(
+                Translation3d::new(5.0, 5.0, 0.0),
+                Rotation3d::new(degrees(0.0), degrees(0.0), degrees(90.0)),
+            ),
+        );
+    }
+
+    #[test]
+    fn pose_exp_matches_2d() {
+        let start = Pose2d::new(1.0, -2.0, Rotation2d::from_degrees(30.0));
+
+        for (dx, dy, dtheta) in [(1.0, 0.5, 0.7), (2.0, 0.0, 0.0), (-1.0, 3.0, -2.5)] {
+            let expected = start.exp(Twist2d::new(dx, dy, dtheta));
+            let actual = Pose3d::from(start).exp(Twist3d::new(dx, dy, 0.0, 0.0, 0.0, dtheta));
+
+            assert_pose(actual, expected.into());
+            assert_close(actual.to_pose2d().x(), expected.x());
+            assert_close(actual.to_pose2d().y(), expected.y());
+        }
+    }
+
+    #[test]
+    fn pose_log_pure_translation() {
+        let start = Pose3d::default();
+        let end = Pose3d::new(Translation3d::new(1.0, -2.0, 3.0), Rotation3d::default());
+
+        let twist = start.log(&end);
+
+        assert_close(twist.dx, 1.0);
+        assert_close(twist.dy, -2.0);
+        assert_close(twist.dz, 3.0);
+        assert_close(twist.rx, 0.0);
+        assert_close(twist.ry, 0.0);
+        assert_close(twist.rz, 0.0);
+    }
+
+    #[test]
+    fn pose_exp_log_round_trip() {
+        let start = Pose3d::new(
+            Translation3d::new(1.0, 2.0, 3.0),
+            Rotation3d::new(degrees(10.0), degrees(20.0), degrees(30.0)),
+        );
+
+        for end in [
+            Pose3d::new(
+                Translation3d::new(-2.0, 0.5, 1.0),
+                Rotation3d::new(degrees(-40.0), degrees(5.0), degrees(100.0)),
+            ),
+            Pose3d::new(
+                Translation3d::new(1.001, 2.0, 3.002),
+                Rotation3d::new(degrees(10.01), degrees(20.0), degrees(30.0)),
+            ),
+            Pose3d::new(
+                Translation3d::new(0.0, 0.0, 0.0),
+                Rotation3d::new(degrees(90.0), degrees(0.0), degrees(0.0)),
+            ),
+        ] {
+            assert_pose(start.exp(start.log(&end)), end);
+        }
+    }
+
+    #[test]
+    fn pose_interpolate() {
+        let start = Pose3d::default();
+        let end = Pose3d::new(
+            Translation3d::new(5.0, 5.0, 0.0),
+            Rotation3d::new(degrees(0.0), degrees(0.0), degrees(90.0)),
+        );
+
+        assert_pose(start.interpolate(&end, 0.0), start);
+        assert_pose(start.interpolate(&end, 1.0), end);
+        assert_pose(
+            start.interpolate(&end, 0.5),
+            Pose2d::default().interpolate(&end.to_pose2d(), 0.5).into(),
+        );
+    }
+}
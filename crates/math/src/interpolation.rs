@@ -0,0 +1,266 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use crate::{
+    geometry::{Pose2d, Pose3d, Rotation2d, Rotation3d, Translation2d, Translation3d},
+    kinematics::SwerveState,
+    State,
+};
+
+/// A value that can be blended with another value of the same type
+pub trait Interpolate {
+    /// Get the value `t` of the way from this value to `end`, where `t` is between 0 and 1
+    fn interpolate(&self, end: &Self, t: f32) -> Self;
+}
+
+impl Interpolate for f32 {
+    fn interpolate(&self, end: &Self, t: f32) -> Self {
+        self + (end - self) * t.clamp(0.0, 1.0)
+    }
+}
+
+impl Interpolate for State {
+    fn interpolate(&self, end: &Self, t: f32) -> Self {
+        Self {
+            position: self.position.interpolate(&end.position, t),
+            velocity: self.velocity.interpolate(&end.velocity, t),
+        }
+    }
+}
+
+/// The angle is interpolated along the shortest path
+impl Interpolate for SwerveState {
+    fn interpolate(&self, end: &Self, t: f32) -> Self {
+        Self {
+            drive: self.drive.interpolate(&end.drive, t),
+            angle: Rotation2d::new(self.angle)
+                .interpolate(&Rotation2d::new(end.angle), t)
+                .angle(),
+        }
+    }
+}
+
+impl<T: Interpolate, const N: usize> Interpolate for [T; N] {
+    fn interpolate(&self, end: &Self, t: f32) -> Self {
+        std::array::from_fn(|i| self[i].interpolate(&end[i], t))
+    }
+}
+
+macro_rules! impl_interpolate {
+    ($($ty:ty),+) => {
+        $(
+            impl Interpolate for $ty {
+                fn interpolate(&self, end: &Self, t: f32) -> Self {
+                    <$ty>::interpolate(self, end, t)
+                }
+            }
+        )+
+    };
+}
+
+impl_interpolate!(
+    Rotation2d,
+    Translation2d,
+    Pose2d,
+    Rotation3d,
+    Translation3d,
+    Pose3d
+);
+
+/// A history of values over time that can be sampled at any point between the samples by
+/// interpolating between the samples on either side. Samples older than the history window are
+/// removed when new samples are added.
+///
+/// # Example
+///
+/// ```rust
+/// # use math::interpolation::TimeInterpolatableBuffer;
+/// # use std::time::Duration;
+/// let mut buffer = TimeInterpolatableBuffer::new(Duration::from_millis(1500));
+///
+/// buffer.add_sample(Duration::from_secs(1), 1.0);
+/// buffer.add_sample(Duration::from_secs(2), 3.0);
+///
+/// assert_eq!(buffer.sample(Duration::from_millis(1500)), Some(2.0));
+/// ```
+#[derive(Debug, Clone)]
+pub struct TimeInterpolatableBuffer<T> {
+    history: Duration,
+    samples: BTreeMap<Duration, T>,
+}
+
+impl<T: Interpolate + Clone> TimeInterpolatableBuffer<T> {
+    pub fn new(history: Duration) -> Self {
+        Self {
+            history,
+            samples: BTreeMap::new(),
+        }
+    }
+
+    /// Add a sample and remove samples that are older than the history window
+    pub fn add_sample(&mut self, time: Duration, value: T) {
+        self.samples.insert(time, value);
+
+        if let Some(oldest) = self
+            .newest_time()
+            .and_then(|newest| newest.checked_sub(self.history))
+        {
+            self.samples = self.samples.split_off(&oldest);
+        }
+    }
+
+    /// Get the value at `time`. Times before the first sample or after the last sample give the
+    /// first or last sample. Returns [None] if the buffer is empty
+    pub fn sample(&self, time: Duration) -> Option<T> {
+        let before = self.samples.range(..=time).next_back();
+        let after = self.samples.range(time..).next();
+
+        match (before, after) {
+            (Some((before_time, before)), Some((after_time, after))) => {
+                if before_time == after_time {
+                    Some(before.clone())
+                } else {
+                    let t = (time - *before_time).as_secs_f32()
+                        / (*after_time - *before_time).as_secs_f32();
+
+                    Some(before.interpolate(after, t))
+                }
+            }
+            (Some((_, value)), None) | (None, Some((_, value))) => Some(value.clone()),
+            (None, None) => None,
+        }
+    }
+
+    /// Get the time and value of the newest sample
+    pub fn newest(&self) -> Option<(Duration, &T)> {
+        self.samples
+            .last_key_value()
+            .map(|(time, value)| (*time, value))
+    }
+
+    /// Get the time of the oldest sample that is still within the history window
+    pub fn oldest_time(&self) -> Option<Duration> {
+        self.samples.first_key_value().map(|(time, _)| *time)
+    }
+
+    pub fn history(&self) -> Duration {
+        self.history
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    fn newest_time(&self) -> Option<Duration> {
+        self.samples.last_key_value().map(|(time, _)| *time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-4;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < EPSILON,
+            "{actual} != {expected}"
+        );
+    }
+
+    fn buffer() -> TimeInterpolatableBuffer<f32> {
+        let mut buffer = TimeInterpolatableBuffer::new(Duration::from_secs(1));
+
+        buffer.add_sample(Duration::from_millis(1000), 0.0);
+        buffer.add_sample(Duration::from_millis(1200), 2.0);
+        buffer.add_sample(Duration::from_millis(1400), 6.0);
+
+        buffer
+    }
+
+    #[test]
+    fn empty() {
+        let buffer = TimeInterpolatableBuffer::<f32>::new(Duration::from_secs(1));
+
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.sample(Duration::ZERO), None);
+        assert_eq!(buffer.newest(), None);
+        assert_eq!(buffer.oldest_time(), None);
+    }
+
+    #[test]
+    fn samples_between_points() {
+        let buffer = buffer();
+
+        assert_close(buffer.sample(Duration::from_millis(1100)).unwrap(), 1.0);
+        assert_close(buffer.sample(Duration::from_millis(1250)).unwrap(), 3.0);
+        assert_close(buffer.sample(Duration::from_millis(1200)).unwrap(), 2.0);
+    }
+
+    #[test]
+    fn clamps_outside_samples() {
+        let buffer = buffer();
+
+        assert_eq!(buffer.sample(Duration::ZERO), Some(0.0));
+        assert_eq!(buffer.sample(Duration::from_secs(5)), Some(6.0));
+    }
+
+    #[test]
+    fn removes_old_samples() {
+        let mut buffer = buffer();
+
+        // Samples exactly at the edge of the window are kept
+        buffer.add_sample(Duration::from_millis(2000), 8.0);
+
+        assert_eq!(buffer.len(), 4);
+        assert_eq!(buffer.oldest_time(), Some(Duration::from_millis(1000)));
+
+        buffer.add_sample(Duration::from_millis(2300), 10.0);
+
+        assert_eq!(buffer.len(), 3);
+        assert_eq!(buffer.oldest_time(), Some(Duration::from_millis(1400)));
+        assert_eq!(buffer.newest(), Some((Duration::from_millis(2300), &10.0)));
+        assert_eq!(buffer.sample(Duration::from_millis(1000)), Some(6.0));
+    }
+
+    #[test]
+    fn replaces_samples_at_same_time() {
+        let mut buffer = buffer();
+
+        buffer.add_sample(Duration::from_millis(1200), 4.0);
+
+        assert_eq!(buffer.len(), 3);
+        assert_close(buffer.sample(Duration::from_millis(1100)).unwrap(), 2.0);
+    }
+
+    #[test]
+    fn clear() {
+        let mut buffer = buffer();
+
+        buffer.clear();
+
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.sample(Duration::from_millis(1200)), None);
+    }
+
+    #[test]
+    fn interpolates_rotation_along_shortest_path() {
+        let mut buffer = TimeInterpolatableBuffer::new(Duration::from_secs(1));
+
+        buffer.add_sample(Duration::ZERO, Rotation2d::from_degrees(170.0));
+        buffer.add_sample(Duration::from_millis(100), Rotation2d::from_degrees(-170.0));
+
+        assert_eq!(
+            buffer.sample(Duration::from_millis(50)),
+            Some(Rotation2d::from_degrees(180.0))
+        );
+    }
+}
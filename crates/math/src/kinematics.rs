@@ -1,14 +1,20 @@
-use std::f32::consts::PI;
+use std::{array, f32::consts::PI, ops::Sub, time::Duration};
 
-use nalgebra::{matrix, Dim, SMatrix, Vector2, Vector3, VectorView2, U1, U2};
+use nalgebra::{Dim, Dyn, OMatrix, SMatrix, Vector3, Vector4, VectorView2, U3};
 
-use crate::normalize_angle;
+use crate::{
+    geometry::{Pose2d, Rotation2d, Translation2d},
+    units::{Angle, AngularVelocity, Length, LinearVelocity},
+};
 
 /// A trait that takes in a the requested robot speeds and returns the state the drivetrain
 /// should be in to achieve those speeds.
 ///
 /// The vector is \[x, y, rotation\] in the WPILib robot coordinate system
-/// ([Link to WPILib docs](https://docs.wpilib.org/en/stable/docs/software/basic-programming/coordinate-system.html#wpilib-coordinate-system)).
+/// ([Link to WPILib docs](https://docs.wpilib.org/en/stable/docs/software/basic-programming/coordinate-system.html#wpilib-coordinate-system)),
+/// in meters per second and radians per second. Odometry and the pose estimator do matrix math on
+/// the vector directly, so use [Kinematics::inverse_speeds] and [Kinematics::forward_speeds] to
+/// work with typed [ChassisSpeeds] instead.
 pub trait Kinematics {
     type State;
 
@@ -16,14 +22,107 @@ pub trait Kinematics {
     fn inverse(&self, robot_speeds: Vector3<f32>) -> Self::State;
     /// Convert from drive train state to robot speeds
     fn forward(&self, state: Self::State) -> Vector3<f32>;
+
+    /// Convert from robot speeds to drive train state
+    fn inverse_speeds(&self, robot_speeds: ChassisSpeeds) -> Self::State {
+        self.inverse(robot_speeds.into())
+    }
+
+    /// Convert from drive train state to robot speeds
+    fn forward_speeds(&self, state: Self::State) -> ChassisSpeeds {
+        self.forward(state).into()
+    }
+}
+
+/// The speed of the robot in the WPILib robot coordinate system. This is the typed version of the
+/// \[x, y, rotation\] vector used by [Kinematics]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ChassisSpeeds {
+    /// Forward speed
+    pub vx: LinearVelocity,
+    /// Speed to the left
+    pub vy: LinearVelocity,
+    /// Counterclockwise rotational speed
+    pub omega: AngularVelocity,
 }
 
-/// An implementation of the `Kinematics` trait for a swerve drivetrain.
+impl ChassisSpeeds {
+    pub fn new(vx: LinearVelocity, vy: LinearVelocity, omega: AngularVelocity) -> Self {
+        Self { vx, vy, omega }
+    }
+
+    /// Convert speeds relative to the field into speeds relative to the robot
+    pub fn from_field_relative(field_speeds: ChassisSpeeds, robot_angle: Rotation2d) -> Self {
+        let velocity = Translation2d::new(
+            field_speeds.vx.meters_per_second(),
+            field_speeds.vy.meters_per_second(),
+        )
+        .rotate_by(&robot_angle.inverse());
+
+        Self {
+            vx: LinearVelocity::from_meters_per_second(velocity.x),
+            vy: LinearVelocity::from_meters_per_second(velocity.y),
+            omega: field_speeds.omega,
+        }
+    }
+
+    /// Correct the speeds for the robot moving along an arc instead of a straight line when it
+    /// translates and rotates at the same time. Without this the robot drifts sideways in the
+    /// direction it is rotating. `dt` is the time until the speeds are next updated
+    pub fn discretize(&self, dt: Duration) -> Self {
+        let dt = dt.as_secs_f32();
+
+        let desired = Pose2d::new(
+            self.vx.meters_per_second() * dt,
+            self.vy.meters_per_second() * dt,
+            Rotation2d::from_radians(self.omega.radians_per_second() * dt),
+        );
+
+        let twist = Pose2d::default().log(&desired);
+
+        Self {
+            vx: LinearVelocity::from_meters_per_second(twist.dx / dt),
+            vy: LinearVelocity::from_meters_per_second(twist.dy / dt),
+            omega: AngularVelocity::from_radians_per_second(twist.dtheta / dt),
+        }
+    }
+
+    /// Convert speeds relative to the robot into speeds relative to the field
+    pub fn to_field_relative(&self, robot_angle: Rotation2d) -> Self {
+        Self::from_field_relative(*self, robot_angle.inverse())
+    }
+}
+
+impl From<ChassisSpeeds> for Vector3<f32> {
+    fn from(value: ChassisSpeeds) -> Self {
+        Vector3::new(
+            value.vx.meters_per_second(),
+            value.vy.meters_per_second(),
+            value.omega.radians_per_second(),
+        )
+    }
+}
+
+impl From<Vector3<f32>> for ChassisSpeeds {
+    fn from(value: Vector3<f32>) -> Self {
+        Self {
+            vx: LinearVelocity::from_meters_per_second(value.x),
+            vy: LinearVelocity::from_meters_per_second(value.y),
+            omega: AngularVelocity::from_radians_per_second(value.z),
+        }
+    }
+}
+
+/// An implementation of the `Kinematics` trait for a swerve drivetrain with `N` modules. The
+/// matrices are allocated once when this is created, and [Kinematics::inverse] and
+/// [Kinematics::forward] work on them in place, so they don't allocate.
 #[derive(Clone)]
-pub struct SwerveKinematics {
-    pub inverse_matrix: SMatrix<f32, 8, 3>,
-    pub forward_matrix: SMatrix<f32, 3, 8>,
-    pub positions: [Vector2<f32>; 4],
+pub struct SwerveKinematics<const N: usize = 4> {
+    /// A 2N x 3 matrix
+    pub inverse_matrix: OMatrix<f32, Dyn, U3>,
+    /// A 3 x 2N matrix
+    pub forward_matrix: OMatrix<f32, U3, Dyn>,
+    pub positions: [Translation2d; N],
 }
 
 /// Calculates the positions of the modules on the robot given the track width and wheel base.
@@ -34,35 +133,32 @@ pub struct SwerveKinematics {
 /// 2: Front Right
 /// 3: Back Left
 /// 4: Back Right
-pub fn module_positions_from_dimensions(track_width: f32, wheel_base: f32) -> [Vector2<f32>; 4] {
+pub fn module_positions_from_dimensions(track_width: f32, wheel_base: f32) -> [Translation2d; 4] {
     let half_track_width = track_width / 2.0;
     let half_wheel_base = wheel_base / 2.0;
 
     [
-        Vector2::new(half_wheel_base, half_track_width),
-        Vector2::new(half_wheel_base, -half_track_width),
-        Vector2::new(-half_wheel_base, half_track_width),
-        Vector2::new(-half_wheel_base, -half_track_width),
+        Translation2d::new(half_wheel_base, half_track_width),
+        Translation2d::new(half_wheel_base, -half_track_width),
+        Translation2d::new(-half_wheel_base, half_track_width),
+        Translation2d::new(-half_wheel_base, -half_track_width),
     ]
 }
 
-impl SwerveKinematics {
+impl<const N: usize> SwerveKinematics<N> {
     /// Creates a new `SwerveKinematics` struct with the given module positions.
     ///
     /// See [module_positions_from_dimensions] for an easy way to get these positions.
-    pub fn new(positions: [Vector2<f32>; 4]) -> Self {
-        let inverse_matrix = nalgebra::matrix![
-            1.0, 0.0, -positions[0].y;
-            0.0, 1.0, positions[0].x;
-            1.0, 0.0, -positions[1].y;
-            0.0, 1.0, positions[1].x;
-            1.0, 0.0, -positions[2].y;
-            0.0, 1.0, positions[2].x;
-            1.0, 0.0, -positions[3].y;
-            0.0, 1.0, positions[3].x
-        ];
+    pub fn new(positions: [Translation2d; N]) -> Self {
+        let inverse_matrix = OMatrix::<f32, Dyn, U3>::from_row_iterator(
+            2 * N,
+            positions
+                .iter()
+                .flat_map(|position| [1.0, 0.0, -position.y, 0.0, 1.0, position.x]),
+        );
 
         let forward_matrix = inverse_matrix
+            .clone()
             .pseudo_inverse(f32::EPSILON)
             .expect("Could not calculate swerve forward kinematics matrix");
 
@@ -74,28 +170,18 @@ impl SwerveKinematics {
     }
 
     /// ALign the wheels in an X to prevent the robot from moving.
-    pub fn brake(&self) -> [SwerveState; 4] {
-        let mut states = self.positions.iter().map(|position| {
-            let mut state = SwerveState::from(position.as_view::<_, _, U1, U2>());
-            state.stop();
-            state
-        });
-
-        [
-            states.next().unwrap(),
-            states.next().unwrap(),
-            states.next().unwrap(),
-            states.next().unwrap(),
-        ]
+    pub fn brake(&self) -> [SwerveState; N] {
+        self.positions
+            .map(|position| SwerveState::new(position.angle().angle(), 0.0))
     }
 
-    /// Rescale the speeds of each module to not be higher than the maximum
-    pub fn scale(mut states: [SwerveState; 4], max_speed: f32) -> [SwerveState; 4] {
+    /// Rescale the speeds of each module so that none of them are faster than the maximum in
+    /// either direction, keeping the ratio between them
+    pub fn desaturate(mut states: [SwerveState; N], max_speed: f32) -> [SwerveState; N] {
         let max = states
             .iter()
-            .map(|state| state.drive)
-            .max_by(|a, b| a.partial_cmp(b).unwrap())
-            .unwrap();
+            .map(|state| state.drive.abs())
+            .fold(0.0, f32::max);
 
         if max > max_speed {
             let scale = max_speed / max;
@@ -105,62 +191,391 @@ impl SwerveKinematics {
 
         states
     }
+
+    #[deprecated(note = "Use `SwerveKinematics::desaturate` instead")]
+    pub fn scale(states: [SwerveState; N], max_speed: f32) -> [SwerveState; N] {
+        Self::desaturate(states, max_speed)
+    }
+
+    /// Rescale the speeds of each module so the robot does not go faster than it can actually
+    /// translate or rotate. `robot_speeds` are the speeds the states were created from. Scaling
+    /// only by the module speed lets the robot rotate faster than it can while translating, which
+    /// makes it drift.
+    ///
+    /// The speeds are scaled down by how far over its limit the translation or rotation is,
+    /// whichever is larger. The module speeds are never scaled up, and no module is left faster
+    /// than `max_module_speed`.
+    pub fn desaturate_with_chassis(
+        mut states: [SwerveState; N],
+        robot_speeds: ChassisSpeeds,
+        max_module_speed: LinearVelocity,
+        max_translational_speed: LinearVelocity,
+        max_rotational_speed: AngularVelocity,
+    ) -> [SwerveState; N] {
+        let max = states
+            .iter()
+            .map(|state| state.drive.abs())
+            .fold(0.0, f32::max);
+
+        if max_translational_speed == LinearVelocity::ZERO
+            || max_rotational_speed == AngularVelocity::ZERO
+            || max == 0.0
+        {
+            return states;
+        }
+
+        let translational_k = robot_speeds
+            .vx
+            .meters_per_second()
+            .hypot(robot_speeds.vy.meters_per_second())
+            / max_translational_speed.meters_per_second();
+        let rotational_k = robot_speeds.omega.abs() / max_rotational_speed;
+
+        // Requests past the limits are treated as at the limit so the modules still end up within
+        // their maximum speed
+        let k = translational_k.max(rotational_k).min(1.0);
+
+        let scale = (k * max_module_speed.meters_per_second() / max).min(1.0);
+
+        states.iter_mut().for_each(|val| val.drive *= scale);
+
+        states
+    }
+
+    /// Convert from robot speeds and accelerations to the state of each module along with how
+    /// fast the modules need to accelerate and turn. Using the module angular velocity as a
+    /// feedforward for the turning motors keeps the modules from lagging behind while the robot
+    /// rotates.
+    ///
+    /// Both vectors are \[x, y, rotation\] like in [Kinematics], and the accelerations are the
+    /// rate of change of the robot relative speeds.
+    pub fn inverse_second_order(
+        &self,
+        robot_speeds: Vector3<f32>,
+        robot_accelerations: Vector3<f32>,
+    ) -> [SwerveSecondOrderState; N] {
+        let omega = robot_speeds.z;
+        let alpha = robot_accelerations.z;
+
+        self.positions.map(|position| {
+            let vx = robot_speeds.x - omega * position.y;
+            let vy = robot_speeds.y + omega * position.x;
+
+            let ax = robot_accelerations.x - alpha * position.y;
+            let ay = robot_accelerations.y + alpha * position.x;
+
+            let speed = vx.hypot(vy);
+
+            let (acceleration, angular_velocity) = if speed > f32::EPSILON {
+                (
+                    (vx * ax + vy * ay) / speed,
+                    (vx * ay - vy * ax) / speed.powi(2),
+                )
+            } else {
+                (0.0, 0.0)
+            };
+
+            SwerveSecondOrderState {
+                state: SwerveState::new(Angle::atan2(vy, vx), speed),
+                acceleration,
+                angular_velocity: AngularVelocity::from_radians_per_second(angular_velocity),
+            }
+        })
+    }
 }
 
-impl Kinematics for SwerveKinematics {
-    type State = [SwerveState; 4];
+impl<const N: usize> Kinematics for SwerveKinematics<N> {
+    type State = [SwerveState; N];
+
+    fn inverse(&self, robot_speeds: Vector3<f32>) -> Self::State {
+        // Each module only depends on its two rows of the matrix
+        array::from_fn(|i| {
+            let output = self.inverse_matrix.fixed_rows::<2>(2 * i) * robot_speeds;
+
+            output.fixed_rows::<2>(0).into()
+        })
+    }
+
+    fn forward(&self, state: Self::State) -> Vector3<f32> {
+        // Multiply one module at a time instead of collecting the modules into a vector
+        state
+            .iter()
+            .enumerate()
+            .fold(Vector3::zeros(), |speeds, (i, state)| {
+                speeds
+                    + self.forward_matrix.column(2 * i) * (state.drive * state.angle.cos())
+                    + self.forward_matrix.column(2 * i + 1) * (state.drive * state.angle.sin())
+            })
+    }
+}
+
+/// The state of a swerve module along with how fast it is changing. See
+/// [SwerveKinematics::inverse_second_order]
+#[derive(Debug, Clone, Copy)]
+pub struct SwerveSecondOrderState {
+    pub state: SwerveState,
+    /// The acceleration of the drive wheel in m/s^2
+    pub acceleration: f32,
+    /// How fast the module needs to turn
+    pub angular_velocity: AngularVelocity,
+}
+
+/// An implementation of the `Kinematics` trait for a differential (tank) drivetrain.
+#[derive(Debug, Clone, Copy)]
+pub struct DifferentialDriveKinematics {
+    /// The distance between the left and right wheels in meters
+    pub track_width: f32,
+}
+
+impl DifferentialDriveKinematics {
+    pub fn new(track_width: f32) -> Self {
+        Self { track_width }
+    }
+
+    /// Rescale the speeds of each side to not be higher than the maximum while keeping the ratio
+    /// between them
+    pub fn desaturate(state: DifferentialState, max_speed: f32) -> DifferentialState {
+        let max = state.left.abs().max(state.right.abs());
+
+        if max > max_speed {
+            let scale = max_speed / max;
+
+            DifferentialState::new(state.left * scale, state.right * scale)
+        } else {
+            state
+        }
+    }
+}
+
+impl Kinematics for DifferentialDriveKinematics {
+    type State = DifferentialState;
+
+    /// The sideways speed is ignored because a differential drivetrain can not strafe
+    fn inverse(&self, robot_speeds: Vector3<f32>) -> Self::State {
+        let rotation_speed = self.track_width / 2.0 * robot_speeds.z;
+
+        DifferentialState::new(
+            robot_speeds.x - rotation_speed,
+            robot_speeds.x + rotation_speed,
+        )
+    }
+
+    fn forward(&self, state: Self::State) -> Vector3<f32> {
+        Vector3::new(
+            (state.left + state.right) / 2.0,
+            0.0,
+            (state.right - state.left) / self.track_width,
+        )
+    }
+}
+
+/// The state of both sides of a differential drivetrain.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DifferentialState {
+    /// The speed or displacement of the left side in m/s or m
+    pub left: f32,
+    /// The speed or displacement of the right side in m/s or m
+    pub right: f32,
+}
+
+impl DifferentialState {
+    pub fn new(left: f32, right: f32) -> Self {
+        Self { left, right }
+    }
+
+    /// Create the state of a drivetrain moving at the given speeds
+    pub fn from_speeds(left: LinearVelocity, right: LinearVelocity) -> Self {
+        Self::new(left.meters_per_second(), right.meters_per_second())
+    }
+
+    /// Create the state of a drivetrain that has driven the given distances
+    pub fn from_distances(left: Length, right: Length) -> Self {
+        Self::new(left.meters(), right.meters())
+    }
+
+    /// Get the left value as a speed
+    pub fn left_speed(&self) -> LinearVelocity {
+        LinearVelocity::from_meters_per_second(self.left)
+    }
+
+    /// Get the right value as a speed
+    pub fn right_speed(&self) -> LinearVelocity {
+        LinearVelocity::from_meters_per_second(self.right)
+    }
+
+    /// Get the left value as a distance
+    pub fn left_distance(&self) -> Length {
+        Length::from_meters(self.left)
+    }
+
+    /// Get the right value as a distance
+    pub fn right_distance(&self) -> Length {
+        Length::from_meters(self.right)
+    }
+}
+
+impl Sub for DifferentialState {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.left - rhs.left, self.right - rhs.right)
+    }
+}
+
+/// An implementation of the `Kinematics` trait for a mecanum drivetrain.
+#[derive(Clone)]
+pub struct MecanumKinematics {
+    pub inverse_matrix: SMatrix<f32, 4, 3>,
+    pub forward_matrix: SMatrix<f32, 3, 4>,
+    pub positions: [Translation2d; 4],
+}
+
+impl MecanumKinematics {
+    /// Creates a new `MecanumKinematics` struct with the given wheel positions in the same order as
+    /// [module_positions_from_dimensions], which is an easy way to get these positions.
+    pub fn new(positions: [Translation2d; 4]) -> Self {
+        let [front_left, front_right, rear_left, rear_right] = positions;
+
+        let inverse_matrix = nalgebra::matrix![
+            1.0, -1.0, -(front_left.x + front_left.y);
+            1.0, 1.0, front_right.x - front_right.y;
+            1.0, 1.0, rear_left.x - rear_left.y;
+            1.0, -1.0, -(rear_right.x + rear_right.y)
+        ];
+
+        let forward_matrix = inverse_matrix
+            .pseudo_inverse(f32::EPSILON)
+            .expect("Could not calculate mecanum forward kinematics matrix");
+
+        Self {
+            inverse_matrix,
+            forward_matrix,
+            positions,
+        }
+    }
+
+    /// Rescale the speeds of each wheel to not be higher than the maximum while keeping the ratio
+    /// between them
+    pub fn desaturate(state: MecanumState, max_speed: f32) -> MecanumState {
+        let max = state
+            .to_array()
+            .into_iter()
+            .map(f32::abs)
+            .fold(0.0, f32::max);
+
+        if max > max_speed {
+            let scale = max_speed / max;
+
+            MecanumState::from_array(state.to_array().map(|wheel| wheel * scale))
+        } else {
+            state
+        }
+    }
+}
+
+impl Kinematics for MecanumKinematics {
+    type State = MecanumState;
 
     fn inverse(&self, robot_speeds: Vector3<f32>) -> Self::State {
         let output = self.inverse_matrix * robot_speeds;
 
+        MecanumState::new(output[0], output[1], output[2], output[3])
+    }
+
+    fn forward(&self, state: Self::State) -> Vector3<f32> {
+        self.forward_matrix * Vector4::from(state.to_array())
+    }
+}
+
+/// The state of each wheel of a mecanum drivetrain.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MecanumState {
+    /// The speed or displacement of the front left wheel in m/s or m
+    pub front_left: f32,
+    /// The speed or displacement of the front right wheel in m/s or m
+    pub front_right: f32,
+    /// The speed or displacement of the rear left wheel in m/s or m
+    pub rear_left: f32,
+    /// The speed or displacement of the rear right wheel in m/s or m
+    pub rear_right: f32,
+}
+
+impl MecanumState {
+    pub fn new(front_left: f32, front_right: f32, rear_left: f32, rear_right: f32) -> Self {
+        Self {
+            front_left,
+            front_right,
+            rear_left,
+            rear_right,
+        }
+    }
+
+    /// Create the state from the wheels in the order front left, front right, rear left and rear
+    /// right
+    pub fn from_array([front_left, front_right, rear_left, rear_right]: [f32; 4]) -> Self {
+        Self::new(front_left, front_right, rear_left, rear_right)
+    }
+
+    /// Get the wheels in the order front left, front right, rear left and rear right
+    pub fn to_array(&self) -> [f32; 4] {
         [
-            output.fixed_rows::<2>(0).into(),
-            output.fixed_rows::<2>(2).into(),
-            output.fixed_rows::<2>(4).into(),
-            output.fixed_rows::<2>(6).into(),
+            self.front_left,
+            self.front_right,
+            self.rear_left,
+            self.rear_right,
         ]
     }
+}
 
-    fn forward(&self, state: Self::State) -> Vector3<f32> {
-        let input = matrix![
-            state[0].drive * state[0].angle.cos();
-            state[0].drive * state[0].angle.sin();
-            state[1].drive * state[1].angle.cos();
-            state[1].drive * state[1].angle.sin();
-            state[2].drive * state[2].angle.cos();
-            state[2].drive * state[2].angle.sin();
-            state[3].drive * state[3].angle.cos();
-            state[3].drive * state[3].angle.sin()
-        ];
+impl Sub for MecanumState {
+    type Output = Self;
 
-        self.forward_matrix * input
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(
+            self.front_left - rhs.front_left,
+            self.front_right - rhs.front_right,
+            self.rear_left - rhs.rear_left,
+            self.rear_right - rhs.rear_right,
+        )
     }
 }
 
 /// The state of an individual swerve module.
 #[derive(Debug, Clone, Copy)]
 pub struct SwerveState {
-    /// The speed or displacment of the module in m/s or m
+    /// The speed or displacment of the module in m/s or m. This is a plain number because the
+    /// same state is used for speeds and distances, so prefer [SwerveState::from_speed],
+    /// [SwerveState::from_distance], [SwerveState::speed] and [SwerveState::distance]
     pub drive: f32,
-    /// The angle of the module in radians
-    pub angle: f32,
+    /// The angle of the module
+    pub angle: Angle,
 }
 
 impl<'a, R: Dim, C: Dim> From<VectorView2<'a, f32, R, C>> for SwerveState {
     fn from(vector: VectorView2<'a, f32, R, C>) -> Self {
         Self {
             drive: vector.magnitude(),
-            angle: vector.index((1, 0)).atan2(*vector.index((0, 0))),
+            angle: Angle::atan2(*vector.index((1, 0)), *vector.index((0, 0))),
         }
     }
 }
 
 impl SwerveState {
-    pub fn new(angle: f32, drive: f32) -> Self {
+    pub fn new(angle: Angle, drive: f32) -> Self {
         Self { angle, drive }
     }
 
-    pub fn get_angle(&self) -> f32 {
+    /// Create the state of a module moving at the given speed
+    pub fn from_speed(angle: Angle, speed: LinearVelocity) -> Self {
+        Self::new(angle, speed.meters_per_second())
+    }
+
+    /// Create the state of a module that has driven the given distance
+    pub fn from_distance(angle: Angle, distance: Length) -> Self {
+        Self::new(angle, distance.meters())
+    }
+
+    pub fn get_angle(&self) -> Angle {
         self.angle
     }
 
@@ -168,6 +583,16 @@ impl SwerveState {
         self.drive
     }
 
+    /// Get the drive value as a speed
+    pub fn speed(&self) -> LinearVelocity {
+        LinearVelocity::from_meters_per_second(self.drive)
+    }
+
+    /// Get the drive value as a distance
+    pub fn distance(&self) -> Length {
+        Length::from_meters(self.drive)
+    }
+
     /// Stop the module from moving
     pub fn stop(&mut self) {
         self.drive = 0.0
@@ -182,17 +607,457 @@ impl SwerveState {
             };
         }
 
-        let new_angle = normalize_angle(self.angle);
-        let old_angle = normalize_angle(old.angle);
+        let new_angle = self.angle.normalized();
+        let old_angle = old.angle.normalized();
         let diff = new_angle - old_angle;
 
-        if diff.abs() < PI / 2.0 {
+        if diff.abs() < Angle::from_radians(PI / 2.0) {
             self
         } else {
             Self {
-                angle: normalize_angle(new_angle - PI),
+                angle: (new_angle - Angle::from_radians(PI)).normalized(),
                 drive: -1.0 * self.drive,
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Twist2d;
+
+    const EPSILON: f32 = 1e-4;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < EPSILON,
+            "{actual} is not close to {expected}"
+        );
+    }
+
+    fn differential() -> DifferentialDriveKinematics {
+        DifferentialDriveKinematics::new(0.381 * 2.0)
+    }
+
+    #[test]
+    fn differential_zeros() {
+        let state = differential().inverse(Vector3::zeros());
+
+        assert_close(state.left, 0.0);
+        assert_close(state.right, 0.0);
+
+        let speeds = differential().forward(DifferentialState::default());
+
+        assert_close(speeds.x, 0.0);
+        assert_close(speeds.y, 0.0);
+        assert_close(speeds.z, 0.0);
+    }
+
+    #[test]
+    fn differential_straight_line() {
+        let state = differential().inverse(Vector3::new(3.0, 0.0, 0.0));
+
+        assert_close(state.left, 3.0);
+        assert_close(state.right, 3.0);
+
+        let speeds = differential().forward(DifferentialState::new(3.0, 3.0));
+
+        assert_close(speeds.x, 3.0);
+        assert_close(speeds.y, 0.0);
+        assert_close(speeds.z, 0.0);
+    }
+
+    #[test]
+    fn differential_rotate_in_place() {
+        let state = differential().inverse(Vector3::new(0.0, 0.0, PI));
+
+        assert_close(state.left, -0.381 * PI);
+        assert_close(state.right, 0.381 * PI);
+
+        let speeds = differential().forward(DifferentialState::new(0.381 * PI, -0.381 * PI));
+
+        assert_close(speeds.x, 0.0);
+        assert_close(speeds.y, 0.0);
+        assert_close(speeds.z, -PI);
+    }
+
+    #[test]
+    fn differential_ignores_strafe() {
+        let state = differential().inverse(Vector3::new(1.0, 5.0, 0.0));
+
+        assert_close(state.left, 1.0);
+        assert_close(state.right, 1.0);
+    }
+
+    #[test]
+    fn differential_desaturate() {
+        let state = DifferentialDriveKinematics::desaturate(DifferentialState::new(2.0, -4.0), 3.0);
+
+        assert_close(state.left, 1.5);
+        assert_close(state.right, -3.0);
+
+        let state = DifferentialState::new(1.0, -2.0);
+
+        assert_eq!(DifferentialDriveKinematics::desaturate(state, 3.0), state);
+    }
+
+    fn mecanum() -> MecanumKinematics {
+        MecanumKinematics::new([
+            Translation2d::new(12.0, 12.0),
+            Translation2d::new(12.0, -12.0),
+            Translation2d::new(-12.0, 12.0),
+            Translation2d::new(-12.0, -12.0),
+        ])
+    }
+
+    fn assert_mecanum(actual: MecanumState, expected: [f32; 4]) {
+        for (actual, expected) in actual.to_array().into_iter().zip(expected) {
+            assert!(
+                (actual - expected).abs() < 1e-3,
+                "{actual} is not close to {expected}"
+            );
+        }
+    }
+
+    fn assert_speeds(actual: Vector3<f32>, x: f32, y: f32, z: f32) {
+        assert!((actual.x - x).abs() < 1e-2, "{} != {x}", actual.x);
+        assert!((actual.y - y).abs() < 1e-2, "{} != {y}", actual.y);
+        assert!((actual.z - z).abs() < 1e-2, "{} != {z}", actual.z);
+    }
+
+    #[test]
+    fn mecanum_straight_line() {
+        assert_mecanum(
+            mecanum().inverse(Vector3::new(5.0, 0.0, 0.0)),
+            [5.0, 5.0, 5.0, 5.0],
+        );
+        assert_speeds(
+            mecanum().forward(MecanumState::new(3.536, 3.536, 3.536, 3.536)),
+            3.536,
+            0.0,
+            0.0,
+        );
+    }
+
+    #[test]
+    fn mecanum_strafe() {
+        assert_mecanum(
+            mecanum().inverse(Vector3::new(0.0, 4.0, 0.0)),
+            [-4.0, 4.0, 4.0, -4.0],
+        );
+        assert_speeds(
+            mecanum().forward(MecanumState::new(-2.828427, 2.828427, 2.828427, -2.828427)),
+            0.0,
+            2.8284,
+            0.0,
+        );
+    }
+
+    #[test]
+    fn mecanum_rotation() {
+        assert_mecanum(
+            mecanum().inverse(Vector3::new(0.0, 0.0, 2.0 * PI)),
+            [-150.79645, 150.79645, -150.79645, 150.79645],
+        );
+        assert_speeds(
+            mecanum().forward(MecanumState::new(
+                -150.79645, 150.79645, -150.79645, 150.79645,
+            )),
+            0.0,
+            0.0,
+            2.0 * PI,
+        );
+    }
+
+    #[test]
+    fn mecanum_mixed_translation_rotation() {
+        assert_mecanum(
+            mecanum().inverse(Vector3::new(2.0, 3.0, 1.0)),
+            [-25.0, 29.0, -19.0, 23.0],
+        );
+        assert_speeds(
+            mecanum().forward(MecanumState::new(-17.67767, 20.51, -13.44, 16.26)),
+            1.413,
+            2.122,
+            0.707,
+        );
+    }
+
+    #[test]
+    fn mecanum_round_trip() {
+        for speeds in [
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(-0.5, 2.0, 0.3),
+            Vector3::new(3.0, -1.0, -2.0),
+        ] {
+            let actual = mecanum().forward(mecanum().inverse(speeds));
+
+            assert_speeds(actual, speeds.x, speeds.y, speeds.z);
+        }
+    }
+
+    #[test]
+    fn mecanum_desaturate() {
+        let state = MecanumKinematics::desaturate(MecanumState::new(5.0, 6.0, 4.0, 7.0), 5.5);
+        let scale = 5.5 / 7.0;
+
+        assert_mecanum(state, [5.0 * scale, 6.0 * scale, 4.0 * scale, 7.0 * scale]);
+
+        let state = MecanumState::new(-5.0, 1.0, 2.0, 3.0);
+
+        assert_eq!(MecanumKinematics::desaturate(state, 5.5), state);
+    }
+
+    /// A small deterministic random number generator so the property tests are repeatable
+    struct Rng(u64);
+
+    impl Rng {
+        fn range(&mut self, min: f32, max: f32) -> f32 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+
+            min + (max - min) * ((self.0 >> 40) as f32 / (1u64 << 24) as f32)
+        }
+    }
+
+    fn random_states(rng: &mut Rng) -> [SwerveState; 4] {
+        array::from_fn(|_| {
+            SwerveState::new(
+                Angle::from_radians(rng.range(-PI, PI)),
+                rng.range(-10.0, 10.0),
+            )
+        })
+    }
+
+    fn max_drive(states: &[SwerveState]) -> f32 {
+        states
+            .iter()
+            .map(|state| state.drive.abs())
+            .fold(0.0, f32::max)
+    }
+
+    /// Check that every module was scaled by the same amount and kept its angle
+    fn assert_scaled_evenly(input: &[SwerveState], output: &[SwerveState]) {
+        let largest = input
+            .iter()
+            .zip(output)
+            .max_by(|(a, _), (b, _)| a.drive.abs().total_cmp(&b.drive.abs()))
+            .unwrap();
+
+        let scale = if largest.0.drive == 0.0 {
+            1.0
+        } else {
+            largest.1.drive / largest.0.drive
+        };
+
+        assert!(scale > 0.0 && scale <= 1.0 + EPSILON, "Scaled by {scale}");
+
+        for (input, output) in input.iter().zip(output) {
+            assert_close(output.drive, input.drive * scale);
+            assert_eq!(output.angle, input.angle);
+        }
+    }
+
+    #[test]
+    fn swerve_desaturate_properties() {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+
+        for _ in 0..1000 {
+            let states = random_states(&mut rng);
+            let max_speed = rng.range(0.5, 12.0);
+
+            let desaturated = SwerveKinematics::desaturate(states, max_speed);
+
+            assert!(max_drive(&desaturated) <= max_speed + EPSILON);
+            assert_scaled_evenly(&states, &desaturated);
+
+            if max_drive(&states) <= max_speed {
+                for (input, output) in states.iter().zip(&desaturated) {
+                    assert_eq!(input.drive, output.drive);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn swerve_desaturate_with_chassis_properties() {
+        let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+        let kinematics = SwerveKinematics::new(module_positions_from_dimensions(0.6, 0.5));
+
+        for _ in 0..1000 {
+            let robot_speeds = ChassisSpeeds::new(
+                LinearVelocity::from_meters_per_second(rng.range(-6.0, 6.0)),
+                LinearVelocity::from_meters_per_second(rng.range(-6.0, 6.0)),
+                AngularVelocity::from_radians_per_second(rng.range(-10.0, 10.0)),
+            );
+            let max_module_speed = LinearVelocity::from_meters_per_second(rng.range(1.0, 5.0));
+
+            let states = kinematics.inverse_speeds(robot_speeds);
+
+            let desaturated = SwerveKinematics::desaturate_with_chassis(
+                states,
+                robot_speeds,
+                max_module_speed,
+                LinearVelocity::from_meters_per_second(rng.range(1.0, 5.0)),
+                AngularVelocity::from_radians_per_second(rng.range(1.0, 10.0)),
+            );
+
+            assert!(max_drive(&desaturated) <= max_module_speed.meters_per_second() + EPSILON);
+            assert_scaled_evenly(&states, &desaturated);
+        }
+    }
+
+    #[test]
+    fn swerve_desaturate_with_chassis_within_limits() {
+        let mut rng = Rng(0x1234_5678_9abc_def1);
+        let kinematics = SwerveKinematics::new(module_positions_from_dimensions(0.6, 0.5));
+
+        for _ in 0..1000 {
+            let max_speed = rng.range(1.0, 5.0);
+            let speed = rng.range(0.1, max_speed);
+            let direction = rng.range(-PI, PI);
+
+            // Without rotation every module moves at the robot speed
+            let robot_speeds = ChassisSpeeds::new(
+                LinearVelocity::from_meters_per_second(speed * direction.cos()),
+                LinearVelocity::from_meters_per_second(speed * direction.sin()),
+                AngularVelocity::ZERO,
+            );
+
+            let states = kinematics.inverse_speeds(robot_speeds);
+
+            let desaturated = SwerveKinematics::desaturate_with_chassis(
+                states,
+                robot_speeds,
+                LinearVelocity::from_meters_per_second(max_speed),
+                LinearVelocity::from_meters_per_second(max_speed),
+                AngularVelocity::from_radians_per_second(rng.range(1.0, 10.0)),
+            );
+
+            for (input, output) in states.iter().zip(&desaturated) {
+                assert_close(output.drive, input.drive);
+                assert_eq!(output.angle, input.angle);
+            }
+        }
+    }
+
+    /// Modules spaced evenly on a circle, starting straight ahead
+    fn ring<const N: usize>(radius: f32) -> SwerveKinematics<N> {
+        SwerveKinematics::new(array::from_fn(|i| {
+            let angle = 2.0 * PI * i as f32 / N as f32;
+
+            Translation2d::new(radius * angle.cos(), radius * angle.sin())
+        }))
+    }
+
+    fn assert_swerve_round_trip<const N: usize>(kinematics: &SwerveKinematics<N>) {
+        let mut rng = Rng(0x0fed_cba9_8765_4321);
+
+        for _ in 0..100 {
+            let speeds = Vector3::new(
+                rng.range(-4.0, 4.0),
+                rng.range(-4.0, 4.0),
+                rng.range(-6.0, 6.0),
+            );
+
+            let states = kinematics.inverse(speeds);
+
+            assert_speeds(kinematics.forward(states), speeds.x, speeds.y, speeds.z);
+        }
+    }
+
+    #[test]
+    fn swerve_three_module_round_trip() {
+        let kinematics = ring::<3>(0.3);
+
+        // The module straight ahead of the center moves to the left when rotating
+        let states = kinematics.inverse(Vector3::new(0.0, 0.0, 2.0));
+        assert_close(states[0].drive, 0.6);
+        assert_close(states[0].angle.radians(), PI / 2.0);
+
+        assert_swerve_round_trip(&kinematics);
+    }
+
+    #[test]
+    fn swerve_six_module_round_trip() {
+        assert_swerve_round_trip(&ring::<6>(0.4));
+    }
+
+    #[test]
+    fn swerve_second_order_feedforward() {
+        let kinematics = SwerveKinematics::new([Translation2d::new(0.5, 0.5)]);
+
+        // The module moves at (2 - 1 * 0.5, 1 * 0.5) = (1.5, 0.5) and accelerates at
+        // (-0.5 * 0.5, 0.5 * 0.5) = (-0.25, 0.25)
+        let [module] = kinematics
+            .inverse_second_order(Vector3::new(2.0, 0.0, 1.0), Vector3::new(0.0, 0.0, 0.5));
+
+        assert_close(module.state.drive, 2.5_f32.sqrt());
+        assert_close(module.state.angle.radians(), (0.5_f32).atan2(1.5));
+        // The acceleration along the wheel is (v . a) / |v|
+        assert_close(module.acceleration, -0.25 / 2.5_f32.sqrt());
+        // The heading turns at (v x a) / |v|^2
+        assert_close(module.angular_velocity.radians_per_second(), 0.5 / 2.5);
+
+        // Accelerating sideways while driving forward turns the module
+        let [module] = kinematics
+            .inverse_second_order(Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0));
+
+        assert_close(module.acceleration, 0.0);
+        assert_close(module.angular_velocity.radians_per_second(), 1.0);
+    }
+
+    #[test]
+    fn swerve_second_order_stopped() {
+        let kinematics = SwerveKinematics::new(module_positions_from_dimensions(0.6, 0.5));
+
+        // A stopped module has no direction, so it can't have a feedforward
+        let states =
+            kinematics.inverse_second_order(Vector3::zeros(), Vector3::new(1.0, -2.0, 3.0));
+
+        for module in states {
+            assert_close(module.state.drive, 0.0);
+            assert_close(module.acceleration, 0.0);
+            assert_close(module.angular_velocity.radians_per_second(), 0.0);
+        }
+    }
+
+    #[test]
+    fn discretize_matches_wpilib() {
+        let speeds = ChassisSpeeds::new(
+            LinearVelocity::from_meters_per_second(1.0),
+            LinearVelocity::ZERO,
+            AngularVelocity::from_radians_per_second(1.0),
+        );
+
+        let discretized = speeds.discretize(Duration::from_secs(1));
+
+        assert_close(discretized.vx.meters_per_second(), 0.915244);
+        assert_close(discretized.vy.meters_per_second(), -0.5);
+        assert_close(discretized.omega.radians_per_second(), 1.0);
+
+        // Following the discretized speeds for dt ends up where the original speeds point
+        let end = Pose2d::default().exp(Twist2d {
+            dx: discretized.vx.meters_per_second(),
+            dy: discretized.vy.meters_per_second(),
+            dtheta: discretized.omega.radians_per_second(),
+        });
+
+        assert_close(end.translation.x, 1.0);
+        assert_close(end.translation.y, 0.0);
+        assert_close(end.rotation.radians(), 1.0);
+    }
+
+    #[test]
+    fn discretize_without_rotation_does_nothing() {
+        let speeds = ChassisSpeeds::new(
+            LinearVelocity::from_meters_per_second(2.0),
+            LinearVelocity::from_meters_per_second(-1.0),
+            AngularVelocity::ZERO,
+        );
+
+        assert_eq!(speeds.discretize(Duration::from_millis(20)), speeds);
+    }
+}
@@ -4,15 +4,22 @@
 use std::{marker::ConstParamTy, ops::Add, time::Duration};
 
 use impl_trait_for_tuples::impl_for_tuples;
+use units::{Angle, AngularVelocity, Length, LinearVelocity};
 
 use std::f32::consts::PI;
 
 pub mod feedforward;
 pub mod filter;
+pub mod interpolation;
+pub mod pid;
 pub mod profile;
 
+pub mod geometry;
 pub mod kinematics;
 pub mod odometry;
+pub mod pose_estimator;
+
+pub mod units;
 
 #[cfg(feature = "std")]
 pub fn get_time() -> Duration {
@@ -91,6 +98,11 @@ pub trait Controller {
     }
 }
 
+/// A controller that can tell when the mechanism it controls has reached its target
+pub trait AtGoal {
+    fn at_goal(&self) -> bool;
+}
+
 #[impl_for_tuples(1, 8)]
 impl<S, O: Add<Output = O>> Controller for Tuple {
     type Output = O;
@@ -104,7 +116,7 @@ impl<S, O: Add<Output = O>> Controller for Tuple {
     }
 }
 
-#[derive(PartialEq, Eq, ConstParamTy)]
+#[derive(Clone, Copy, PartialEq, Eq, ConstParamTy)]
 pub struct ConstFloat(u32);
 
 impl ConstFloat {
@@ -117,6 +129,12 @@ impl ConstFloat {
     }
 }
 
+impl std::fmt::Debug for ConstFloat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.get().fmt(f)
+    }
+}
+
 pub type Gain = ConstFloat;
 
 pub struct P<const K: Gain>;
@@ -170,8 +188,13 @@ impl<const K: Gain> Default for I<K> {
 
 pub type D<const K: Gain> = Derive<P<K>>;
 
-pub type PID<const KP: Gain, const KI: Gain, const KD: Gain> = (P<KP>, I<KP>, D<KP>);
+pub type PID<const KP: Gain, const KI: Gain, const KD: Gain> = (P<KP>, I<KI>, D<KD>);
 
+/// The position and velocity of a mechanism. The values are stored in SI base units, so meters
+/// and meters per second for linear mechanisms and radians and radians per second for rotating
+/// mechanisms. The fields are plain numbers because the controllers use the same state for both
+/// kinds of mechanism, so create it with [State::linear] or [State::angular] and read it with the
+/// typed accessors to keep the units checked
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct State {
     pub position: f32,
@@ -182,10 +205,47 @@ impl State {
     pub fn new(position: f32, velocity: f32) -> Self {
         Self { position, velocity }
     }
+
+    /// Create the state of a linear mechanism, such as an elevator
+    pub fn linear(position: Length, velocity: LinearVelocity) -> Self {
+        Self::new(position.meters(), velocity.meters_per_second())
+    }
+
+    /// Create the state of a rotating mechanism, such as an arm
+    pub fn angular(position: Angle, velocity: AngularVelocity) -> Self {
+        Self::new(position.radians(), velocity.radians_per_second())
+    }
+
+    /// Get the position of a linear mechanism
+    pub fn length(&self) -> Length {
+        Length::from_meters(self.position)
+    }
+
+    /// Get the velocity of a linear mechanism
+    pub fn linear_velocity(&self) -> LinearVelocity {
+        LinearVelocity::from_meters_per_second(self.velocity)
+    }
+
+    /// Get the position of a rotating mechanism
+    pub fn angle(&self) -> Angle {
+        Angle::from_radians(self.position)
+    }
+
+    /// Get the velocity of a rotating mechanism
+    pub fn angular_velocity(&self) -> AngularVelocity {
+        AngularVelocity::from_radians_per_second(self.velocity)
+    }
 }
 
 pub struct Velocity<C>(C);
 
+impl<C> Velocity<C> {
+    /// Run `controller` on the velocity of the state
+    pub fn new(controller: C) -> Self {
+        Self(controller)
+    }
+}
+
 impl<C: Controller<State = f32>> Controller for Velocity<C> {
     type Output = C::Output;
     type State = State;
@@ -204,6 +264,13 @@ impl<C: Controller<State = f32>> Controller for Velocity<C> {
 
 pub struct Position<C>(C);
 
+impl<C> Position<C> {
+    /// Run `controller` on the position of the state
+    pub fn new(controller: C) -> Self {
+        Self(controller)
+    }
+}
+
 impl<C: Controller<State = f32>> Controller for Position<C> {
     type State = State;
     type Output = C::Output;
@@ -1,4 +1,5 @@
 use math::{
+    geometry::{Pose2d, Rotation2d},
     kinematics::{module_positions_from_dimensions, Kinematics, SwerveKinematics},
     odometry::Odometry,
 };
@@ -7,9 +8,12 @@ use nalgebra::Vector3;
 fn main() {
     let drive = SwerveKinematics::new(module_positions_from_dimensions(1.0, 1.0));
 
-    let odometry = Odometry::new(drive.clone(), Vector3::new(0.0, 0.0, 0.0), 0.0);
+    let odometry = Odometry::new(drive.clone(), Pose2d::default(), Rotation2d::IDENTITY);
 
-    odometry.update(drive.inverse(Vector3::new(1.0, 1.0, 0.0)), 0.0);
+    odometry.update(
+        drive.inverse(Vector3::new(1.0, 1.0, 0.0)),
+        Rotation2d::IDENTITY,
+    );
 
     dbg!(odometry.get_pose());
 }
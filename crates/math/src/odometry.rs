@@ -1,8 +1,9 @@
 use std::{cell::Cell, rc::Rc};
 
-use nalgebra::{matrix, Rotation2, Vector3};
-
-use crate::kinematics::Kinematics;
+use crate::{
+    geometry::{Pose2d, Rotation2d, Twist2d},
+    kinematics::{DifferentialDriveKinematics, DifferentialState, Kinematics},
+};
 
 #[derive(Clone)]
 /// This object calculates the robots positon over time by integrating the speeds derived from
@@ -14,12 +15,12 @@ pub struct Odometry<K: Kinematics> {
 
 pub(crate) struct InnerOdometry<K: Kinematics> {
     kinematics: K,
-    pose: Cell<Vector3<f32>>,
-    last_rotation: Cell<f32>,
+    pose: Cell<Pose2d>,
+    last_rotation: Cell<Rotation2d>,
 }
 
 impl<K: Kinematics> InnerOdometry<K> {
-    pub(crate) fn new(kinematics: K, starting_pose: Vector3<f32>, current_heading: f32) -> Self {
+    pub(crate) fn new(kinematics: K, starting_pose: Pose2d, current_heading: Rotation2d) -> Self {
         Self {
             kinematics,
             pose: Cell::new(starting_pose),
@@ -27,49 +28,31 @@ impl<K: Kinematics> InnerOdometry<K> {
         }
     }
 
-    pub(crate) fn update(&self, value: K::State, current_heading: f32) {
-        let displacement = self.kinematics.forward(value).fixed_resize::<2, 1>(0.0);
+    pub(crate) fn update(&self, value: K::State, current_heading: Rotation2d) {
+        let displacement = self.kinematics.forward(value);
 
         let delta_theta = current_heading - self.last_rotation.get();
 
         self.last_rotation.set(current_heading);
 
-        let sin_theta = delta_theta.sin();
-        let cos_theta = delta_theta.cos();
-
-        let pose_exponential_matrix = if delta_theta.abs() > f32::EPSILON {
-            let sin_theta_expr = sin_theta / delta_theta;
-
-            matrix![sin_theta_expr, (cos_theta - 1.0) / delta_theta;
-            (1.0 - cos_theta) / delta_theta, sin_theta_expr
-            ]
-        } else {
-            let sin_theta_expr = 1.0 - delta_theta.powi(2) / 6.0;
-
-            matrix![sin_theta_expr, -delta_theta / 2.0;
-            delta_theta / 2.0, sin_theta_expr
-            ]
-        };
-
-        let rotation = Rotation2::new(self.pose.get().z + delta_theta);
-
-        let final_displacement = rotation.matrix() * pose_exponential_matrix * displacement;
-
-        self.pose
-            .set(self.pose.get() + final_displacement.fixed_resize::<3, 1>(delta_theta));
+        self.pose.set(self.pose.get().exp(Twist2d::new(
+            displacement.x,
+            displacement.y,
+            delta_theta.radians(),
+        )));
     }
 
-    pub(crate) fn get_pose(&self) -> Vector3<f32> {
+    pub(crate) fn get_pose(&self) -> Pose2d {
         self.pose.get()
     }
 
-    pub(crate) fn set_pose(&self, pose: Vector3<f32>) {
+    pub(crate) fn set_pose(&self, pose: Pose2d) {
         self.pose.set(pose);
     }
 }
 
 impl<K: Kinematics> Odometry<K> {
-    pub fn new(kinematics: K, starting_pose: Vector3<f32>, current_heading: f32) -> Self {
+    pub fn new(kinematics: K, starting_pose: Pose2d, current_heading: Rotation2d) -> Self {
         Self {
             inner: Rc::new(InnerOdometry::new(
                 kinematics,
@@ -81,17 +64,129 @@ impl<K: Kinematics> Odometry<K> {
 
     /// The state in this case should represent displacement not speed. This updates the current
     /// estimate using the displacement of the drivetrain and the current heading.
-    pub fn update(&self, value: K::State, current_heading: f32) {
+    pub fn update(&self, value: K::State, current_heading: Rotation2d) {
         self.inner.update(value, current_heading)
     }
 
-    /// Return the current pose as x, y, rotation
-    pub fn get_pose(&self) -> Vector3<f32> {
+    pub fn get_pose(&self) -> Pose2d {
         self.inner.get_pose()
     }
 
-    /// Set the current pose as x, y, rotation
-    pub fn set_pose(&self, pose: Vector3<f32>) {
+    /// Reset the pose. The heading passed to the next update is still measured relative to the
+    /// last heading, so the gyro does not need to be reset
+    pub fn set_pose(&self, pose: Pose2d) {
         self.inner.set_pose(pose)
     }
 }
+
+/// Odometry for a differential drivetrain that is updated with the total distance driven by each
+/// side, such as the encoder positions, instead of the displacement since the last update.
+#[derive(Clone)]
+pub struct DifferentialOdometry {
+    odometry: Odometry<DifferentialDriveKinematics>,
+    last_positions: Rc<Cell<DifferentialState>>,
+}
+
+impl DifferentialOdometry {
+    pub fn new(
+        kinematics: DifferentialDriveKinematics,
+        starting_pose: Pose2d,
+        current_heading: Rotation2d,
+        positions: DifferentialState,
+    ) -> Self {
+        Self {
+            odometry: Odometry::new(kinematics, starting_pose, current_heading),
+            last_positions: Rc::new(Cell::new(positions)),
+        }
+    }
+
+    /// Update the current estimate using the distance driven by each side and the current heading
+    pub fn update(&self, positions: DifferentialState, current_heading: Rotation2d) {
+        let displacement = positions - self.last_positions.replace(positions);
+
+        self.odometry.update(displacement, current_heading)
+    }
+
+    pub fn get_pose(&self) -> Pose2d {
+        self.odometry.get_pose()
+    }
+
+    /// Reset the pose along with the wheel positions, for example after the encoders are reset
+    pub fn reset(&self, pose: Pose2d, positions: DifferentialState) {
+        self.last_positions.set(positions);
+        self.odometry.set_pose(pose);
+    }
+
+    /// Get the underlying [Odometry], which shares its state with this object
+    pub fn odometry(&self) -> &Odometry<DifferentialDriveKinematics> {
+        &self.odometry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::*;
+
+    const EPSILON: f32 = 1e-4;
+
+    fn assert_pose(actual: Pose2d, x: f32, y: f32, degrees: f32) {
+        assert!((actual.x() - x).abs() < EPSILON, "{} != {x}", actual.x());
+        assert!((actual.y() - y).abs() < EPSILON, "{} != {y}", actual.y());
+        assert_eq!(actual.rotation, Rotation2d::from_degrees(degrees));
+    }
+
+    fn differential(heading: Rotation2d) -> DifferentialOdometry {
+        DifferentialOdometry::new(
+            DifferentialDriveKinematics::new(0.381 * 2.0),
+            Pose2d::default(),
+            heading,
+            DifferentialState::default(),
+        )
+    }
+
+    #[test]
+    fn differential_encoder_distances() {
+        let odometry = differential(Rotation2d::from_degrees(45.0));
+
+        odometry.update(
+            DifferentialState::new(0.0, 5.0 * PI),
+            Rotation2d::from_degrees(135.0),
+        );
+
+        assert_pose(odometry.get_pose(), 5.0, 5.0, 90.0);
+    }
+
+    #[test]
+    fn differential_straight_line() {
+        let odometry = differential(Rotation2d::IDENTITY);
+
+        for i in 1..=10 {
+            let distance = i as f32 * 0.5;
+
+            odometry.update(
+                DifferentialState::new(distance, distance),
+                Rotation2d::IDENTITY,
+            );
+        }
+
+        assert_pose(odometry.get_pose(), 5.0, 0.0, 0.0);
+    }
+
+    #[test]
+    fn differential_reset() {
+        let odometry = differential(Rotation2d::IDENTITY);
+
+        odometry.update(DifferentialState::new(2.0, 2.0), Rotation2d::IDENTITY);
+        odometry.reset(
+            Pose2d::new(1.0, 1.0, Rotation2d::from_degrees(90.0)),
+            DifferentialState::default(),
+        );
+
+        // Only the distance driven since the reset is applied
+        odometry.update(DifferentialState::new(1.0, 1.0), Rotation2d::IDENTITY);
+
+        assert_pose(odometry.get_pose(), 1.0, 2.0, 90.0);
+    }
+}
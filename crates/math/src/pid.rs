@@ -0,0 +1,442 @@
+use std::time::Duration;
+
+use crate::{optimize_angle, AtGoal, Controller};
+
+/// A PID controller whose gains can be changed while the robot is running, for example from
+/// NetworkTables while tuning. Use [P](crate::P), [I](crate::I) and [D](crate::D) if the gains
+/// are known at compile time.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let mut controller = PidController::new(0.5, 0.0, 0.01)
+///     .continuous_input()
+///     .output_limits(-1.0, 1.0)
+///     .tolerance(0.05, 0.1);
+///
+/// while !controller.at_setpoint() {
+///     let output = controller.calculate(&turret.angle().radians(), &target);
+///     turret.set_percent(output)?;
+///     yield_now().await;
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct PidController {
+    kp: f32,
+    ki: f32,
+    kd: f32,
+    integral_zone: Option<f32>,
+    integral_range: (f32, f32),
+    output_range: (f32, f32),
+    continuous: bool,
+    derivative_on_measurement: bool,
+    position_tolerance: f32,
+    velocity_tolerance: f32,
+    integral: f32,
+    last_time: Option<Duration>,
+    last_error: f32,
+    last_measurement: f32,
+    error_derivative: f32,
+}
+
+impl PidController {
+    pub fn new(kp: f32, ki: f32, kd: f32) -> Self {
+        Self {
+            kp,
+            ki,
+            kd,
+            integral_zone: None,
+            integral_range: (f32::NEG_INFINITY, f32::INFINITY),
+            output_range: (f32::NEG_INFINITY, f32::INFINITY),
+            continuous: false,
+            derivative_on_measurement: false,
+            position_tolerance: 0.05,
+            velocity_tolerance: f32::INFINITY,
+            integral: 0.0,
+            last_time: None,
+            last_error: 0.0,
+            last_measurement: 0.0,
+            error_derivative: 0.0,
+        }
+    }
+
+    /// Only accumulate the integral while the error is smaller than `zone`. The integral is reset
+    /// when the error leaves the zone
+    pub fn integral_zone(self, zone: f32) -> Self {
+        Self {
+            integral_zone: Some(zone),
+            ..self
+        }
+    }
+
+    /// Limit the contribution of the integral term to the output
+    pub fn integral_range(self, min: f32, max: f32) -> Self {
+        Self {
+            integral_range: (min, max),
+            ..self
+        }
+    }
+
+    /// Clamp the output of the controller
+    pub fn output_limits(self, min: f32, max: f32) -> Self {
+        Self {
+            output_range: (min, max),
+            ..self
+        }
+    }
+
+    /// Treat the measurement and target as angles in radians and always turn the shortest way.
+    /// See [optimize_angle]
+    pub fn continuous_input(self) -> Self {
+        Self {
+            continuous: true,
+            ..self
+        }
+    }
+
+    /// Calculate the derivative from the change in measurement instead of the change in error.
+    /// This prevents the output from spiking when the target changes suddenly
+    pub fn derivative_on_measurement(self) -> Self {
+        Self {
+            derivative_on_measurement: true,
+            ..self
+        }
+    }
+
+    /// Set how close the error and its rate of change must be to zero for
+    /// [PidController::at_setpoint]
+    pub fn tolerance(self, position: f32, velocity: f32) -> Self {
+        Self {
+            position_tolerance: position,
+            velocity_tolerance: velocity,
+            ..self
+        }
+    }
+
+    pub fn set_gains(&mut self, kp: f32, ki: f32, kd: f32) {
+        self.kp = kp;
+        self.ki = ki;
+        self.kd = kd;
+    }
+
+    pub fn set_p(&mut self, kp: f32) {
+        self.kp = kp;
+    }
+
+    pub fn set_i(&mut self, ki: f32) {
+        self.ki = ki;
+    }
+
+    pub fn set_d(&mut self, kd: f32) {
+        self.kd = kd;
+    }
+
+    pub fn set_integral_zone(&mut self, zone: Option<f32>) {
+        self.integral_zone = zone;
+    }
+
+    pub fn set_integral_range(&mut self, min: f32, max: f32) {
+        self.integral_range = (min, max);
+    }
+
+    pub fn set_output_limits(&mut self, min: f32, max: f32) {
+        self.output_range = (min, max);
+    }
+
+    pub fn set_tolerance(&mut self, position: f32, velocity: f32) {
+        self.position_tolerance = position;
+        self.velocity_tolerance = velocity;
+    }
+
+    /// Get the gains as (p, i, d)
+    pub fn gains(&self) -> (f32, f32, f32) {
+        (self.kp, self.ki, self.kd)
+    }
+
+    /// Get the error from the last time the controller was run
+    pub fn error(&self) -> f32 {
+        self.last_error
+    }
+
+    /// Get the rate of change of the error from the last time the controller was run
+    pub fn error_derivative(&self) -> f32 {
+        self.error_derivative
+    }
+
+    /// Returns true if the error and its rate of change are within the tolerance. This is always
+    /// false before the controller has been run
+    pub fn at_setpoint(&self) -> bool {
+        self.last_time.is_some()
+            && self.last_error.abs() <= self.position_tolerance
+            && self.error_derivative.abs() <= self.velocity_tolerance
+    }
+
+    /// Clear the integral and derivative history, for example when the mechanism is re-enabled
+    pub fn reset(&mut self) {
+        self.integral = 0.0;
+        self.last_time = None;
+        self.last_error = 0.0;
+        self.last_measurement = 0.0;
+        self.error_derivative = 0.0;
+    }
+}
+
+impl Controller for PidController {
+    type State = f32;
+    type Output = f32;
+
+    fn calculate_with_time(&mut self, current: &f32, target: &f32, time: Duration) -> f32 {
+        let (current, target) = if self.continuous {
+            optimize_angle(*current, *target)
+        } else {
+            (*current, *target)
+        };
+
+        let error = target - current;
+
+        let mut derivative = 0.0;
+
+        if let Some(last_time) = self.last_time {
+            let dt = time.as_secs_f32() - last_time.as_secs_f32();
+
+            if dt > 0.0 {
+                self.error_derivative = (error - self.last_error) / dt;
+
+                if self.integral_zone.is_some_and(|zone| error.abs() > zone) {
+                    self.integral = 0.0;
+                } else {
+                    self.integral += error * dt;
+                }
+
+                derivative = if self.derivative_on_measurement {
+                    let (last_measurement, current) = if self.continuous {
+                        optimize_angle(self.last_measurement, current)
+                    } else {
+                        (self.last_measurement, current)
+                    };
+
+                    -(current - last_measurement) / dt
+                } else {
+                    self.error_derivative
+                };
+            }
+        }
+
+        self.last_time = Some(time);
+        self.last_error = error;
+        self.last_measurement = current;
+
+        let (integral_min, integral_max) = self.integral_range;
+        let integral_term = (self.ki * self.integral).clamp(integral_min, integral_max);
+
+        // Stop the integral from winding up past the range
+        if self.ki != 0.0 {
+            self.integral = integral_term / self.ki;
+        }
+
+        let (min, max) = self.output_range;
+
+        (self.kp * error + integral_term + self.kd * derivative).clamp(min, max)
+    }
+}
+
+impl Default for PidController {
+    fn default() -> Self {
+        Self::new(0.0, 0.0, 0.0)
+    }
+}
+
+impl AtGoal for PidController {
+    fn at_goal(&self) -> bool {
+        self.at_setpoint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::*;
+
+    const EPSILON: f32 = 1e-4;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < EPSILON,
+            "{actual} != {expected}"
+        );
+    }
+
+    fn seconds(seconds: f32) -> Duration {
+        Duration::from_secs_f32(seconds)
+    }
+
+    #[test]
+    fn proportional() {
+        let mut controller = PidController::new(2.0, 0.0, 0.0);
+
+        assert_close(
+            controller.calculate_with_time(&1.0, &3.0, seconds(0.0)),
+            4.0,
+        );
+        assert_close(controller.error(), 2.0);
+    }
+
+    #[test]
+    fn output_limits() {
+        let mut controller = PidController::new(10.0, 0.0, 0.0).output_limits(-1.0, 1.0);
+
+        assert_close(
+            controller.calculate_with_time(&0.0, &3.0, seconds(0.0)),
+            1.0,
+        );
+        assert_close(
+            controller.calculate_with_time(&0.0, &-3.0, seconds(0.1)),
+            -1.0,
+        );
+    }
+
+    #[test]
+    fn integral_accumulates() {
+        let mut controller = PidController::new(0.0, 1.0, 0.0);
+
+        assert_close(
+            controller.calculate_with_time(&0.0, &1.0, seconds(0.0)),
+            0.0,
+        );
+        assert_close(
+            controller.calculate_with_time(&0.0, &1.0, seconds(0.1)),
+            0.1,
+        );
+        assert_close(
+            controller.calculate_with_time(&0.0, &1.0, seconds(0.2)),
+            0.2,
+        );
+    }
+
+    #[test]
+    fn integral_does_not_wind_up() {
+        let mut controller = PidController::new(0.0, 1.0, 0.0).integral_range(-0.5, 0.5);
+
+        for i in 0..=20 {
+            controller.calculate_with_time(&0.0, &1.0, seconds(i as f32 * 0.1));
+        }
+
+        assert_close(
+            controller.calculate_with_time(&0.0, &1.0, seconds(2.1)),
+            0.5,
+        );
+
+        // The integral was held at the limit, so it starts unwinding as soon as the error changes
+        // sign instead of after the extra 1.6 seconds of windup
+        assert_close(
+            controller.calculate_with_time(&0.0, &-1.0, seconds(2.2)),
+            0.4,
+        );
+    }
+
+    #[test]
+    fn integral_zone() {
+        let mut controller = PidController::new(0.0, 1.0, 0.0).integral_zone(0.5);
+
+        controller.calculate_with_time(&0.0, &0.2, seconds(0.0));
+
+        assert_close(
+            controller.calculate_with_time(&0.0, &0.2, seconds(0.1)),
+            0.02,
+        );
+
+        // Leaving the zone clears the integral
+        assert_close(
+            controller.calculate_with_time(&0.0, &1.0, seconds(0.2)),
+            0.0,
+        );
+    }
+
+    #[test]
+    fn continuous_input_takes_the_shortest_way() {
+        let mut controller = PidController::new(1.0, 0.0, 0.0);
+
+        assert_close(
+            controller.calculate_with_time(&3.0, &-3.0, seconds(0.0)),
+            -6.0,
+        );
+
+        let mut controller = PidController::new(1.0, 0.0, 0.0).continuous_input();
+
+        assert_close(
+            controller.calculate_with_time(&3.0, &-3.0, seconds(0.0)),
+            2.0 * PI - 6.0,
+        );
+        assert_close(
+            controller.calculate_with_time(&(-3.0), &3.0, seconds(0.1)),
+            6.0 - 2.0 * PI,
+        );
+        assert_close(
+            controller.calculate_with_time(&0.1, &(4.0 * PI + 0.3), seconds(0.2)),
+            0.2,
+        );
+    }
+
+    #[test]
+    fn derivative() {
+        let mut controller = PidController::new(0.0, 0.0, 1.0);
+
+        assert_close(
+            controller.calculate_with_time(&0.0, &1.0, seconds(0.0)),
+            0.0,
+        );
+        assert_close(
+            controller.calculate_with_time(&0.1, &2.0, seconds(0.1)),
+            9.0,
+        );
+        assert_close(controller.error_derivative(), 9.0);
+    }
+
+    #[test]
+    fn derivative_on_measurement() {
+        let mut controller = PidController::new(0.0, 0.0, 1.0).derivative_on_measurement();
+
+        controller.calculate_with_time(&0.0, &1.0, seconds(0.0));
+
+        // The change in target does not cause a spike
+        assert_close(
+            controller.calculate_with_time(&0.1, &2.0, seconds(0.1)),
+            -1.0,
+        );
+    }
+
+    #[test]
+    fn derivative_on_measurement_wraps() {
+        let mut controller = PidController::new(0.0, 0.0, 1.0)
+            .continuous_input()
+            .derivative_on_measurement();
+
+        controller.calculate_with_time(&3.1, &0.0, seconds(0.0));
+
+        assert_close(
+            controller.calculate_with_time(&-3.1, &0.0, seconds(0.1)),
+            -(2.0 * PI - 6.2) / 0.1,
+        );
+    }
+
+    #[test]
+    fn at_setpoint() {
+        let mut controller = PidController::new(1.0, 0.0, 0.0).tolerance(0.1, 1.0);
+
+        assert!(!controller.at_setpoint());
+
+        controller.calculate_with_time(&0.95, &1.0, seconds(0.0));
+        assert!(controller.at_setpoint());
+
+        controller.calculate_with_time(&0.5, &1.0, seconds(0.1));
+        assert!(!controller.at_setpoint());
+
+        // Within the position tolerance but moving too fast
+        controller.calculate_with_time(&1.0, &1.0, seconds(0.2));
+        assert!(!controller.at_setpoint());
+
+        controller.reset();
+        assert!(!controller.at_setpoint());
+    }
+}
@@ -0,0 +1,442 @@
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc, time::Duration};
+
+use nalgebra::Vector3;
+
+use crate::{
+    geometry::{Pose2d, Rotation2d, Twist2d},
+    get_time,
+    interpolation::TimeInterpolatableBuffer,
+    kinematics::Kinematics,
+    odometry::Odometry,
+};
+
+/// How long odometry poses are kept for vision measurements to be applied to
+const BUFFER_DURATION: Duration = Duration::from_millis(1500);
+
+#[derive(Clone)]
+/// This object fuses odometry with vision measurements to estimate the robots position. Vision
+/// measurements are applied at the time the image was taken, and the odometry from after that
+/// time is replayed on top of the corrected pose, so camera latency does not pull the estimate
+/// backwards. Like [Odometry] this uses an [Rc] internally to allow it to be cloned and maintain
+/// the same internal state.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let estimator = PoseEstimator::new(
+///     kinematics,
+///     Pose2d::default(),
+///     gyro.rotation().into(),
+///     Vector3::new(0.1, 0.1, 0.1),
+///     Vector3::new(0.9, 0.9, 0.9),
+/// );
+///
+/// loop {
+///     estimator.update(drivetrain.displacement(), gyro.rotation().into());
+///
+///     if let Some(pose) = camera.get_result()?.estimated_pose(ROBOT_TO_CAMERA) {
+///         estimator.add_vision_measurement(pose.to_pose2d(), result.timestamp());
+///     }
+///
+///     yield_now().await;
+/// }
+/// ```
+pub struct PoseEstimator<K: Kinematics> {
+    inner: Rc<RefCell<InnerPoseEstimator>>,
+    odometry: Odometry<K>,
+}
+
+struct VisionUpdate {
+    /// The corrected pose at the time of the measurement
+    vision_pose: Pose2d,
+    /// The odometry pose at the time of the measurement
+    odometry_pose: Pose2d,
+}
+
+impl VisionUpdate {
+    /// Apply the odometry motion since this update to the corrected pose
+    fn compensate(&self, odometry_pose: Pose2d) -> Pose2d {
+        self.vision_pose + (odometry_pose - self.odometry_pose)
+    }
+}
+
+struct InnerPoseEstimator {
+    state_variance: Vector3<f32>,
+    vision_gain: Vector3<f32>,
+    odometry_poses: TimeInterpolatableBuffer<Pose2d>,
+    vision_updates: BTreeMap<Duration, VisionUpdate>,
+    pose: Pose2d,
+}
+
+impl InnerPoseEstimator {
+    fn set_vision_std_devs(&mut self, std_devs: Vector3<f32>) {
+        self.vision_gain = calculate_gain(self.state_variance, std_devs);
+    }
+
+    fn estimated_pose_at(&self, time: Duration) -> Option<Pose2d> {
+        let odometry_pose = self.odometry_poses.sample(time)?;
+
+        Some(match self.vision_updates.range(..=time).next_back() {
+            Some((_, update)) => update.compensate(odometry_pose),
+            None => odometry_pose,
+        })
+    }
+
+    /// Remove vision updates that are older than the odometry history
+    fn clean_up(&mut self) {
+        let Some(oldest) = self.odometry_poses.oldest_time() else {
+            return;
+        };
+
+        // Keep the newest update from before the window since it still applies to the poses in
+        // the window
+        if let Some(newest_old) = self
+            .vision_updates
+            .range(..oldest)
+            .next_back()
+            .map(|(time, _)| *time)
+        {
+            self.vision_updates = self.vision_updates.split_off(&newest_old);
+        }
+    }
+
+    fn add_vision_measurement(
+        &mut self,
+        vision_pose: Pose2d,
+        timestamp: Duration,
+        vision_gain: Vector3<f32>,
+    ) {
+        let Some((newest, &latest_odometry)) = self.odometry_poses.newest() else {
+            return;
+        };
+
+        // The measurement is too old to be applied
+        if newest
+            .checked_sub(BUFFER_DURATION)
+            .is_some_and(|oldest| timestamp < oldest)
+        {
+            return;
+        }
+
+        self.clean_up();
+
+        let (Some(odometry_pose), Some(estimated_pose)) = (
+            self.odometry_poses.sample(timestamp),
+            self.estimated_pose_at(timestamp),
+        ) else {
+            return;
+        };
+
+        let twist = estimated_pose.log(&vision_pose);
+
+        let scaled_twist = Twist2d::new(
+            twist.dx * vision_gain.x,
+            twist.dy * vision_gain.y,
+            twist.dtheta * vision_gain.z,
+        );
+
+        let update = VisionUpdate {
+            vision_pose: estimated_pose.exp(scaled_twist),
+            odometry_pose,
+        };
+
+        // Later updates were based on the uncorrected pose, so they are no longer valid
+        self.vision_updates.split_off(&timestamp);
+        self.vision_updates.insert(timestamp, update);
+
+        self.pose = self.vision_updates[&timestamp].compensate(latest_odometry);
+    }
+
+    fn update(&mut self, odometry_pose: Pose2d, time: Duration) {
+        self.odometry_poses.add_sample(time, odometry_pose);
+        self.clean_up();
+
+        self.pose = match self.vision_updates.values().next_back() {
+            Some(update) => update.compensate(odometry_pose),
+            None => odometry_pose,
+        };
+    }
+}
+
+/// Calculate the gain applied to vision measurements from the variance of the state and the
+/// standard deviations of the measurement. This is the steady state Kalman gain when the state
+/// and measurement are measured directly
+fn calculate_gain(state_variance: Vector3<f32>, vision_std_devs: Vector3<f32>) -> Vector3<f32> {
+    state_variance.zip_map(&vision_std_devs, |q, std_dev| {
+        let r = std_dev.powi(2);
+
+        if q == 0.0 {
+            0.0
+        } else {
+            q / (q + (q * r).sqrt())
+        }
+    })
+}
+
+impl<K: Kinematics> PoseEstimator<K> {
+    /// The standard deviations are for x and y in meters and the rotation in radians. Lower
+    /// values mean that source is trusted more.
+    pub fn new(
+        kinematics: K,
+        starting_pose: Pose2d,
+        current_heading: Rotation2d,
+        state_std_devs: Vector3<f32>,
+        vision_std_devs: Vector3<f32>,
+    ) -> Self {
+        let state_variance = state_std_devs.map(|std_dev| std_dev.powi(2));
+
+        Self {
+            inner: Rc::new(RefCell::new(InnerPoseEstimator {
+                state_variance,
+                vision_gain: calculate_gain(state_variance, vision_std_devs),
+                odometry_poses: TimeInterpolatableBuffer::new(BUFFER_DURATION),
+                vision_updates: BTreeMap::new(),
+                pose: starting_pose,
+            })),
+            odometry: Odometry::new(kinematics, starting_pose, current_heading),
+        }
+    }
+
+    /// Set how much vision measurements are trusted by default
+    pub fn set_vision_std_devs(&self, std_devs: Vector3<f32>) {
+        self.inner.borrow_mut().set_vision_std_devs(std_devs);
+    }
+
+    /// The state in this case should represent displacement not speed. See [Odometry::update]
+    pub fn update(&self, value: K::State, current_heading: Rotation2d) {
+        self.update_with_time(value, current_heading, get_time())
+    }
+
+    /// Same as [PoseEstimator::update], but with the time of the measurement
+    pub fn update_with_time(&self, value: K::State, current_heading: Rotation2d, time: Duration) {
+        self.odometry.update(value, current_heading);
+
+        self.inner
+            .borrow_mut()
+            .update(self.odometry.get_pose(), time);
+    }
+
+    /// Correct the estimate with a pose measured by vision. `timestamp` is when the image was
+    /// taken, such as the timestamp of a PhotonVision pipeline result. Measurements older than
+    /// the odometry history are ignored.
+    pub fn add_vision_measurement(&self, pose: Pose2d, timestamp: Duration) {
+        let mut inner = self.inner.borrow_mut();
+        let vision_gain = inner.vision_gain;
+
+        inner.add_vision_measurement(pose, timestamp, vision_gain);
+    }
+
+    /// Same as [PoseEstimator::add_vision_measurement], but with the standard deviations for this
+    /// measurement. These only apply to this measurement, use
+    /// [PoseEstimator::set_vision_std_devs] to change the default
+    pub fn add_vision_measurement_with_std_devs(
+        &self,
+        pose: Pose2d,
+        timestamp: Duration,
+        std_devs: Vector3<f32>,
+    ) {
+        let mut inner = self.inner.borrow_mut();
+        let vision_gain = calculate_gain(inner.state_variance, std_devs);
+
+        inner.add_vision_measurement(pose, timestamp, vision_gain);
+    }
+
+    pub fn get_pose(&self) -> Pose2d {
+        self.inner.borrow().pose
+    }
+
+    /// Get the estimated pose at a past time, or [None] if there is no odometry from around then
+    pub fn sample_at(&self, time: Duration) -> Option<Pose2d> {
+        self.inner.borrow().estimated_pose_at(time)
+    }
+
+    /// Reset the pose and clear the history
+    pub fn reset_pose(&self, pose: Pose2d) {
+        let mut inner = self.inner.borrow_mut();
+
+        self.odometry.set_pose(pose);
+        inner.odometry_poses.clear();
+        inner.vision_updates.clear();
+        inner.pose = pose;
+    }
+
+    /// Get the odometry without vision measurements applied
+    pub fn odometry(&self) -> &Odometry<K> {
+        &self.odometry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        geometry::Translation2d,
+        kinematics::{DifferentialDriveKinematics, DifferentialState},
+    };
+
+    const EPSILON: f32 = 1e-4;
+    const TRACK_WIDTH: f32 = 0.6;
+
+    fn assert_pose(actual: Pose2d, x: f32, y: f32) {
+        assert!((actual.x() - x).abs() < EPSILON, "{} != {x}", actual.x());
+        assert!((actual.y() - y).abs() < EPSILON, "{} != {y}", actual.y());
+    }
+
+    fn estimator() -> PoseEstimator<DifferentialDriveKinematics> {
+        PoseEstimator::new(
+            DifferentialDriveKinematics::new(TRACK_WIDTH),
+            Pose2d::default(),
+            Rotation2d::IDENTITY,
+            Vector3::new(0.1, 0.1, 0.1),
+            Vector3::new(0.1, 0.1, 0.1),
+        )
+    }
+
+    /// Drive forward at 1 m/s for a second in 20ms steps with perfect odometry
+    fn drive_straight(estimator: &PoseEstimator<DifferentialDriveKinematics>) {
+        for i in 1..=50 {
+            estimator.update_with_time(
+                DifferentialState::new(0.02, 0.02),
+                Rotation2d::IDENTITY,
+                Duration::from_millis(i * 20),
+            );
+        }
+    }
+
+    #[test]
+    fn latency_is_replayed() {
+        let estimator = estimator();
+
+        drive_straight(&estimator);
+
+        // With equal standard deviations the measurement moves the estimate halfway
+        estimator.add_vision_measurement(
+            Pose2d::new(0.5, 1.0, Rotation2d::IDENTITY),
+            Duration::from_millis(500),
+        );
+
+        // The correction is applied at the time of the measurement and the odometry since then
+        // is replayed on top of it
+        assert_pose(estimator.get_pose(), 1.0, 0.5);
+        assert_pose(
+            estimator.sample_at(Duration::from_millis(250)).unwrap(),
+            0.25,
+            0.0,
+        );
+        assert_pose(
+            estimator.sample_at(Duration::from_millis(750)).unwrap(),
+            0.75,
+            0.5,
+        );
+
+        estimator.update_with_time(
+            DifferentialState::new(0.02, 0.02),
+            Rotation2d::IDENTITY,
+            Duration::from_millis(1020),
+        );
+
+        assert_pose(estimator.get_pose(), 1.02, 0.5);
+        assert_pose(estimator.odometry().get_pose(), 1.02, 0.0);
+    }
+
+    #[test]
+    fn measurement_std_devs_are_not_kept() {
+        let estimator = estimator();
+
+        drive_straight(&estimator);
+
+        let measurement = Pose2d::new(1.0, 1.0, Rotation2d::IDENTITY);
+        let timestamp = Duration::from_millis(1000);
+
+        estimator.add_vision_measurement_with_std_devs(
+            measurement,
+            timestamp,
+            Vector3::new(1e6, 1e6, 1e6),
+        );
+
+        assert!(estimator.get_pose().y() < 1e-3);
+
+        // The default standard deviations are still used for later measurements
+        estimator.add_vision_measurement(measurement, timestamp);
+
+        assert!((estimator.get_pose().y() - 0.5).abs() < 1e-3);
+    }
+
+    #[test]
+    fn old_measurements_are_ignored() {
+        let estimator = estimator();
+
+        for i in 1..=150 {
+            estimator.update_with_time(
+                DifferentialState::new(0.02, 0.02),
+                Rotation2d::IDENTITY,
+                Duration::from_millis(i * 20),
+            );
+        }
+
+        estimator.add_vision_measurement(
+            Pose2d::new(0.5, 1.0, Rotation2d::IDENTITY),
+            Duration::from_millis(500),
+        );
+
+        assert_pose(estimator.get_pose(), 3.0, 0.0);
+    }
+
+    #[test]
+    fn converges_to_delayed_vision() {
+        let estimator = estimator();
+
+        let speed = 1.0;
+        let turn_rate = 0.5;
+        let dt = 0.02;
+        let latency = Duration::from_millis(60);
+
+        let true_pose = |time: Duration| {
+            let t = time.as_secs_f32();
+
+            Pose2d::default().exp(Twist2d::new(speed * t, 0.0, turn_rate * t))
+        };
+
+        // The wheels slip so the odometry only measures 90% of the distance driven
+        let left = 0.9 * (speed - turn_rate * TRACK_WIDTH / 2.0) * dt;
+        let right = 0.9 * (speed + turn_rate * TRACK_WIDTH / 2.0) * dt;
+
+        for i in 1..=250u32 {
+            let time = Duration::from_millis(20) * i;
+
+            estimator.update_with_time(
+                DifferentialState::new(left, right),
+                true_pose(time).rotation,
+                time,
+            );
+
+            // The camera sees the robot every 100ms with noise of up to 10cm, and the result
+            // arrives after the latency
+            if i % 5 == 0 {
+                let timestamp = time - latency;
+                let noise = Translation2d::new(
+                    0.1 * (i as f32 * 12.9898).sin(),
+                    0.1 * (i as f32 * 78.233).sin(),
+                );
+                let seen = true_pose(timestamp);
+
+                estimator.add_vision_measurement(
+                    Pose2d::from_parts(seen.translation + noise, seen.rotation),
+                    timestamp,
+                );
+            }
+        }
+
+        let end = true_pose(Duration::from_secs(5));
+
+        let odometry_error = estimator
+            .odometry()
+            .get_pose()
+            .translation
+            .distance(&end.translation);
+        let error = estimator.get_pose().translation.distance(&end.translation);
+
+        assert!(odometry_error > 0.3, "Odometry error was {odometry_error}");
+        assert!(error < 0.1, "Error was {error}");
+    }
+}
@@ -1,11 +1,14 @@
 use std::{
+    f32::consts::PI,
     marker::{ConstParamTy, PhantomData},
     time::Duration,
 };
 
-use super::{ConstFloat, Controller, State};
+use super::{pid::PidController, AtGoal, ConstFloat, Controller, State};
 
-#[derive(PartialEq, Eq, ConstParamTy)]
+/// The maximum velocity and acceleration of a profile. This can be used as a const generic for
+/// [TrapezoidProfile] or created at runtime for [ProfiledPid]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ConstParamTy)]
 pub struct Constraints {
     pub max_velocity: ConstFloat,
     pub max_acceleration: ConstFloat,
@@ -229,3 +232,358 @@ pub fn calculate_trapezoid_area(a: f32, b: f32, delta_x: f32) -> f32 {
 pub fn calculate_trapezoid_area_from_slope(a: f32, b: f32, v: f32) -> f32 {
     calculate_trapezoid_area(a, b, (a - b).abs() / v)
 }
+
+/// A [PidController] that follows a trapezoid profile to the goal instead of jumping straight to
+/// it. Unlike [TrapezoidProfile] the constraints can be changed at runtime, and the profile is
+/// advanced from the last setpoint each loop instead of being regenerated from the measured state,
+/// so noise in the measurement does not change the profile.
+///
+/// The output is only the PID output. Use [ProfiledPid::setpoint] to add a feedforward for the
+/// current point on the profile.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let mut controller = ProfiledPid::new(
+///     PidController::new(4.0, 0.0, 0.1),
+///     Constraints::new(2.0, 4.0),
+/// )
+/// .continuous_input();
+///
+/// loop {
+///     let output = controller.calculate(&turret.state()?, &State::new(target, 0.0));
+///     let feedforward = turret_feedforward.calculate(&controller.setpoint());
+///
+///     turret.set_voltage(Voltage::from_volts(output) + feedforward)?;
+///
+///     if controller.at_goal() {
+///         break;
+///     }
+///
+///     yield_now().await;
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ProfiledPid {
+    pid: PidController,
+    constraints: Constraints,
+    goal: State,
+    setpoint: State,
+    continuous: bool,
+    last_time: Option<Duration>,
+}
+
+impl ProfiledPid {
+    pub fn new(pid: PidController, constraints: Constraints) -> Self {
+        Self {
+            pid,
+            constraints,
+            goal: State::new(0.0, 0.0),
+            setpoint: State::new(0.0, 0.0),
+            continuous: false,
+            last_time: None,
+        }
+    }
+
+    /// Treat the positions as angles in radians and always take the shortest way to the goal, for
+    /// example for a turret or a swerve module
+    pub fn continuous_input(self) -> Self {
+        Self {
+            pid: self.pid.continuous_input(),
+            continuous: true,
+            ..self
+        }
+    }
+
+    /// Set how close the error and its rate of change must be to zero for
+    /// [ProfiledPid::at_goal]
+    pub fn tolerance(self, position: f32, velocity: f32) -> Self {
+        Self {
+            pid: self.pid.tolerance(position, velocity),
+            ..self
+        }
+    }
+
+    pub fn constraints(&self) -> Constraints {
+        self.constraints
+    }
+
+    /// Change the constraints. The profile continues from the current setpoint
+    pub fn set_constraints(&mut self, constraints: Constraints) {
+        self.constraints = constraints;
+    }
+
+    pub fn goal(&self) -> State {
+        self.goal
+    }
+
+    /// Get the current point on the profile that the PID controller is following
+    pub fn setpoint(&self) -> State {
+        self.setpoint
+    }
+
+    pub fn pid(&self) -> &PidController {
+        &self.pid
+    }
+
+    /// Get the PID controller, for example to change the gains
+    pub fn pid_mut(&mut self) -> &mut PidController {
+        &mut self.pid
+    }
+
+    /// Returns true if the profile has reached the goal and the measurement is within the
+    /// tolerance of it
+    pub fn at_goal(&self) -> bool {
+        self.pid.at_setpoint()
+            && (self.goal.position - self.setpoint.position).abs() <= f32::EPSILON
+            && (self.goal.velocity - self.setpoint.velocity).abs() <= f32::EPSILON
+    }
+
+    /// Restart the profile from `measurement`, for example when the mechanism is re-enabled
+    pub fn reset(&mut self, measurement: State) {
+        self.setpoint = measurement;
+        self.last_time = None;
+        self.pid.reset();
+    }
+}
+
+/// Get the state `dt` seconds along the trapezoid profile from `current` to `goal`
+fn step_profile(constraints: Constraints, dt: f32, current: State, goal: State) -> State {
+    let max_velocity = constraints.max_velocity.get();
+    let max_acceleration = constraints.max_acceleration.get();
+
+    // Solve the profile as if it was moving forwards and flip the result if it is not
+    let direction = if current.position > goal.position {
+        -1.0
+    } else {
+        1.0
+    };
+
+    let directed =
+        |state: State| State::new(state.position * direction, state.velocity * direction);
+
+    let mut current = directed(current);
+    let goal = directed(goal);
+
+    current.velocity = current.velocity.min(max_velocity);
+
+    // Treat the current and goal velocity as a truncated part of a profile that starts and ends
+    // at rest
+    let cutoff_begin = current.velocity / max_acceleration;
+    let cutoff_dist_begin = cutoff_begin.powi(2) * max_acceleration / 2.0;
+
+    let cutoff_end = goal.velocity / max_acceleration;
+    let cutoff_dist_end = cutoff_end.powi(2) * max_acceleration / 2.0;
+
+    let full_trapezoid_dist =
+        cutoff_dist_begin + (goal.position - current.position) + cutoff_dist_end;
+    let mut acceleration_time = max_velocity / max_acceleration;
+
+    let mut full_speed_dist = full_trapezoid_dist - acceleration_time.powi(2) * max_acceleration;
+
+    // The profile never reaches the maximum velocity
+    if full_speed_dist < 0.0 {
+        acceleration_time = (full_trapezoid_dist / max_acceleration).sqrt();
+        full_speed_dist = 0.0;
+    }
+
+    let end_accel = acceleration_time - cutoff_begin;
+    let end_full_speed = end_accel + full_speed_dist / max_velocity;
+    let end_decel = end_full_speed + acceleration_time - cutoff_end;
+
+    let result = if dt < end_accel {
+        State::new(
+            current.position + (current.velocity + dt * max_acceleration / 2.0) * dt,
+            current.velocity + dt * max_acceleration,
+        )
+    } else if dt < end_full_speed {
+        State::new(
+            current.position
+                + (current.velocity + end_accel * max_acceleration / 2.0) * end_accel
+                + max_velocity * (dt - end_accel),
+            max_velocity,
+        )
+    } else if dt <= end_decel {
+        let time_left = end_decel - dt;
+
+        State::new(
+            goal.position - (goal.velocity + time_left * max_acceleration / 2.0) * time_left,
+            goal.velocity + time_left * max_acceleration,
+        )
+    } else {
+        goal
+    };
+
+    directed(result)
+}
+
+/// Wrap an angle in radians to between -pi and pi
+fn wrap_angle(angle: f32) -> f32 {
+    angle - 2.0 * PI * ((angle + PI) / (2.0 * PI)).floor()
+}
+
+impl Controller for ProfiledPid {
+    type State = State;
+    type Output = f32;
+
+    fn calculate_with_time(&mut self, current: &State, target: &State, time: Duration) -> f32 {
+        let Some(last_time) = self.last_time.replace(time) else {
+            self.setpoint = *current;
+            self.goal = *target;
+
+            return self
+                .pid
+                .calculate_with_time(&current.position, &self.setpoint.position, time);
+        };
+
+        self.goal = *target;
+
+        if self.continuous {
+            // Move the goal and setpoint to within half a rotation of the measurement so the
+            // profile takes the shortest way around
+            self.goal.position =
+                current.position + wrap_angle(self.goal.position - current.position);
+            self.setpoint.position =
+                current.position + wrap_angle(self.setpoint.position - current.position);
+        }
+
+        self.setpoint = step_profile(
+            self.constraints,
+            time.saturating_sub(last_time).as_secs_f32(),
+            self.setpoint,
+            self.goal,
+        );
+
+        self.pid
+            .calculate_with_time(&current.position, &self.setpoint.position, time)
+    }
+}
+
+impl AtGoal for ProfiledPid {
+    fn at_goal(&self) -> bool {
+        ProfiledPid::at_goal(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-4;
+
+    fn assert_state(actual: State, position: f32, velocity: f32) {
+        assert!(
+            (actual.position - position).abs() < EPSILON,
+            "{} != {position}",
+            actual.position
+        );
+        assert!(
+            (actual.velocity - velocity).abs() < EPSILON,
+            "{} != {velocity}",
+            actual.velocity
+        );
+    }
+
+    fn controller() -> ProfiledPid {
+        ProfiledPid::new(
+            PidController::new(1.0, 0.0, 0.0),
+            Constraints::new(1.0, 1.0),
+        )
+    }
+
+    /// Run the controller every 20ms from `start` to `end` seconds with the mechanism perfectly
+    /// following the setpoint
+    fn follow(controller: &mut ProfiledPid, goal: State, start: u32, end: u32) {
+        for i in start..=end {
+            let current = controller.setpoint();
+
+            controller.calculate_with_time(&current, &goal, Duration::from_millis(20) * i);
+        }
+    }
+
+    #[test]
+    fn first_call_starts_from_measurement() {
+        let mut controller = controller();
+
+        let output = controller.calculate_with_time(
+            &State::new(1.0, 0.5),
+            &State::new(3.0, 0.0),
+            Duration::from_secs(2),
+        );
+
+        assert_state(controller.setpoint(), 1.0, 0.5);
+        assert_state(controller.goal(), 3.0, 0.0);
+        assert_eq!(output, 0.0);
+        assert!(!controller.at_goal());
+    }
+
+    #[test]
+    fn follows_profile_to_goal() {
+        let mut controller = controller();
+        let goal = State::new(10.0, 0.0);
+
+        controller.calculate_with_time(&State::new(0.0, 0.0), &goal, Duration::ZERO);
+
+        follow(&mut controller, goal, 1, 25);
+        assert_state(controller.setpoint(), 0.125, 0.5);
+
+        follow(&mut controller, goal, 26, 50);
+        assert_state(controller.setpoint(), 0.5, 1.0);
+
+        follow(&mut controller, goal, 51, 300);
+        assert_state(controller.setpoint(), 5.5, 1.0);
+        assert!(!controller.at_goal());
+
+        // The profile takes 11 seconds, accelerating and decelerating for 1 second each
+        follow(&mut controller, goal, 301, 600);
+        assert_state(controller.setpoint(), 10.0, 0.0);
+        assert!(controller.at_goal());
+    }
+
+    #[test]
+    fn profile_moves_backwards() {
+        let mut controller = controller();
+        let goal = State::new(-10.0, 0.0);
+
+        controller.calculate_with_time(&State::new(0.0, 0.0), &goal, Duration::ZERO);
+
+        follow(&mut controller, goal, 1, 50);
+        assert_state(controller.setpoint(), -0.5, -1.0);
+    }
+
+    #[test]
+    fn continuous_goal_takes_the_shortest_way() {
+        let mut controller = controller().continuous_input();
+        let goal = State::new(0.5, 0.0);
+
+        controller.calculate_with_time(&State::new(6.0, 0.0), &goal, Duration::ZERO);
+
+        follow(&mut controller, goal, 1, 1);
+
+        // Going up through 2pi is 0.78 radians instead of 5.5 radians backwards
+        assert_state(controller.goal(), 0.5 + 2.0 * PI, 0.0);
+        assert!(controller.setpoint().velocity > 0.0);
+        assert!(controller.setpoint().position > 6.0);
+
+        follow(&mut controller, goal, 2, 150);
+        assert_state(controller.setpoint(), 0.5 + 2.0 * PI, 0.0);
+        assert!(controller.at_goal());
+    }
+
+    #[test]
+    fn reset_restarts_from_measurement() {
+        let mut controller = controller();
+        let goal = State::new(10.0, 0.0);
+
+        controller.calculate_with_time(&State::new(0.0, 0.0), &goal, Duration::ZERO);
+        follow(&mut controller, goal, 1, 50);
+
+        controller.reset(State::new(2.0, 0.0));
+
+        assert_state(controller.setpoint(), 2.0, 0.0);
+
+        // The first call after the reset starts the profile, so it has been running for 0.98s
+        follow(&mut controller, goal, 51, 100);
+        assert_state(controller.setpoint(), 2.0 + 0.98 * 0.98 / 2.0, 0.98);
+    }
+}
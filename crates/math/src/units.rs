@@ -0,0 +1,531 @@
+//! Zero cost wrappers for physical quantities. Each quantity stores its value in SI base units
+//! (meters, radians, seconds, volts and amps) and can be created from or converted to any of
+//! its supported units.
+//!
+//! APIs that work with one kind of quantity, like the feedforward controllers and
+//! `MotorController::set_voltage`, take and return these types directly. [State](crate::State),
+//! [SwerveState](crate::kinematics::SwerveState) and the vectors used by
+//! [Kinematics](crate::kinematics::Kinematics) store plain SI numbers instead, because the same
+//! type holds either linear or rotational values, or either speeds or distances, and the
+//! estimators do matrix math on them. Convert at the boundary with their typed constructors and
+//! accessors, such as [State::linear](crate::State::linear),
+//! [SwerveState::from_speed](crate::kinematics::SwerveState::from_speed) and
+//! [Kinematics::inverse_speeds](crate::kinematics::Kinematics::inverse_speeds).
+//!
+//! ```rust
+//! # use math::units::UnitExt;
+//! let wheel_radius = 2.0.inches();
+//! let speed = 300.0.rpm().surface_speed(wheel_radius);
+//! let time_to_cross_field = 54.0.feet() / speed;
+//! ```
+
+use std::{
+    f32::consts::PI,
+    iter::Sum,
+    ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign},
+    time::Duration,
+};
+
+use crate::normalize_angle;
+
+macro_rules! quantity {
+    ($(#[$meta:meta])* $name:ident { $($from:ident / $to:ident = $factor:expr),+ $(,)? }) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+        #[repr(transparent)]
+        pub struct $name(f32);
+
+        impl $name {
+            pub const ZERO: Self = Self(0.0);
+
+            $(
+                pub fn $from(value: f32) -> Self {
+                    Self(value * $factor)
+                }
+
+                pub fn $to(&self) -> f32 {
+                    self.0 / $factor
+                }
+            )+
+
+            pub fn abs(self) -> Self {
+                Self(self.0.abs())
+            }
+
+            /// Returns 1 if the quantity is positive and -1 if it is negative
+            pub fn signum(self) -> f32 {
+                self.0.signum()
+            }
+
+            pub fn min(self, other: Self) -> Self {
+                Self(self.0.min(other.0))
+            }
+
+            pub fn max(self, other: Self) -> Self {
+                Self(self.0.max(other.0))
+            }
+
+            pub fn clamp(self, min: Self, max: Self) -> Self {
+                Self(self.0.clamp(min.0, max.0))
+            }
+        }
+
+        impl Add for $name {
+            type Output = Self;
+
+            fn add(self, rhs: Self) -> Self {
+                Self(self.0 + rhs.0)
+            }
+        }
+
+        impl AddAssign for $name {
+            fn add_assign(&mut self, rhs: Self) {
+                self.0 += rhs.0;
+            }
+        }
+
+        impl Sub for $name {
+            type Output = Self;
+
+            fn sub(self, rhs: Self) -> Self {
+                Self(self.0 - rhs.0)
+            }
+        }
+
+        impl SubAssign for $name {
+            fn sub_assign(&mut self, rhs: Self) {
+                self.0 -= rhs.0;
+            }
+        }
+
+        impl Neg for $name {
+            type Output = Self;
+
+            fn neg(self) -> Self {
+                Self(-self.0)
+            }
+        }
+
+        impl Mul<f32> for $name {
+            type Output = Self;
+
+            fn mul(self, rhs: f32) -> Self {
+                Self(self.0 * rhs)
+            }
+        }
+
+        impl Mul<$name> for f32 {
+            type Output = $name;
+
+            fn mul(self, rhs: $name) -> $name {
+                $name(self * rhs.0)
+            }
+        }
+
+        impl Div<f32> for $name {
+            type Output = Self;
+
+            fn div(self, rhs: f32) -> Self {
+                Self(self.0 / rhs)
+            }
+        }
+
+        /// Dividing two quantities of the same kind gives a unitless ratio
+        impl Div for $name {
+            type Output = f32;
+
+            fn div(self, rhs: Self) -> f32 {
+                self.0 / rhs.0
+            }
+        }
+
+        impl Sum for $name {
+            fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+                Self(iter.map(|value| value.0).sum())
+            }
+        }
+    };
+}
+
+/// Implement `numerator / denominator = quotient` along with the matching multiplications
+macro_rules! relation {
+    ($numerator:ident / $denominator:ident = $quotient:ident) => {
+        impl Div<$denominator> for $numerator {
+            type Output = $quotient;
+
+            fn div(self, rhs: $denominator) -> $quotient {
+                $quotient(self.0 / rhs.0)
+            }
+        }
+
+        impl Div<$quotient> for $numerator {
+            type Output = $denominator;
+
+            fn div(self, rhs: $quotient) -> $denominator {
+                $denominator(self.0 / rhs.0)
+            }
+        }
+
+        impl Mul<$denominator> for $quotient {
+            type Output = $numerator;
+
+            fn mul(self, rhs: $denominator) -> $numerator {
+                $numerator(self.0 * rhs.0)
+            }
+        }
+
+        impl Mul<$quotient> for $denominator {
+            type Output = $numerator;
+
+            fn mul(self, rhs: $quotient) -> $numerator {
+                $numerator(self.0 * rhs.0)
+            }
+        }
+    };
+}
+
+quantity!(
+    /// A distance, stored in meters
+    Length {
+        from_meters / meters = 1.0,
+        from_centimeters / centimeters = 0.01,
+        from_millimeters / millimeters = 0.001,
+        from_inches / inches = 0.0254,
+        from_feet / feet = 0.3048,
+    }
+);
+
+quantity!(
+    /// An angle, stored in radians. Positive angles are counterclockwise
+    Angle {
+        from_radians / radians = 1.0,
+        from_degrees / degrees = PI / 180.0,
+        from_rotations / rotations = 2.0 * PI,
+    }
+);
+
+quantity!(
+    /// A linear speed, stored in meters per second
+    LinearVelocity {
+        from_meters_per_second / meters_per_second = 1.0,
+        from_feet_per_second / feet_per_second = 0.3048,
+        from_inches_per_second / inches_per_second = 0.0254,
+    }
+);
+
+quantity!(
+    /// A rotational speed, stored in radians per second
+    AngularVelocity {
+        from_radians_per_second / radians_per_second = 1.0,
+        from_degrees_per_second / degrees_per_second = PI / 180.0,
+        from_rotations_per_second / rotations_per_second = 2.0 * PI,
+        from_rpm / rpm = 2.0 * PI / 60.0,
+    }
+);
+
+quantity!(
+    /// A linear acceleration, stored in meters per second squared
+    LinearAcceleration {
+        from_meters_per_second_squared / meters_per_second_squared = 1.0,
+        from_feet_per_second_squared / feet_per_second_squared = 0.3048,
+        from_gs / gs = 9.80665,
+    }
+);
+
+quantity!(
+    /// A rotational acceleration, stored in radians per second squared
+    AngularAcceleration {
+        from_radians_per_second_squared / radians_per_second_squared = 1.0,
+        from_degrees_per_second_squared / degrees_per_second_squared = PI / 180.0,
+        from_rotations_per_second_squared / rotations_per_second_squared = 2.0 * PI,
+    }
+);
+
+quantity!(
+    /// An electric potential, stored in volts
+    Voltage {
+        from_volts / volts = 1.0,
+        from_millivolts / millivolts = 0.001,
+    }
+);
+
+quantity!(
+    /// An electric current, stored in amps
+    Current {
+        from_amps / amps = 1.0,
+        from_milliamps / milliamps = 0.001,
+    }
+);
+
+quantity!(
+    /// A span of time, stored in seconds. This converts to and from [Duration], but unlike a
+    /// [Duration] it can be negative
+    Time {
+        from_seconds / seconds = 1.0,
+        from_milliseconds / milliseconds = 0.001,
+        from_microseconds / microseconds = 0.000001,
+        from_minutes / minutes = 60.0,
+    }
+);
+
+relation!(Length / Time = LinearVelocity);
+relation!(LinearVelocity / Time = LinearAcceleration);
+relation!(Angle / Time = AngularVelocity);
+relation!(AngularVelocity / Time = AngularAcceleration);
+
+impl Angle {
+    /// Get the angle of the vector (x, y)
+    pub fn atan2(y: f32, x: f32) -> Self {
+        Self(y.atan2(x))
+    }
+
+    pub fn sin(&self) -> f32 {
+        self.0.sin()
+    }
+
+    pub fn cos(&self) -> f32 {
+        self.0.cos()
+    }
+
+    pub fn tan(&self) -> f32 {
+        self.0.tan()
+    }
+
+    /// Constrain the angle to between 0 and 1 rotation. See [normalize_angle]
+    pub fn normalized(self) -> Self {
+        Self(normalize_angle(self.0))
+    }
+
+    /// Get the arc length of this angle on a circle with the given radius, for example the
+    /// distance a wheel travels when it turns this far
+    pub fn arc_length(&self, radius: Length) -> Length {
+        Length(self.0 * radius.0)
+    }
+}
+
+impl AngularVelocity {
+    /// Get the surface speed of a wheel with the given radius spinning at this speed
+    pub fn surface_speed(&self, radius: Length) -> LinearVelocity {
+        LinearVelocity(self.0 * radius.0)
+    }
+}
+
+impl LinearVelocity {
+    /// Get how fast a wheel with the given radius spins when its surface moves at this speed
+    pub fn wheel_speed(&self, radius: Length) -> AngularVelocity {
+        AngularVelocity(self.0 / radius.0)
+    }
+}
+
+impl Voltage {
+    /// The nominal voltage of the robot battery
+    pub const NOMINAL: Voltage = Voltage(12.0);
+
+    /// Convert a percent output between -1 and 1 to a voltage using the given battery voltage
+    pub fn from_percent(percent: f32, battery: Voltage) -> Self {
+        Self(percent * battery.0)
+    }
+
+    /// Get the percent output that produces this voltage with the given battery voltage
+    pub fn percent(&self, battery: Voltage) -> f32 {
+        self.0 / battery.0
+    }
+}
+
+impl From<Duration> for Time {
+    fn from(value: Duration) -> Self {
+        Self(value.as_secs_f32())
+    }
+}
+
+impl TryFrom<Time> for Duration {
+    type Error = std::time::TryFromFloatSecsError;
+
+    /// Fails if the time is negative or too large
+    fn try_from(value: Time) -> Result<Self, Self::Error> {
+        Duration::try_from_secs_f32(value.0)
+    }
+}
+
+/// Create quantities from numbers, for example `3.0.feet()` or `90.0.degrees()`
+pub trait UnitExt {
+    fn meters(self) -> Length;
+    fn inches(self) -> Length;
+    fn feet(self) -> Length;
+    fn radians(self) -> Angle;
+    fn degrees(self) -> Angle;
+    fn rotations(self) -> Angle;
+    fn meters_per_second(self) -> LinearVelocity;
+    fn radians_per_second(self) -> AngularVelocity;
+    fn rpm(self) -> AngularVelocity;
+    fn meters_per_second_squared(self) -> LinearAcceleration;
+    fn volts(self) -> Voltage;
+    fn amps(self) -> Current;
+    fn seconds(self) -> Time;
+    fn milliseconds(self) -> Time;
+}
+
+impl UnitExt for f32 {
+    fn meters(self) -> Length {
+        Length::from_meters(self)
+    }
+
+    fn inches(self) -> Length {
+        Length::from_inches(self)
+    }
+
+    fn feet(self) -> Length {
+        Length::from_feet(self)
+    }
+
+    fn radians(self) -> Angle {
+        Angle::from_radians(self)
+    }
+
+    fn degrees(self) -> Angle {
+        Angle::from_degrees(self)
+    }
+
+    fn rotations(self) -> Angle {
+        Angle::from_rotations(self)
+    }
+
+    fn meters_per_second(self) -> LinearVelocity {
+        LinearVelocity::from_meters_per_second(self)
+    }
+
+    fn radians_per_second(self) -> AngularVelocity {
+        AngularVelocity::from_radians_per_second(self)
+    }
+
+    fn rpm(self) -> AngularVelocity {
+        AngularVelocity::from_rpm(self)
+    }
+
+    fn meters_per_second_squared(self) -> LinearAcceleration {
+        LinearAcceleration::from_meters_per_second_squared(self)
+    }
+
+    fn volts(self) -> Voltage {
+        Voltage::from_volts(self)
+    }
+
+    fn amps(self) -> Current {
+        Current::from_amps(self)
+    }
+
+    fn seconds(self) -> Time {
+        Time::from_seconds(self)
+    }
+
+    fn milliseconds(self) -> Time {
+        Time::from_milliseconds(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{kinematics::SwerveState, State};
+
+    const EPSILON: f32 = 1e-4;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < EPSILON,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn units_round_trip() {
+        for value in [-3.5, 0.0, 0.25, 42.0] {
+            assert_close(Length::from_inches(value).inches(), value);
+            assert_close(Length::from_feet(value).feet(), value);
+            assert_close(Length::from_millimeters(value).millimeters(), value);
+            assert_close(Angle::from_degrees(value).degrees(), value);
+            assert_close(Angle::from_rotations(value).rotations(), value);
+            assert_close(
+                LinearVelocity::from_feet_per_second(value).feet_per_second(),
+                value,
+            );
+            assert_close(AngularVelocity::from_rpm(value).rpm(), value);
+            assert_close(LinearAcceleration::from_gs(value).gs(), value);
+            assert_close(Voltage::from_millivolts(value).millivolts(), value);
+            assert_close(Current::from_milliamps(value).milliamps(), value);
+            assert_close(Time::from_minutes(value).minutes(), value);
+        }
+    }
+
+    #[test]
+    fn units_convert_to_si() {
+        assert_close(1.0.inches().meters(), 0.0254);
+        assert_close(1.0.feet().inches(), 12.0);
+        assert_close(180.0.degrees().radians(), PI);
+        assert_close(1.0.rotations().degrees(), 360.0);
+        assert_close(60.0.rpm().rotations_per_second(), 1.0);
+        assert_close(60.0.rpm().radians_per_second(), 2.0 * PI);
+        assert_close(1500.0.milliseconds().seconds(), 1.5);
+        assert_close(Voltage::from_millivolts(500.0).volts(), 0.5);
+    }
+
+    #[test]
+    fn relations() {
+        let speed = 3.0.meters() / 2.0.seconds();
+        assert_close(speed.meters_per_second(), 1.5);
+        assert_close((speed * 2.0.seconds()).meters(), 3.0);
+        assert_close((3.0.meters() / speed).seconds(), 2.0);
+
+        let acceleration = speed / 0.5.seconds();
+        assert_close(acceleration.meters_per_second_squared(), 3.0);
+
+        let wheel = 2.0.inches();
+        let surface = 300.0.rpm().surface_speed(wheel);
+        assert_close(surface.wheel_speed(wheel).rpm(), 300.0);
+        assert_close(1.0.rotations().arc_length(wheel).inches(), 4.0 * PI);
+    }
+
+    #[test]
+    fn voltage_percent_round_trip() {
+        let battery = 11.5.volts();
+
+        assert_close(Voltage::from_percent(0.5, battery).volts(), 5.75);
+        assert_close(Voltage::from_percent(-0.3, battery).percent(battery), -0.3);
+        assert_close(Voltage::NOMINAL.percent(Voltage::NOMINAL), 1.0);
+    }
+
+    #[test]
+    fn time_converts_to_duration() {
+        let time = Time::from(Duration::from_millis(250));
+        assert_close(time.milliseconds(), 250.0);
+        assert_eq!(Duration::try_from(time), Ok(Duration::from_millis(250)));
+
+        assert!(Duration::try_from(-1.0.seconds()).is_err());
+    }
+
+    #[test]
+    fn angle_helpers() {
+        assert_close(Angle::atan2(1.0, 1.0).degrees(), 45.0);
+        assert_close((-90.0).degrees().normalized().degrees(), 270.0);
+        assert_close(30.0.degrees().sin(), 0.5);
+    }
+
+    #[test]
+    fn typed_states_round_trip() {
+        let state = State::linear(2.0.feet(), 3.0.meters_per_second());
+        assert_close(state.length().feet(), 2.0);
+        assert_close(state.linear_velocity().meters_per_second(), 3.0);
+
+        let state = State::angular(90.0.degrees(), 30.0.rpm());
+        assert_close(state.position, PI / 2.0);
+        assert_close(state.angle().degrees(), 90.0);
+        assert_close(state.angular_velocity().rpm(), 30.0);
+
+        let module = SwerveState::from_speed(45.0.degrees(), 10.0.feet() / 1.0.seconds());
+        assert_close(module.speed().feet_per_second(), 10.0);
+
+        let module = SwerveState::from_distance(45.0.degrees(), 6.0.inches());
+        assert_close(module.distance().inches(), 6.0);
+        assert_close(module.get_angle().degrees(), 45.0);
+    }
+}
@@ -4,6 +4,7 @@ use bitflags::bitflags;
 use io::IO;
 use parking_lot::Mutex;
 use protocol::{AHRSPosUpdate, AHRSUpdateBase, BoardID, GyroUpdate};
+use robotrs::math::units::Angle;
 use thiserror::Error;
 use tracing::warn;
 
@@ -104,11 +105,17 @@ impl NavX {
         self.data.lock().clone()
     }
 
-    /// Returned in cw+ and degrees, aka the opposite of what we want
+    /// Returned in cw+ and degrees, aka the opposite of what we want. See [NavX::rotation]
     pub fn heading(&self) -> f32 {
         self.data.lock().base.yaw
     }
 
+    /// The heading of the robot, counterclockwise positive like the rest of the WPILib
+    /// coordinate system
+    pub fn rotation(&self) -> Angle {
+        Angle::from_degrees(-self.heading())
+    }
+
     pub fn get_config(&self) -> Config {
         self.config.lock().clone()
     }
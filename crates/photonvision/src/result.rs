@@ -1,6 +1,7 @@
 use std::{io::Read, time::Duration};
 
 use nalgebra::{Quaternion, Translation3};
+use robotrs::math::geometry::{Pose3d, Rotation3d, Transform3d, Translation3d};
 
 use crate::decode::{decode_f64, decode_i16, decode_i32, decode_u8};
 
@@ -31,6 +32,18 @@ impl PipelineResult {
     pub fn timestamp(&self) -> Duration {
         Duration::from_secs_f64(self.timestamp)
     }
+
+    /// Get the pose of the robot on the field from the multi-tag estimate, if there is one.
+    /// `robot_to_camera` is the position of the camera relative to the center of the robot
+    pub fn estimated_pose(&self, robot_to_camera: Transform3d) -> Option<Pose3d> {
+        let estimate = self.position_estimate.estimate.as_ref()?;
+
+        Some(
+            Pose3d::default()
+                .transform_by(&Transform3d::from(&estimate.best))
+                .transform_by(&robot_to_camera.inverse()),
+        )
+    }
 }
 
 #[derive(Debug)]
@@ -107,6 +120,24 @@ impl Transform {
     }
 }
 
+impl From<&Transform> for Transform3d {
+    fn from(value: &Transform) -> Self {
+        Transform3d::new(
+            Translation3d::new(
+                value.translation.x as f32,
+                value.translation.y as f32,
+                value.translation.z as f32,
+            ),
+            Rotation3d::from_wxyz(
+                value.rotation.w as f32,
+                value.rotation.i as f32,
+                value.rotation.j as f32,
+                value.rotation.k as f32,
+            ),
+        )
+    }
+}
+
 #[derive(Debug)]
 pub struct PositionEstimateResult {
     pub apriltag_ids: Vec<i16>,
@@ -2,6 +2,7 @@ use encoder::{absolute::SparkMaxAbsoluteEncoder, relative::SparkMaxRelativeEncod
 use error::REVError;
 use robotrs::{
     control::ControlSafe,
+    math::units::Voltage,
     motor::{MotorController, SetIdleMode},
 };
 use std::{
@@ -296,8 +297,8 @@ impl MotorController for SparkMax {
         self.set(value)
     }
 
-    fn set_voltage(&mut self, value: f32) -> Result<(), Self::Error> {
-        self.set_reference(value, ControlType::Voltage)
+    fn set_voltage(&mut self, value: Voltage) -> Result<(), Self::Error> {
+        self.set_reference(value.volts(), ControlType::Voltage)
     }
 
     fn set_inverted(&mut self, is_inverted: bool) -> Result<(), Self::Error> {
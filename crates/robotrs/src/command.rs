@@ -6,6 +6,7 @@ use std::{
 
 use anyhow::bail;
 use futures::{task::waker_ref, Future};
+use tracing::error;
 
 use crate::{queue_waker, waker::SingleWaker};
 
@@ -17,8 +18,9 @@ pub trait Command {
     fn start(&mut self) -> anyhow::Result<()>;
     /// Runs for every tick of the scheduler
     fn execute(&mut self) -> anyhow::Result<()>;
-    /// Runs when the command ends
-    fn end(&mut self) -> anyhow::Result<()>;
+    /// Runs when the command ends. `interrupted` is true if the command was stopped before
+    /// [Command::is_finished] returned true, for example because its future was dropped
+    fn end(&mut self, interrupted: bool) -> anyhow::Result<()>;
     /// Runs for every tick and returns true if the command is complete
     fn is_finished(&mut self) -> anyhow::Result<bool>;
 }
@@ -36,36 +38,88 @@ impl<C: Command + Unpin> ToFuture for C {
     fn to_future(self) -> CommandFuture<C> {
         CommandFuture {
             command: self,
-            started: false,
-            stopped: false,
+            state: CommandState::NotStarted,
         }
     }
 }
 
-/// A future that runs a command
+/// Where a [CommandFuture] is in the lifecycle of its command
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CommandState {
+    /// [Command::start] has not been called yet
+    NotStarted,
+    /// [Command::start] has been called, but [Command::end] has not
+    Running,
+    /// [Command::end] has been called or [Command::start] failed. The command will not be run
+    /// again
+    Finished,
+}
+
+/// A future that runs a command. If this future is dropped while the command is running,
+/// [Command::end] is called with `interrupted` set to true.
 pub struct CommandFuture<C: Command + Unpin> {
     command: C,
-    started: bool,
-    stopped: bool,
+    state: CommandState,
 }
 
 impl<C: Command + Unpin> CommandFuture<C> {
+    /// Get the current lifecycle state of the command
+    pub fn state(&self) -> CommandState {
+        self.state
+    }
+
     fn failable_poll(&mut self, waker: Waker) -> anyhow::Result<Poll<()>> {
-        if !self.started {
-            self.command.start()?;
+        match self.state {
+            CommandState::NotStarted => {
+                if let Err(err) = self.command.start() {
+                    // The command never ran, so there is nothing to end
+                    self.state = CommandState::Finished;
+                    return Err(err);
+                }
+
+                self.state = CommandState::Running;
+            }
+            CommandState::Running => {}
+            CommandState::Finished => bail!("Polled a command future after it completed"),
         }
 
-        self.command.execute()?;
+        let finished: anyhow::Result<bool> = try {
+            self.command.execute()?;
+            self.command.is_finished()?
+        };
 
-        if self.command.is_finished()? {
-            self.command.end()?;
-            self.stopped = true;
+        match finished {
+            Ok(true) => {
+                self.state = CommandState::Finished;
+                self.command.end(false)?;
 
-            Ok(Poll::Ready(()))
-        } else {
-            queue_waker(waker);
+                Ok(Poll::Ready(()))
+            }
+            Ok(false) => {
+                queue_waker(waker);
+
+                Ok(Poll::Pending)
+            }
+            Err(err) => {
+                self.interrupt();
 
-            Ok(Poll::Pending)
+                Err(err)
+            }
+        }
+    }
+
+    /// End the command as interrupted if it is running. Errors are reported through tracing
+    /// because there is no caller to return them to.
+    fn interrupt(&mut self) {
+        if self.state == CommandState::Running {
+            self.state = CommandState::Finished;
+
+            if let Err(err) = self.command.end(true) {
+                error!(
+                    "An error occurred while ending an interrupted command: {}",
+                    err
+                );
+            }
         }
     }
 }
@@ -94,9 +148,7 @@ impl<C: Command + Unpin> Future for CommandFuture<C> {
 
 impl<C: Command + Unpin> Drop for CommandFuture<C> {
     fn drop(&mut self) {
-        if self.started && !self.stopped {
-            self.command.end().unwrap();
-        }
+        self.interrupt();
     }
 }
 
@@ -138,7 +190,7 @@ impl<F: Future<Output = anyhow::Result<()>>> Command for FutureCommand<F> {
         }
     }
 
-    fn end(&mut self) -> anyhow::Result<()> {
+    fn end(&mut self, _interrupted: bool) -> anyhow::Result<()> {
         Ok(())
     }
 
@@ -166,3 +218,417 @@ impl<F: Future<Output = anyhow::Result<()>>> ToCommand for F {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::RefCell,
+        future::Future,
+        pin::Pin,
+        rc::Rc,
+        task::{Context, Poll},
+    };
+
+    use anyhow::anyhow;
+    use futures::task::noop_waker;
+
+    use super::{ext::CommandExt, Command, CommandState, ToCommand, ToFuture};
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Event {
+        Start,
+        Execute,
+        End { interrupted: bool },
+    }
+
+    type Log = Rc<RefCell<Vec<(&'static str, Event)>>>;
+
+    /// A command that finishes after it has executed `ticks` times and records every call
+    struct TestCommand {
+        name: &'static str,
+        log: Log,
+        ticks: usize,
+        executed: usize,
+        fail_start: bool,
+        fail_execute: bool,
+        fail_end: bool,
+    }
+
+    impl TestCommand {
+        fn new(name: &'static str, log: &Log, ticks: usize) -> Self {
+            Self {
+                name,
+                log: log.clone(),
+                ticks,
+                executed: 0,
+                fail_start: false,
+                fail_execute: false,
+                fail_end: false,
+            }
+        }
+
+        fn record(&self, event: Event) {
+            self.log.borrow_mut().push((self.name, event));
+        }
+    }
+
+    impl Command for TestCommand {
+        fn start(&mut self) -> anyhow::Result<()> {
+            self.record(Event::Start);
+
+            if self.fail_start {
+                return Err(anyhow!("start failed"));
+            }
+
+            Ok(())
+        }
+
+        fn execute(&mut self) -> anyhow::Result<()> {
+            self.record(Event::Execute);
+            self.executed += 1;
+
+            if self.fail_execute {
+                return Err(anyhow!("execute failed"));
+            }
+
+            Ok(())
+        }
+
+        fn end(&mut self, interrupted: bool) -> anyhow::Result<()> {
+            self.record(Event::End { interrupted });
+
+            if self.fail_end {
+                return Err(anyhow!("end failed"));
+            }
+
+            Ok(())
+        }
+
+        fn is_finished(&mut self) -> anyhow::Result<bool> {
+            Ok(self.executed >= self.ticks)
+        }
+    }
+
+    fn poll<F: Future + Unpin>(future: &mut F) -> Poll<F::Output> {
+        Pin::new(future).poll(&mut Context::from_waker(&noop_waker()))
+    }
+
+    /// Poll the future until it completes, failing if it takes more than `max_polls`
+    fn run<F: Future + Unpin>(future: &mut F, max_polls: usize) -> F::Output {
+        for _ in 0..max_polls {
+            if let Poll::Ready(output) = poll(future) {
+                return output;
+            }
+        }
+
+        panic!("Future did not complete within {max_polls} polls");
+    }
+
+    fn events(log: &Log) -> Vec<(&'static str, Event)> {
+        log.borrow().clone()
+    }
+
+    #[test]
+    fn runs_to_completion() {
+        let log = Log::default();
+        let mut future = TestCommand::new("a", &log, 3).to_future();
+
+        assert_eq!(future.state(), CommandState::NotStarted);
+        assert!(poll(&mut future).is_pending());
+        assert_eq!(future.state(), CommandState::Running);
+        assert!(poll(&mut future).is_pending());
+        assert!(matches!(poll(&mut future), Poll::Ready(Ok(()))));
+        assert_eq!(future.state(), CommandState::Finished);
+
+        drop(future);
+
+        assert_eq!(
+            events(&log),
+            [
+                ("a", Event::Start),
+                ("a", Event::Execute),
+                ("a", Event::Execute),
+                ("a", Event::Execute),
+                ("a", Event::End { interrupted: false }),
+            ]
+        );
+    }
+
+    #[test]
+    fn poll_after_completion_errors() {
+        let log = Log::default();
+        let mut future = TestCommand::new("a", &log, 1).to_future();
+
+        assert!(matches!(poll(&mut future), Poll::Ready(Ok(()))));
+        assert!(matches!(poll(&mut future), Poll::Ready(Err(_))));
+        assert_eq!(log.borrow().len(), 3);
+    }
+
+    #[test]
+    fn drop_while_running_interrupts() {
+        let log = Log::default();
+        let mut future = TestCommand::new("a", &log, 10).to_future();
+
+        assert!(poll(&mut future).is_pending());
+        assert!(poll(&mut future).is_pending());
+
+        drop(future);
+
+        assert_eq!(
+            events(&log),
+            [
+                ("a", Event::Start),
+                ("a", Event::Execute),
+                ("a", Event::Execute),
+                ("a", Event::End { interrupted: true }),
+            ]
+        );
+    }
+
+    #[test]
+    fn drop_before_start_does_nothing() {
+        let log = Log::default();
+
+        drop(TestCommand::new("a", &log, 1).to_future());
+
+        assert!(events(&log).is_empty());
+    }
+
+    #[test]
+    fn start_error_does_not_end() {
+        let log = Log::default();
+        let mut command = TestCommand::new("a", &log, 1);
+        command.fail_start = true;
+
+        let mut future = command.to_future();
+
+        match poll(&mut future) {
+            Poll::Ready(Err(err)) => assert_eq!(err.to_string(), "start failed"),
+            _ => panic!("Expected the start error"),
+        }
+
+        assert_eq!(future.state(), CommandState::Finished);
+
+        drop(future);
+
+        assert_eq!(events(&log), [("a", Event::Start)]);
+    }
+
+    #[test]
+    fn execute_error_interrupts() {
+        let log = Log::default();
+        let mut command = TestCommand::new("a", &log, 3);
+        command.fail_execute = true;
+
+        let mut future = command.to_future();
+
+        match poll(&mut future) {
+            Poll::Ready(Err(err)) => assert_eq!(err.to_string(), "execute failed"),
+            _ => panic!("Expected the execute error"),
+        }
+
+        drop(future);
+
+        assert_eq!(
+            events(&log),
+            [
+                ("a", Event::Start),
+                ("a", Event::Execute),
+                ("a", Event::End { interrupted: true }),
+            ]
+        );
+    }
+
+    #[test]
+    fn end_error_on_completion_is_returned() {
+        let log = Log::default();
+        let mut command = TestCommand::new("a", &log, 1);
+        command.fail_end = true;
+
+        let mut future = command.to_future();
+
+        match poll(&mut future) {
+            Poll::Ready(Err(err)) => assert_eq!(err.to_string(), "end failed"),
+            _ => panic!("Expected the end error"),
+        }
+
+        drop(future);
+
+        assert_eq!(
+            events(&log).last(),
+            Some(&("a", Event::End { interrupted: false }))
+        );
+    }
+
+    #[test]
+    fn end_error_on_drop_does_not_panic() {
+        let log = Log::default();
+        let mut command = TestCommand::new("a", &log, 10);
+        command.fail_end = true;
+
+        let mut future = command.to_future();
+
+        assert!(poll(&mut future).is_pending());
+
+        drop(future);
+
+        assert_eq!(
+            events(&log).last(),
+            Some(&("a", Event::End { interrupted: true }))
+        );
+    }
+
+    #[test]
+    fn chain_runs_in_order() {
+        let log = Log::default();
+        let mut future = TestCommand::new("a", &log, 2)
+            .chain(TestCommand::new("b", &log, 1))
+            .to_future();
+
+        assert!(run(&mut future, 10).is_ok());
+
+        drop(future);
+
+        assert_eq!(
+            events(&log),
+            [
+                ("a", Event::Start),
+                ("a", Event::Execute),
+                ("a", Event::Execute),
+                ("a", Event::End { interrupted: false }),
+                ("b", Event::Start),
+                ("b", Event::Execute),
+                ("b", Event::End { interrupted: false }),
+            ]
+        );
+    }
+
+    #[test]
+    fn chain_drop_interrupts_current_command() {
+        let log = Log::default();
+        let mut future = TestCommand::new("a", &log, 1)
+            .chain(TestCommand::new("b", &log, 10))
+            .to_future();
+
+        for _ in 0..3 {
+            assert!(poll(&mut future).is_pending());
+        }
+
+        drop(future);
+
+        let events = events(&log);
+
+        assert!(events.contains(&("a", Event::End { interrupted: false })));
+        assert_eq!(
+            events.last(),
+            Some(&("b", Event::End { interrupted: true }))
+        );
+        assert_eq!(
+            events
+                .iter()
+                .filter(|(_, event)| matches!(event, Event::End { .. }))
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn parallel_waits_for_both() {
+        let log = Log::default();
+        let mut future = TestCommand::new("a", &log, 1)
+            .parallel(TestCommand::new("b", &log, 3))
+            .to_future();
+
+        assert!(poll(&mut future).is_pending());
+        assert!(poll(&mut future).is_pending());
+        assert!(matches!(poll(&mut future), Poll::Ready(Ok(()))));
+
+        drop(future);
+
+        let events = events(&log);
+
+        assert_eq!(
+            events
+                .iter()
+                .filter(|(name, _)| *name == "b")
+                .filter(|(_, event)| *event == Event::Execute)
+                .count(),
+            3
+        );
+        assert!(events.contains(&("a", Event::End { interrupted: false })));
+        assert!(events.contains(&("b", Event::End { interrupted: false })));
+        assert!(!events.contains(&("a", Event::End { interrupted: true })));
+        assert!(!events.contains(&("b", Event::End { interrupted: true })));
+    }
+
+    #[test]
+    fn parallel_drop_interrupts_both() {
+        let log = Log::default();
+        let mut future = TestCommand::new("a", &log, 10)
+            .parallel(TestCommand::new("b", &log, 10))
+            .to_future();
+
+        assert!(poll(&mut future).is_pending());
+
+        drop(future);
+
+        let events = events(&log);
+
+        assert!(events.contains(&("a", Event::End { interrupted: true })));
+        assert!(events.contains(&("b", Event::End { interrupted: true })));
+    }
+
+    #[test]
+    fn race_interrupts_loser() {
+        let log = Log::default();
+        let mut future = TestCommand::new("a", &log, 1)
+            .race(TestCommand::new("b", &log, 10))
+            .to_future();
+
+        assert!(run(&mut future, 10).is_ok());
+
+        drop(future);
+
+        let events = events(&log);
+
+        assert!(events.contains(&("a", Event::End { interrupted: false })));
+        assert!(events.contains(&("b", Event::End { interrupted: true })));
+    }
+
+    #[test]
+    fn until_interrupts_inner_command() {
+        let log = Log::default();
+        let mut polls = 0;
+        let mut future = TestCommand::new("a", &log, 10)
+            .until(move || {
+                polls += 1;
+                Ok(polls >= 2)
+            })
+            .to_future();
+
+        assert!(run(&mut future, 10).is_ok());
+
+        drop(future);
+
+        assert_eq!(
+            events(&log).last(),
+            Some(&("a", Event::End { interrupted: true }))
+        );
+    }
+
+    #[test]
+    fn future_command_propagates_errors() {
+        let mut future = async { Ok(()) }.to_command().to_future();
+
+        assert!(run(&mut future, 1).is_ok());
+
+        let mut future = async { Err(anyhow!("future failed")) }
+            .to_command()
+            .to_future();
+
+        match run(&mut future, 1) {
+            Err(err) => assert_eq!(err.to_string(), "future failed"),
+            Ok(()) => panic!("Expected the future error"),
+        }
+    }
+}
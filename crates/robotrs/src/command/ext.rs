@@ -8,6 +8,7 @@ pub trait CommandExt: Command + Sized {
         UntilCommand {
             is_finished,
             command: self,
+            command_finished: false,
         }
     }
 
@@ -20,7 +21,9 @@ pub trait CommandExt: Command + Sized {
     fn race<C: Command>(self, other: C) -> RaceCommand<Self, C> {
         RaceCommand {
             command1: self,
+            command1_finished: false,
             command2: other,
+            command2_finished: false,
         }
     }
 
@@ -48,7 +51,9 @@ where
     C2: Command,
 {
     command1: C1,
+    command1_finished: bool,
     command2: C2,
+    command2_finished: bool,
 }
 
 impl<C1, C2> Command for RaceCommand<C1, C2>
@@ -70,15 +75,19 @@ where
         Ok(())
     }
 
-    fn end(&mut self) -> anyhow::Result<()> {
-        self.command1.end()?;
-        self.command2.end()?;
+    fn end(&mut self, interrupted: bool) -> anyhow::Result<()> {
+        // The command that lost the race is always interrupted
+        self.command1.end(interrupted || !self.command1_finished)?;
+        self.command2.end(interrupted || !self.command2_finished)?;
 
         Ok(())
     }
 
     fn is_finished(&mut self) -> anyhow::Result<bool> {
-        Ok(self.command1.is_finished()? || self.command2.is_finished()?)
+        self.command1_finished = self.command1.is_finished()?;
+        self.command2_finished = !self.command1_finished && self.command2.is_finished()?;
+
+        Ok(self.command1_finished || self.command2_finished)
     }
 }
 
@@ -144,15 +153,19 @@ where
         }
     }
 
-    fn end(&mut self) -> anyhow::Result<()> {
+    fn end(&mut self, interrupted: bool) -> anyhow::Result<()> {
+        // Both commands were already ended as they finished
         if self.command1_done && self.command2_done {
-            Err(anyhow!("Command already ended"))
+            Ok(())
         } else if self.command1_done {
-            self.command2.end()
+            self.command2.end(interrupted)
         } else if self.command2_done {
-            self.command1.end()
+            self.command1.end(interrupted)
         } else {
-            match (self.command1.end(), self.command2.end()) {
+            match (
+                self.command1.end(interrupted),
+                self.command2.end(interrupted),
+            ) {
                 (Ok(_), Ok(_)) => Ok(()),
                 (Ok(_), Err(_)) => {
                     self.command2_done = true;
@@ -172,7 +185,7 @@ where
             Ok(true)
         } else if !self.command1_done && self.command1.is_finished().unwrap_or(true) {
             self.command1_done = true;
-            if let Err(err) = self.command1.end() {
+            if let Err(err) = self.command1.end(false) {
                 if self.command2_done {
                     Err(err)
                 } else {
@@ -183,7 +196,7 @@ where
             }
         } else if !self.command2_done && self.command2.is_finished().unwrap_or(true) {
             self.command2_done = true;
-            if let Err(err) = self.command2.end() {
+            if let Err(err) = self.command2.end(false) {
                 if self.command1_done {
                     Err(err)
                 } else {
@@ -221,7 +234,7 @@ where
         if self.command1_done {
             self.command2.execute()
         } else if self.command1.is_finished()? {
-            self.command1.end()?;
+            self.command1.end(false)?;
             self.command1_done = true;
             self.command2.start()
         } else {
@@ -229,16 +242,20 @@ where
         }
     }
 
-    fn end(&mut self) -> anyhow::Result<()> {
+    fn end(&mut self, interrupted: bool) -> anyhow::Result<()> {
         if self.command1_done {
-            self.command2.end()
+            self.command2.end(interrupted)
         } else {
-            self.command1.end()
+            self.command1.end(interrupted)
         }
     }
 
     fn is_finished(&mut self) -> anyhow::Result<bool> {
-        Ok(self.command1.is_finished()? || self.command1.is_finished()?)
+        if self.command1_done {
+            self.command2.is_finished()
+        } else {
+            Ok(false)
+        }
     }
 }
 
@@ -248,6 +265,7 @@ impl<C: Command> CommandExt for C {}
 pub struct UntilCommand<C: Command, F: FnMut() -> anyhow::Result<bool>> {
     is_finished: F,
     command: C,
+    command_finished: bool,
 }
 
 impl<C: Command, F: FnMut() -> anyhow::Result<bool>> Command for UntilCommand<C, F> {
@@ -259,12 +277,15 @@ impl<C: Command, F: FnMut() -> anyhow::Result<bool>> Command for UntilCommand<C,
         self.command.execute()
     }
 
-    fn end(&mut self) -> anyhow::Result<()> {
-        self.command.end()
+    fn end(&mut self, interrupted: bool) -> anyhow::Result<()> {
+        // Stopping because of the predicate interrupts the inner command
+        self.command.end(interrupted || !self.command_finished)
     }
 
     fn is_finished(&mut self) -> anyhow::Result<bool> {
-        Ok(self.command.is_finished()? || (self.is_finished)()?)
+        self.command_finished = self.command.is_finished()?;
+
+        Ok(self.command_finished || (self.is_finished)()?)
     }
 }
 
@@ -288,11 +309,11 @@ impl<C: Command> Command for FusedCommand<C> {
         }
     }
 
-    fn end(&mut self) -> anyhow::Result<()> {
+    fn end(&mut self, interrupted: bool) -> anyhow::Result<()> {
         if !self.1 {
             self.1 = true;
 
-            self.0.end()
+            self.0.end(interrupted)
         } else {
             Ok(())
         }
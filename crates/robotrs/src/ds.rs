@@ -69,7 +69,12 @@ static POLL_SPAN: LazyLock<Span> = LazyLock::new(|| span!(Level::TRACE, "ds stat
 fn check_state() {
     let _span_guard = POLL_SPAN.enter();
     let word = get_control_word().unwrap();
-    let state = State::from_control_word(&word);
+
+    update_state(State::from_control_word(&word));
+}
+
+/// Set the current state, waking anything waiting for a state change if it changed
+pub(crate) fn update_state(state: State) {
     let mut current_state = CURRENT_STATE.lock();
 
     if *current_state != state {
@@ -9,11 +9,17 @@ pub mod all;
 pub mod any;
 pub mod axis;
 pub mod button;
+pub mod combinators;
 pub mod controller;
 pub mod ext;
 pub mod joystick;
 pub mod pov;
-mod reactor;
+pub(crate) mod reactor;
+pub mod rumble;
+pub mod shaping;
+pub mod source;
+pub mod stick;
+pub mod stream;
 
 /// A generic async trigger
 pub trait Trigger {
@@ -27,6 +33,12 @@ pub trait Trigger {
 pub trait ReleaseTrigger: Trigger {
     /// Wait for the falling edge of the trigger. This returns early if an error occurs
     fn wait_for_release(&mut self) -> impl Future<Output = Result<Self::Output, Self::Error>>;
+
+    /// Whether the trigger is currently active, or [None] if it can't be known without waiting.
+    /// Combinators use this to stay in sync with triggers that are already active
+    fn is_active(&self) -> Option<bool> {
+        None
+    }
 }
 
 // TODO: Maybe make this clonable?
@@ -124,4 +136,8 @@ impl<E: 'static> ReleaseTrigger for PollFuture<E> {
             self.state_change.listen().await;
         }
     }
+
+    fn is_active(&self) -> Option<bool> {
+        Some(self.state.get())
+    }
 }
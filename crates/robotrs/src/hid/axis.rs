@@ -2,7 +2,9 @@ use hal_sys::HAL_JoystickAxes;
 
 use super::{
     joystick::Joystick,
-    reactor::{add_trigger, remove_trigger, set_target, wait_for_released, wait_for_triggered},
+    reactor::{
+        add_trigger, is_active, remove_trigger, set_target, wait_for_released, wait_for_triggered,
+    },
     ReleaseTrigger, Trigger,
 };
 
@@ -14,7 +16,8 @@ pub(super) fn get_axis(data: &HAL_JoystickAxes, index: u32) -> Option<f32> {
     }
 }
 
-/// A target that defines when an [Axis] should trigger
+/// A target that defines when an [Axis] should trigger. The hysteresis variants use a separate
+/// threshold for releasing, so an axis resting near the threshold does not chatter.
 #[derive(Copy, Clone, Debug)]
 pub enum AxisTarget {
     /// Activates when the axis moves farther than the given value. Direction doesn't matter
@@ -25,15 +28,50 @@ pub enum AxisTarget {
     Up(f32),
     /// Activates when the axis moves lower than the given value
     Down(f32),
+    /// Activates when the axis is between the two values, inclusive
+    Range(f32, f32),
+    /// Activates when the axis moves farther than `activate` and releases when it moves closer
+    /// than `release`. Direction doesn't matter
+    AwayHysteresis { activate: f32, release: f32 },
+    /// Activates when the axis moves higher than `activate` and releases when it moves lower
+    /// than `release`
+    UpHysteresis { activate: f32, release: f32 },
+    /// Activates when the axis moves lower than `activate` and releases when it moves higher
+    /// than `release`
+    DownHysteresis { activate: f32, release: f32 },
 }
 
 impl AxisTarget {
-    pub(super) fn is_active(&self, value: f32) -> bool {
-        match self {
-            AxisTarget::Away(dist) => value.abs() > *dist,
-            AxisTarget::Within(dist) => value.abs() < *dist,
-            AxisTarget::Down(target) => value < *target,
-            AxisTarget::Up(target) => value > *target,
+    /// Whether the target is active. `active` is whether it was active on the last tick, which
+    /// is used for hysteresis
+    pub(super) fn is_active(&self, value: f32, active: bool) -> bool {
+        match *self {
+            AxisTarget::Away(dist) => value.abs() > dist,
+            AxisTarget::Within(dist) => value.abs() < dist,
+            AxisTarget::Down(target) => value < target,
+            AxisTarget::Up(target) => value > target,
+            AxisTarget::Range(low, high) => (low..=high).contains(&value),
+            AxisTarget::AwayHysteresis { activate, release } => {
+                if active {
+                    value.abs() >= release
+                } else {
+                    value.abs() > activate
+                }
+            }
+            AxisTarget::UpHysteresis { activate, release } => {
+                if active {
+                    value >= release
+                } else {
+                    value > activate
+                }
+            }
+            AxisTarget::DownHysteresis { activate, release } => {
+                if active {
+                    value <= release
+                } else {
+                    value < activate
+                }
+            }
         }
     }
 }
@@ -98,4 +136,8 @@ impl ReleaseTrigger for Axis {
     async fn wait_for_release(&mut self) -> Result<(), ()> {
         wait_for_released(self.reactor_idx).await
     }
+
+    fn is_active(&self) -> Option<bool> {
+        is_active(self.reactor_idx)
+    }
 }
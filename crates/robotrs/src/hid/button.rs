@@ -2,7 +2,9 @@ use hal_sys::HAL_JoystickButtons;
 
 use super::{
     joystick::Joystick,
-    reactor::{add_trigger, remove_trigger, set_target, wait_for_released, wait_for_triggered},
+    reactor::{
+        add_trigger, is_active, remove_trigger, set_target, wait_for_released, wait_for_triggered,
+    },
     ReleaseTrigger, Trigger,
 };
 
@@ -92,4 +94,8 @@ impl ReleaseTrigger for Button {
     async fn wait_for_release(&mut self) -> Result<(), ()> {
         wait_for_released(self.reactor_idx).await
     }
+
+    fn is_active(&self) -> Option<bool> {
+        is_active(self.reactor_idx)
+    }
 }
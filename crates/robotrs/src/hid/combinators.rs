@@ -0,0 +1,692 @@
+use std::time::Duration;
+
+use futures::future::Either;
+use futures_concurrency::future::Race;
+use tracing::{instrument, trace};
+
+use crate::time::delay;
+
+use super::{ReleaseTrigger, Trigger};
+
+/// Which edges of a trigger a [Debounce] trigger filters
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DebounceType {
+    /// Only activate once the inner trigger has been active for the whole duration
+    Rising,
+    /// Only release once the inner trigger has been released for the whole duration
+    Falling,
+    /// Filter both the rising and falling edges
+    Both,
+}
+
+impl DebounceType {
+    fn rising(&self) -> bool {
+        matches!(self, Self::Rising | Self::Both)
+    }
+
+    fn falling(&self) -> bool {
+        matches!(self, Self::Falling | Self::Both)
+    }
+}
+
+/// An extension trait that adds logic combinators to all sized [Trigger]s. The combinators wait
+/// on the inner triggers and [delay], so they are only woken when something changes.
+pub trait CombinatorTarget: Trigger + Sized {
+    /// Create a trigger that activates when this trigger is released and releases when this
+    /// trigger activates
+    #[allow(clippy::should_implement_trait)]
+    fn not(self) -> Not<Self>
+    where
+        Self: ReleaseTrigger,
+    {
+        Not { inner: self }
+    }
+
+    /// Create a trigger that ignores changes that do not last for the given duration
+    fn debounce(self, duration: Duration, debounce_type: DebounceType) -> Debounce<Self>
+    where
+        Self: ReleaseTrigger,
+    {
+        Debounce {
+            inner: self,
+            duration,
+            debounce_type,
+        }
+    }
+
+    /// Create a trigger that activates once this trigger has been held for the given duration
+    fn held_for(self, duration: Duration) -> Debounce<Self>
+    where
+        Self: ReleaseTrigger,
+    {
+        self.debounce(duration, DebounceType::Rising)
+    }
+
+    /// Create a trigger that switches between active and released every time this trigger
+    /// activates
+    fn toggle(self) -> Toggle<Self> {
+        Toggle {
+            inner: self,
+            active: false,
+        }
+    }
+
+    /// Create a trigger that activates when both triggers are active and releases when either
+    /// trigger is released. The triggers can have different output types.
+    fn and<T>(self, other: T) -> And<Self, T>
+    where
+        Self: ReleaseTrigger,
+        T: ReleaseTrigger<Error = Self::Error>,
+    {
+        And {
+            inner: Pair::new(self, other),
+        }
+    }
+
+    /// Create a trigger that activates when either trigger is active and releases when both
+    /// triggers are released. The triggers can have different output types.
+    fn or<T>(self, other: T) -> Or<Self, T>
+    where
+        Self: ReleaseTrigger,
+        T: ReleaseTrigger<Error = Self::Error>,
+    {
+        Or {
+            inner: Pair::new(self, other),
+        }
+    }
+}
+
+impl<T: Trigger> CombinatorTarget for T {}
+
+/// Inverts a trigger. Created through [CombinatorTarget::not]
+pub struct Not<T> {
+    inner: T,
+}
+
+impl<T: ReleaseTrigger> Trigger for Not<T> {
+    type Output = T::Output;
+    type Error = T::Error;
+
+    async fn wait_for_trigger(&mut self) -> Result<Self::Output, Self::Error> {
+        self.inner.wait_for_release().await
+    }
+}
+
+impl<T: ReleaseTrigger> ReleaseTrigger for Not<T> {
+    async fn wait_for_release(&mut self) -> Result<Self::Output, Self::Error> {
+        self.inner.wait_for_trigger().await
+    }
+
+    fn is_active(&self) -> Option<bool> {
+        self.inner.is_active().map(|active| !active)
+    }
+}
+
+/// A trigger that filters out short changes. Created through [CombinatorTarget::debounce] and
+/// [CombinatorTarget::held_for]
+pub struct Debounce<T> {
+    inner: T,
+    duration: Duration,
+    debounce_type: DebounceType,
+}
+
+impl<T: ReleaseTrigger> Trigger for Debounce<T> {
+    type Output = T::Output;
+    type Error = T::Error;
+
+    #[instrument(skip_all, name = "debounce wait for trigger")]
+    async fn wait_for_trigger(&mut self) -> Result<Self::Output, Self::Error> {
+        loop {
+            let output = self.inner.wait_for_trigger().await?;
+
+            if !self.debounce_type.rising() {
+                return Ok(output);
+            }
+
+            trace!("Triggered, waiting for debounce");
+
+            let released = (async { Some(self.inner.wait_for_release().await) }, async {
+                delay(self.duration).await;
+                None
+            })
+                .race()
+                .await;
+
+            match released {
+                Some(Ok(_)) => trace!("Released before debounce finished"),
+                Some(Err(err)) => return Err(err),
+                None => return Ok(output),
+            }
+        }
+    }
+}
+
+impl<T: ReleaseTrigger> ReleaseTrigger for Debounce<T> {
+    #[instrument(skip_all, name = "debounce wait for release")]
+    async fn wait_for_release(&mut self) -> Result<Self::Output, Self::Error> {
+        loop {
+            let output = self.inner.wait_for_release().await?;
+
+            if !self.debounce_type.falling() {
+                return Ok(output);
+            }
+
+            trace!("Released, waiting for debounce");
+
+            let triggered = (async { Some(self.inner.wait_for_trigger().await) }, async {
+                delay(self.duration).await;
+                None
+            })
+                .race()
+                .await;
+
+            match triggered {
+                Some(Ok(_)) => trace!("Triggered before debounce finished"),
+                Some(Err(err)) => return Err(err),
+                None => return Ok(output),
+            }
+        }
+    }
+
+    fn is_active(&self) -> Option<bool> {
+        // An edge that is being debounced might not have lasted long enough yet
+        match self.inner.is_active()? {
+            true if !self.debounce_type.rising() => Some(true),
+            false if !self.debounce_type.falling() => Some(false),
+            _ => None,
+        }
+    }
+}
+
+/// A trigger that toggles every time the inner trigger activates. Created through
+/// [CombinatorTarget::toggle]
+pub struct Toggle<T> {
+    inner: T,
+    active: bool,
+}
+
+impl<T: Trigger> Trigger for Toggle<T> {
+    type Output = T::Output;
+    type Error = T::Error;
+
+    async fn wait_for_trigger(&mut self) -> Result<Self::Output, Self::Error> {
+        loop {
+            let output = self.inner.wait_for_trigger().await?;
+            self.active = !self.active;
+
+            if self.active {
+                return Ok(output);
+            }
+        }
+    }
+}
+
+impl<T: Trigger> ReleaseTrigger for Toggle<T> {
+    async fn wait_for_release(&mut self) -> Result<Self::Output, Self::Error> {
+        loop {
+            let output = self.inner.wait_for_trigger().await?;
+            self.active = !self.active;
+
+            if !self.active {
+                return Ok(output);
+            }
+        }
+    }
+
+    fn is_active(&self) -> Option<bool> {
+        Some(self.active)
+    }
+}
+
+/// Two triggers and whether each one is currently held. The held flags start from the current
+/// state of the triggers, so a pair created while a trigger is held is in sync with it
+struct Pair<A, B> {
+    a: A,
+    a_held: bool,
+    b: B,
+    b_held: bool,
+}
+
+impl<A: ReleaseTrigger, B: ReleaseTrigger<Error = A::Error>> Pair<A, B> {
+    fn new(a: A, b: B) -> Self {
+        Self {
+            a_held: a.is_active().unwrap_or(false),
+            a,
+            b_held: b.is_active().unwrap_or(false),
+            b,
+        }
+    }
+
+    /// Wait for either trigger to change state, returning the output of the edge. The other
+    /// trigger's state is checked again afterwards, since its edge is lost if both triggers
+    /// change on the same tick
+    async fn wait_for_change(&mut self) -> Result<Either<A::Output, B::Output>, A::Error> {
+        let (a_held, b_held) = (self.a_held, self.b_held);
+        let (a, b) = (&mut self.a, &mut self.b);
+
+        let output = (
+            async {
+                let output = if a_held {
+                    a.wait_for_release().await
+                } else {
+                    a.wait_for_trigger().await
+                };
+
+                output.map(Either::Left)
+            },
+            async {
+                let output = if b_held {
+                    b.wait_for_release().await
+                } else {
+                    b.wait_for_trigger().await
+                };
+
+                output.map(Either::Right)
+            },
+        )
+            .race()
+            .await?;
+
+        match output {
+            Either::Left(_) => {
+                self.a_held = !self.a_held;
+                self.b_held = self.b.is_active().unwrap_or(self.b_held);
+            }
+            Either::Right(_) => {
+                self.b_held = !self.b_held;
+                self.a_held = self.a.is_active().unwrap_or(self.a_held);
+            }
+        }
+
+        trace!(a_held = self.a_held, b_held = self.b_held, "Changed");
+
+        Ok(output)
+    }
+}
+
+/// A trigger that is active when both inner triggers are active. Created through
+/// [CombinatorTarget::and]
+pub struct And<A, B> {
+    inner: Pair<A, B>,
+}
+
+impl<A: ReleaseTrigger, B: ReleaseTrigger<Error = A::Error>> Trigger for And<A, B> {
+    type Output = ();
+    type Error = A::Error;
+
+    #[instrument(skip_all, name = "and trigger wait for trigger")]
+    async fn wait_for_trigger(&mut self) -> Result<Self::Output, Self::Error> {
+        loop {
+            self.inner.wait_for_change().await?;
+
+            if self.inner.a_held && self.inner.b_held {
+                return Ok(());
+            }
+        }
+    }
+}
+
+impl<A: ReleaseTrigger, B: ReleaseTrigger<Error = A::Error>> ReleaseTrigger for And<A, B> {
+    #[instrument(skip_all, name = "and trigger wait for release")]
+    async fn wait_for_release(&mut self) -> Result<Self::Output, Self::Error> {
+        loop {
+            let was_active = self.inner.a_held && self.inner.b_held;
+
+            self.inner.wait_for_change().await?;
+
+            if was_active {
+                return Ok(());
+            }
+        }
+    }
+
+    fn is_active(&self) -> Option<bool> {
+        Some(self.inner.a_held && self.inner.b_held)
+    }
+}
+
+/// A trigger that is active when either inner trigger is active. Created through
+/// [CombinatorTarget::or]
+pub struct Or<A, B> {
+    inner: Pair<A, B>,
+}
+
+impl<A: ReleaseTrigger, B: ReleaseTrigger<Error = A::Error>> Trigger for Or<A, B> {
+    type Output = Either<A::Output, B::Output>;
+    type Error = A::Error;
+
+    #[instrument(skip_all, name = "or trigger wait for trigger")]
+    async fn wait_for_trigger(&mut self) -> Result<Self::Output, Self::Error> {
+        loop {
+            let was_active = self.inner.a_held || self.inner.b_held;
+
+            let output = self.inner.wait_for_change().await?;
+
+            if !was_active {
+                return Ok(output);
+            }
+        }
+    }
+}
+
+impl<A: ReleaseTrigger, B: ReleaseTrigger<Error = A::Error>> ReleaseTrigger for Or<A, B> {
+    #[instrument(skip_all, name = "or trigger wait for release")]
+    async fn wait_for_release(&mut self) -> Result<Self::Output, Self::Error> {
+        loop {
+            let output = self.inner.wait_for_change().await?;
+
+            if !self.inner.a_held && !self.inner.b_held {
+                return Ok(output);
+            }
+        }
+    }
+
+    fn is_active(&self) -> Option<bool> {
+        Some(self.inner.a_held || self.inner.b_held)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{future::Future, time::Duration};
+
+    use super::{CombinatorTarget, DebounceType};
+    use crate::{
+        hid::{button::Button, joystick::Joystick, source::ScriptedInput, ReleaseTrigger, Trigger},
+        testing::{Polled, Sim},
+    };
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    /// Joystick 0 with two buttons, connected from the first tick
+    fn script() -> ScriptedInput {
+        ScriptedInput::new().connect(Duration::ZERO, 0, 2, 0, 0)
+    }
+
+    fn button(idx: u32) -> Button {
+        Joystick::new(0).get_button(idx)
+    }
+
+    /// Run the future until it completes and return the time it completed at, or [None] if it
+    /// is still pending after two seconds
+    fn completes_at<T, E>(
+        sim: &mut Sim,
+        future: impl Future<Output = Result<T, E>>,
+    ) -> Option<Duration> {
+        let end = sim.now() + Duration::from_secs(2);
+        let output = sim.run_until(&mut Polled::new(future), end)?;
+
+        assert!(output.is_ok());
+
+        Some(sim.now())
+    }
+
+    #[test]
+    fn not_inverts_trigger() {
+        let mut sim = Sim::new(
+            Duration::ZERO,
+            script().press(ms(100), 0, 0).release(ms(300), 0, 0),
+        );
+
+        let mut not = button(0).not();
+        assert_eq!(not.is_active(), Some(true));
+
+        assert_eq!(
+            completes_at(&mut sim, not.wait_for_release()),
+            Some(ms(100))
+        );
+        assert_eq!(not.is_active(), Some(false));
+
+        assert_eq!(
+            completes_at(&mut sim, not.wait_for_trigger()),
+            Some(ms(300))
+        );
+        assert_eq!(not.is_active(), Some(true));
+    }
+
+    #[test]
+    fn debounce_rising_ignores_short_presses() {
+        let mut sim = Sim::new(
+            Duration::ZERO,
+            script()
+                .press(ms(100), 0, 0)
+                .release(ms(140), 0, 0)
+                .press(ms(300), 0, 0)
+                .release(ms(600), 0, 0),
+        );
+
+        let mut debounce = button(0).debounce(ms(90), DebounceType::Rising);
+
+        assert_eq!(
+            completes_at(&mut sim, debounce.wait_for_trigger()),
+            Some(ms(400))
+        );
+        assert_eq!(
+            completes_at(&mut sim, debounce.wait_for_release()),
+            Some(ms(600))
+        );
+    }
+
+    #[test]
+    fn debounce_falling_ignores_short_releases() {
+        let mut sim = Sim::new(
+            Duration::ZERO,
+            script()
+                .press(ms(100), 0, 0)
+                .release(ms(300), 0, 0)
+                .press(ms(340), 0, 0)
+                .release(ms(500), 0, 0),
+        );
+
+        let mut debounce = button(0).debounce(ms(90), DebounceType::Falling);
+
+        assert_eq!(
+            completes_at(&mut sim, debounce.wait_for_trigger()),
+            Some(ms(100))
+        );
+        assert_eq!(debounce.is_active(), Some(true));
+        assert_eq!(
+            completes_at(&mut sim, debounce.wait_for_release()),
+            Some(ms(600))
+        );
+    }
+
+    #[test]
+    fn debounce_both_filters_both_edges() {
+        let mut sim = Sim::new(
+            Duration::ZERO,
+            script()
+                .press(ms(100), 0, 0)
+                .release(ms(140), 0, 0)
+                .press(ms(300), 0, 0)
+                .release(ms(500), 0, 0)
+                .press(ms(540), 0, 0)
+                .release(ms(700), 0, 0),
+        );
+
+        let mut debounce = button(0).debounce(ms(90), DebounceType::Both);
+
+        assert_eq!(
+            completes_at(&mut sim, debounce.wait_for_trigger()),
+            Some(ms(400))
+        );
+        // The inner trigger is active, but it may not have been for long enough
+        assert_eq!(debounce.is_active(), None);
+        assert_eq!(
+            completes_at(&mut sim, debounce.wait_for_release()),
+            Some(ms(800))
+        );
+    }
+
+    #[test]
+    fn held_for_waits_for_duration() {
+        let mut sim = Sim::new(
+            Duration::ZERO,
+            script()
+                .press(ms(100), 0, 0)
+                .release(ms(400), 0, 0)
+                .press(ms(600), 0, 0),
+        );
+
+        let mut held = button(0).held_for(ms(490));
+
+        assert_eq!(
+            completes_at(&mut sim, held.wait_for_trigger()),
+            Some(ms(1100))
+        );
+    }
+
+    #[test]
+    fn toggle_switches_on_each_press() {
+        let mut sim = Sim::new(
+            Duration::ZERO,
+            script()
+                .press(ms(100), 0, 0)
+                .release(ms(200), 0, 0)
+                .press(ms(300), 0, 0)
+                .release(ms(400), 0, 0)
+                .press(ms(500), 0, 0),
+        );
+
+        let mut toggle = button(0).toggle();
+        assert_eq!(toggle.is_active(), Some(false));
+
+        assert_eq!(
+            completes_at(&mut sim, toggle.wait_for_trigger()),
+            Some(ms(100))
+        );
+        assert_eq!(toggle.is_active(), Some(true));
+
+        assert_eq!(
+            completes_at(&mut sim, toggle.wait_for_release()),
+            Some(ms(300))
+        );
+        assert_eq!(toggle.is_active(), Some(false));
+
+        assert_eq!(
+            completes_at(&mut sim, toggle.wait_for_trigger()),
+            Some(ms(500))
+        );
+    }
+
+    #[test]
+    fn and_requires_both() {
+        let mut sim = Sim::new(
+            Duration::ZERO,
+            script()
+                .press(ms(100), 0, 0)
+                .press(ms(200), 0, 1)
+                .release(ms(300), 0, 0),
+        );
+
+        let mut and = button(0).and(button(1));
+        assert_eq!(and.is_active(), Some(false));
+
+        assert_eq!(
+            completes_at(&mut sim, and.wait_for_trigger()),
+            Some(ms(200))
+        );
+        assert_eq!(
+            completes_at(&mut sim, and.wait_for_release()),
+            Some(ms(300))
+        );
+    }
+
+    #[test]
+    fn or_requires_either() {
+        let mut sim = Sim::new(
+            Duration::ZERO,
+            script()
+                .press(ms(100), 0, 0)
+                .press(ms(200), 0, 1)
+                .release(ms(300), 0, 0)
+                .release(ms(400), 0, 1),
+        );
+
+        let mut or = button(0).or(button(1));
+        assert_eq!(or.is_active(), Some(false));
+
+        assert_eq!(completes_at(&mut sim, or.wait_for_trigger()), Some(ms(100)));
+        assert_eq!(completes_at(&mut sim, or.wait_for_release()), Some(ms(400)));
+    }
+
+    #[test]
+    fn pair_already_held_at_construction() {
+        let mut sim = Sim::new(
+            Duration::ZERO,
+            script()
+                .press(Duration::ZERO, 0, 0)
+                .press(Duration::ZERO, 0, 1)
+                .release(ms(200), 0, 1)
+                .release(ms(400), 0, 0)
+                .press(ms(600), 0, 1),
+        );
+
+        let mut and = button(0).and(button(1));
+        let mut or = button(0).or(button(1));
+        assert_eq!(and.is_active(), Some(true));
+        assert_eq!(or.is_active(), Some(true));
+
+        // The first falling edge releases the triggers, instead of being taken as a rising edge
+        assert_eq!(
+            completes_at(&mut sim, and.wait_for_release()),
+            Some(ms(200))
+        );
+        assert_eq!(completes_at(&mut sim, or.wait_for_release()), Some(ms(400)));
+        assert_eq!(completes_at(&mut sim, or.wait_for_trigger()), Some(ms(600)));
+    }
+
+    /// Both buttons are pressed on the same tick, released on the same tick, then pressed one at
+    /// a time
+    fn same_tick_script() -> ScriptedInput {
+        script()
+            .press(ms(200), 0, 0)
+            .press(ms(200), 0, 1)
+            .release(ms(400), 0, 0)
+            .release(ms(400), 0, 1)
+            .press(ms(600), 0, 0)
+            .press(ms(800), 0, 1)
+    }
+
+    #[test]
+    fn and_same_tick_edges() {
+        let mut sim = Sim::new(Duration::ZERO, same_tick_script());
+
+        let mut and = button(0).and(button(1));
+
+        assert_eq!(
+            completes_at(&mut sim, and.wait_for_trigger()),
+            Some(ms(200))
+        );
+        assert!(and.inner.a_held && and.inner.b_held);
+
+        assert_eq!(
+            completes_at(&mut sim, and.wait_for_release()),
+            Some(ms(400))
+        );
+        assert!(!and.inner.a_held && !and.inner.b_held);
+
+        assert_eq!(
+            completes_at(&mut sim, and.wait_for_trigger()),
+            Some(ms(800))
+        );
+    }
+
+    #[test]
+    fn or_same_tick_edges() {
+        let mut sim = Sim::new(Duration::ZERO, same_tick_script());
+
+        let mut or = button(0).or(button(1));
+
+        assert_eq!(completes_at(&mut sim, or.wait_for_trigger()), Some(ms(200)));
+        assert!(or.inner.a_held && or.inner.b_held);
+
+        assert_eq!(completes_at(&mut sim, or.wait_for_release()), Some(ms(400)));
+        assert!(!or.inner.a_held && !or.inner.b_held);
+
+        assert_eq!(completes_at(&mut sim, or.wait_for_trigger()), Some(ms(600)));
+        assert!(or.inner.a_held && !or.inner.b_held);
+    }
+}
@@ -2,7 +2,8 @@ use super::{
     axis::{get_axis, Axis, AxisTarget},
     button::{Button, ButtonTarget},
     joystick::Joystick,
-    pov::{Pov, PovTarget},
+    pov::{get_pov, Pov, PovDirection, PovTarget},
+    rumble::RumblePattern,
 };
 
 impl Joystick {
@@ -21,33 +22,101 @@ impl Joystick {
     pub fn pov(&self, idx: u32, target: PovTarget) -> Pov {
         Pov::new(*self, idx, target)
     }
+
+    pub fn pov_direction(&self, idx: u32) -> Option<PovDirection> {
+        get_pov(&self.get_pov_data(), idx).map(PovDirection::from_angle)
+    }
 }
 
+/// Declare a controller type that wraps a [Joystick]. The other `define_*` macros can then be
+/// used to add named buttons, axes, and povs to it.
+///
+/// # Example
+///
+/// ```rust
+/// define_controller!(
+///     /// The operator console
+///     pub struct OperatorConsole
+/// );
+///
+/// define_buttons!(OperatorConsole, score_high = 0, score_low = 1);
+/// define_axes!(OperatorConsole, wait_elevator / elevator = 0);
+/// define_povs!(OperatorConsole, up = 0 => 0, down = 0 => 180);
+///
+/// let console = OperatorConsole::new(1);
+/// console.score_high().on_pressed(|| async { /* ... */ });
+/// ```
+#[macro_export]
+macro_rules! define_controller {
+    ($(#[$meta:meta])* $vis:vis struct $class:ident) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy)]
+        $vis struct $class {
+            joystick: $crate::hid::joystick::Joystick,
+        }
+
+        impl $class {
+            /// Create a new controller. `num` is zero based
+            ///
+            /// Panics if `num` >= 6
+            pub fn new(num: u32) -> Self {
+                Self {
+                    joystick: $crate::hid::joystick::Joystick::new(num),
+                }
+            }
+
+            /// Get the joystick this controller reads from
+            pub fn joystick(&self) -> $crate::hid::joystick::Joystick {
+                self.joystick
+            }
+        }
+
+        impl $crate::hid::shaping::ShapeAxes for $class {}
+    };
+}
+
+/// Add named button triggers to a controller declared with
+/// [define_controller](crate::define_controller). Indices are zero based.
+///
+/// # Example
+///
+/// ```rust
+/// define_buttons!(OperatorConsole, score_high = 0, score_low = 1);
+/// ```
 #[macro_export]
 macro_rules! define_buttons {
     ($class:ident, $($name:ident = $index:expr),+) => {
         impl $class {
             $(
                 /// Get a trigger for the button. This trigger activates when the button is prssed,
-                /// but this can be changed through [Button::set_target]
-                pub fn $name (&self) -> Button {
-                    self.joystick.button($index, ButtonTarget::Pressed)
+                /// but this can be changed through `Button::set_target`
+                pub fn $name (&self) -> $crate::hid::button::Button {
+                    self.joystick.button($index, $crate::hid::button::ButtonTarget::Pressed)
                 }
             )+
         }
     };
 }
 
+/// Add named pov triggers to a controller declared with
+/// [define_controller](crate::define_controller). Each trigger is a pov index and either the
+/// angle in degrees that activates it or a [PovTarget].
+///
+/// # Example
+///
+/// ```rust
+/// define_povs!(OperatorConsole, up = 0 => 0, down = 0 => 180, any_up = 0 => PovTarget::Up);
+/// ```
 #[macro_export]
 macro_rules! define_povs {
     ($class:ident, $($name:ident = $index:expr => $dir:expr),+) => {
         impl $class {
             $(
                 /// Get a trigger for the pov.
-                pub fn $name (&self) -> Pov {
+                pub fn $name (&self) -> $crate::hid::pov::Pov {
                     self.joystick.pov(
                         $index,
-                        PovTarget::Raw($dir)
+                        $crate::hid::pov::PovTarget::from($dir)
                     )
                 }
             )+
@@ -55,14 +124,23 @@ macro_rules! define_povs {
     };
 }
 
+/// Add named axes to a controller declared with [define_controller](crate::define_controller).
+/// Each axis gets a trigger method and a method that returns the raw value. Indices are zero
+/// based.
+///
+/// # Example
+///
+/// ```rust
+/// define_axes!(OperatorConsole, wait_elevator / elevator = 0);
+/// ```
 #[macro_export]
 macro_rules! define_axes {
     ($class:ident, $($future_name:ident/$name:ident = $index:expr),+) => {
         impl $class {
             $(
-                /// Get a trigger for the axis. See [AxisTarget] for possible ways this trigger
+                /// Get a trigger for the axis. See `AxisTarget` for possible ways this trigger
                 /// will activate
-                pub fn $future_name (&self, target: AxisTarget) -> Axis {
+                pub fn $future_name (&self, target: $crate::hid::axis::AxisTarget) -> $crate::hid::axis::Axis {
                     self.joystick.wait_for_axis(
                         $index,
                         target
@@ -78,27 +156,22 @@ macro_rules! define_axes {
     };
 }
 
-/// An Xbox controller
-#[derive(Clone, Copy)]
-pub struct XboxController {
-    joystick: Joystick,
-}
+define_controller!(
+    /// An Xbox controller
+    pub struct XboxController
+);
 
 impl XboxController {
-    /// Create a new Xbox controller.
-    ///
-    /// Panics if `num` >= 6
-    pub fn new(num: u32) -> Self {
-        Self {
-            joystick: Joystick::new(num),
-        }
-    }
-
     /// You can likely ignore this error. It won't actually cause a problem and there isn't much
     /// you can do
     pub fn rumble(&self, left: f32, right: f32) -> Result<(), crate::error::HalError> {
         self.joystick.rumble(1, left, right)
     }
+
+    /// Play a rumble pattern. See [Joystick::play_rumble]
+    pub async fn play_rumble(&self, pattern: &RumblePattern, priority: u32) {
+        self.joystick.play_rumble(pattern, priority).await
+    }
 }
 
 define_buttons!(
@@ -137,27 +210,22 @@ define_povs!(
     up_left = 0 => 315
 );
 
-/// A PS4 controller
-#[derive(Clone, Copy)]
-pub struct PS4Controller {
-    joystick: Joystick,
-}
+define_controller!(
+    /// A PS4 controller
+    pub struct PS4Controller
+);
 
 impl PS4Controller {
-    /// Create a new PS4 controller.
-    ///
-    /// Panics if `num` >= 6
-    pub fn new(num: u32) -> Self {
-        Self {
-            joystick: Joystick::new(num),
-        }
-    }
-
     /// You can likely ignore this error. It won't actually cause a problem and there isn't much
     /// you can do
     pub fn rumble(&self, left: f32, right: f32) -> Result<(), crate::error::HalError> {
         self.joystick.rumble(1, left, right)
     }
+
+    /// Play a rumble pattern. See [Joystick::play_rumble]
+    pub async fn play_rumble(&self, pattern: &RumblePattern, priority: u32) {
+        self.joystick.play_rumble(pattern, priority).await
+    }
 }
 
 define_buttons!(
@@ -199,3 +267,168 @@ define_povs!(
     left = 0 => 270,
     up_left = 0 => 315
 );
+
+define_controller!(
+    /// A PS5 DualSense controller
+    pub struct PS5Controller
+);
+
+impl PS5Controller {
+    /// You can likely ignore this error. It won't actually cause a problem and there isn't much
+    /// you can do
+    pub fn rumble(&self, left: f32, right: f32) -> Result<(), crate::error::HalError> {
+        self.joystick.rumble(1, left, right)
+    }
+
+    /// Play a rumble pattern. See [Joystick::play_rumble]
+    pub async fn play_rumble(&self, pattern: &RumblePattern, priority: u32) {
+        self.joystick.play_rumble(pattern, priority).await
+    }
+}
+
+define_buttons!(
+    PS5Controller,
+    square = 0,
+    cross = 1,
+    circle = 2,
+    triangle = 3,
+    l1 = 4,
+    r1 = 5,
+    l2_button = 6,
+    r2_button = 7,
+    create = 8,
+    options = 9,
+    l3 = 10,
+    r3 = 11,
+    ps = 12,
+    touchpad = 13
+);
+
+define_axes!(
+    PS5Controller,
+    wait_left_x / left_x = 0,
+    wait_left_y / left_y = 1,
+    wait_right_x / right_x = 2,
+    wait_right_y / right_y = 5,
+    wait_l2_axis / l2_axis = 3,
+    wait_r2_axis / r2_axis = 4
+);
+
+define_povs!(
+    PS5Controller,
+    up = 0 => 0,
+    up_right = 0 => 45,
+    right = 0 => 90,
+    down_right = 0 => 135,
+    down = 0 => 180,
+    down_left = 0 => 225,
+    left = 0 => 270,
+    up_left = 0 => 315
+);
+
+define_controller!(
+    /// A Nintendo Switch Pro controller connected over USB. The triggers on this controller are
+    /// buttons, not axes. The mapping depends on the driver, so check it against the USB tab of
+    /// the driver station.
+    pub struct SwitchProController
+);
+
+define_buttons!(
+    SwitchProController,
+    b = 0,
+    a = 1,
+    y = 2,
+    x = 3,
+    l = 4,
+    r = 5,
+    zl = 6,
+    zr = 7,
+    minus = 8,
+    plus = 9,
+    left_stick = 10,
+    right_stick = 11,
+    home = 12,
+    capture = 13
+);
+
+define_axes!(
+    SwitchProController,
+    wait_left_x / left_x = 0,
+    wait_left_y / left_y = 1,
+    wait_right_x / right_x = 2,
+    wait_right_y / right_y = 3
+);
+
+define_povs!(
+    SwitchProController,
+    up = 0 => 0,
+    up_right = 0 => 45,
+    right = 0 => 90,
+    down_right = 0 => 135,
+    down = 0 => 180,
+    down_left = 0 => 225,
+    left = 0 => 270,
+    up_left = 0 => 315
+);
+
+define_controller!(
+    /// A Logitech Extreme 3D Pro flight stick
+    pub struct Extreme3DJoystick
+);
+
+define_buttons!(
+    Extreme3DJoystick,
+    trigger = 0,
+    thumb = 1,
+    button_3 = 2,
+    button_4 = 3,
+    button_5 = 4,
+    button_6 = 5,
+    button_7 = 6,
+    button_8 = 7,
+    button_9 = 8,
+    button_10 = 9,
+    button_11 = 10,
+    button_12 = 11
+);
+
+define_axes!(
+    Extreme3DJoystick,
+    wait_x / x = 0,
+    wait_y / y = 1,
+    wait_twist / twist = 2,
+    wait_throttle / throttle = 3
+);
+
+define_povs!(
+    Extreme3DJoystick,
+    hat_up = 0 => 0,
+    hat_up_right = 0 => 45,
+    hat_right = 0 => 90,
+    hat_down_right = 0 => 135,
+    hat_down = 0 => 180,
+    hat_down_left = 0 => 225,
+    hat_left = 0 => 270,
+    hat_up_left = 0 => 315
+);
+
+define_controller!(
+    /// A generic arcade button board, such as the ones built with a zero delay USB encoder. These
+    /// usually have up to 12 buttons and a stick that is reported as two axes. Buttons are not
+    /// named because they are wired differently on every board, see [ButtonBoard::button].
+    pub struct ButtonBoard
+);
+
+impl ButtonBoard {
+    /// Get a trigger for the button at the given index (zero indexed). This trigger activates
+    /// when the button is pressed, but this can be changed through [Button::set_target]
+    pub fn button(&self, idx: u32) -> Button {
+        self.joystick.button(idx, ButtonTarget::Pressed)
+    }
+}
+
+define_axes!(
+    ButtonBoard,
+    wait_stick_x / stick_x = 0,
+    wait_stick_y / stick_y = 1
+);
@@ -10,7 +10,7 @@ use crate::{
     time::delay,
 };
 
-use super::{ReleaseTrigger, Trigger};
+use super::{combinators::CombinatorTarget, ReleaseTrigger, Trigger};
 
 /// A double click extension trait that is auto implemented for sized [Trigger]s
 pub trait DoubleClickTarget: Trigger + Sized {
@@ -113,16 +113,7 @@ where
                     (
                         async {
                             loop {
-                                if let Err(err) = self.wait_for_trigger().await {
-                                    error!("Trigger error: {:?}", err);
-                                } else {
-                                    trace!("Triggering callback");
-                                    if guard(func()).await.is_some() {
-                                        trace!("Callback complete");
-                                    } else {
-                                        trace!("Callback cancelled");
-                                    }
-                                }
+                                run_on_pressed(&mut self, &mut func).await;
                             }
                         },
                         async {
@@ -138,6 +129,43 @@ where
         )
         .detach();
     }
+
+    /// Spawns a new future using the main scheduler that waits for the trigger to activate then
+    /// calls the function and runs the future. The next activation of the trigger cancels the
+    /// future if it is still running. The function and future are wrapped in a cancellation scope
+    /// so the main future will not be cancelled. This only runs if the robot is enabled, and the
+    /// toggle is reset when the robot is disabled
+    fn toggle_on_press<Func, Fut>(mut self, mut func: Func)
+    where
+        Func: FnMut() -> Fut + 'static,
+        Fut: Future + 'static,
+    {
+        spawn(
+            async move {
+                loop {
+                    wait_for_enabled().await;
+
+                    trace!("Robot enabled");
+
+                    (
+                        async {
+                            loop {
+                                run_toggle(&mut self, &mut func).await;
+                            }
+                        },
+                        async {
+                            wait_for_disabled().await;
+                            trace!("Robot disabled");
+                        },
+                    )
+                        .race()
+                        .await;
+                }
+            }
+            .instrument(span!(Level::TRACE, "toggle on press")),
+        )
+        .detach();
+    }
 }
 
 impl<T: Trigger> TriggerExt for T
@@ -170,35 +198,37 @@ where
 
                     trace!("Robot enabled");
 
+                    (run_while_pressed(&mut self, &mut func), async {
+                        wait_for_disabled().await;
+                        trace!("Robot disabled");
+                    })
+                        .race()
+                        .await;
+                }
+            }
+            .instrument(span!(Level::TRACE, "while pressed")),
+        )
+        .detach();
+    }
+
+    /// The same as [TriggerExt::on_pressed], but the future is started when the trigger is
+    /// released after being activated
+    fn on_released<Func, Fut>(mut self, mut func: Func)
+    where
+        Func: FnMut() -> Fut + 'static,
+        Fut: Future + 'static,
+    {
+        spawn(
+            async move {
+                loop {
+                    wait_for_enabled().await;
+
+                    trace!("Robot enabled");
+
                     (
                         async {
-                            if let Err(err) = self.wait_for_trigger().await {
-                                error!("Trigger error: {:?}", err);
-                                return;
-                            }
-
-                            let res = (
-                                async {
-                                    if guard(func()).await.is_some() {
-                                        trace!("Callback complete");
-                                    } else {
-                                        trace!("Callback cancelled");
-                                    }
-                                    pending::<()>().await;
-                                    unreachable!()
-                                },
-                                async {
-                                    let res = self.wait_for_release().await;
-                                    trace!("Trigger released");
-
-                                    res
-                                },
-                            )
-                                .race()
-                                .await;
-
-                            if let Err(err) = res {
-                                error!("Trigger failed: {:?}", err);
+                            loop {
+                                run_on_released(&mut self, &mut func).await;
                             }
                         },
                         async {
@@ -210,10 +240,155 @@ where
                         .await;
                 }
             }
-            .instrument(span!(Level::TRACE, "while pressed")),
+            .instrument(span!(Level::TRACE, "on released")),
         )
         .detach();
     }
+
+    /// The same as [ReleaseTriggerExt::while_pressed], but the future is started when the trigger
+    /// is released and cancelled when it activates again
+    fn while_released<Func, Fut>(self, func: Func)
+    where
+        Func: FnMut() -> Fut + 'static,
+        Fut: Future + 'static,
+    {
+        self.not().while_pressed(func);
+    }
+
+    /// The same as [TriggerExt::on_pressed], but the future is only started once the trigger has
+    /// been held for the given duration
+    fn on_held<Func, Fut>(self, duration: Duration, func: Func)
+    where
+        Func: FnMut() -> Fut + 'static,
+        Fut: Future + 'static,
+    {
+        self.held_for(duration).on_pressed(func);
+    }
+}
+
+/// Run the future from `func` to completion in a cancellation scope
+async fn run_callback<Func, Fut>(func: &mut Func)
+where
+    Func: FnMut() -> Fut,
+    Fut: Future,
+{
+    trace!("Triggering callback");
+    if guard(func()).await.is_some() {
+        trace!("Callback complete");
+    } else {
+        trace!("Callback cancelled");
+    }
+}
+
+/// Wait for the trigger to activate, then run the future from `func` to completion. This is a
+/// single activation of [TriggerExt::on_pressed]
+async fn run_on_pressed<T, Func, Fut>(trigger: &mut T, func: &mut Func)
+where
+    T: Trigger,
+    T::Error: Debug,
+    Func: FnMut() -> Fut,
+    Fut: Future,
+{
+    if let Err(err) = trigger.wait_for_trigger().await {
+        error!("Trigger error: {:?}", err);
+    } else {
+        run_callback(func).await;
+    }
+}
+
+/// Wait for the trigger to activate, then run the future from `func` until it completes or the
+/// trigger activates again. This is a single activation of [TriggerExt::toggle_on_press]
+async fn run_toggle<T, Func, Fut>(trigger: &mut T, func: &mut Func)
+where
+    T: Trigger,
+    T::Error: Debug,
+    Func: FnMut() -> Fut,
+    Fut: Future,
+{
+    if let Err(err) = trigger.wait_for_trigger().await {
+        error!("Trigger error: {:?}", err);
+        return;
+    }
+
+    trace!("Toggled on");
+
+    // The toggle turns off when the callback completes on its own, so the next activation starts
+    // it again
+    let res = (
+        async {
+            run_callback(func).await;
+
+            None
+        },
+        async {
+            let res = trigger.wait_for_trigger().await;
+            trace!("Toggled off");
+
+            Some(res)
+        },
+    )
+        .race()
+        .await;
+
+    if let Some(Err(err)) = res {
+        error!("Trigger failed: {:?}", err);
+    }
+}
+
+/// Wait for the trigger to activate and then release, then run the future from `func` to
+/// completion. This is a single activation of [ReleaseTriggerExt::on_released]
+async fn run_on_released<T, Func, Fut>(trigger: &mut T, func: &mut Func)
+where
+    T: ReleaseTrigger,
+    T::Error: Debug,
+    Func: FnMut() -> Fut,
+    Fut: Future,
+{
+    let res = match trigger.wait_for_trigger().await {
+        Ok(_) => trigger.wait_for_release().await,
+        Err(err) => Err(err),
+    };
+
+    if let Err(err) = res {
+        error!("Trigger error: {:?}", err);
+    } else {
+        run_callback(func).await;
+    }
+}
+
+/// Wait for the trigger to activate, then run the future from `func` until the trigger is
+/// released. This is a single activation of [ReleaseTriggerExt::while_pressed]
+pub(super) async fn run_while_pressed<T, Func, Fut>(trigger: &mut T, func: &mut Func)
+where
+    T: ReleaseTrigger,
+    T::Error: Debug,
+    Func: FnMut() -> Fut,
+    Fut: Future,
+{
+    if let Err(err) = trigger.wait_for_trigger().await {
+        error!("Trigger error: {:?}", err);
+        return;
+    }
+
+    let res = (
+        async {
+            run_callback(func).await;
+            pending::<()>().await;
+            unreachable!()
+        },
+        async {
+            let res = trigger.wait_for_release().await;
+            trace!("Trigger released");
+
+            res
+        },
+    )
+        .race()
+        .await;
+
+    if let Err(err) = res {
+        error!("Trigger failed: {:?}", err);
+    }
 }
 
 impl<T: ReleaseTrigger> ReleaseTriggerExt for T
@@ -222,3 +397,109 @@ where
     Self: 'static,
 {
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, future::Future, pin::Pin, rc::Rc, time::Duration};
+
+    use futures::future::pending;
+
+    use super::{run_on_pressed, run_on_released, run_toggle};
+    use crate::{
+        hid::{combinators::CombinatorTarget, joystick::Joystick, source::ScriptedInput},
+        testing::{DropGuard, Polled, Sim},
+        time::get_time,
+    };
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    /// A callback that records when it was started and when its future was dropped. The future
+    /// never completes on its own
+    fn callback(
+        started: &Rc<Cell<Option<Duration>>>,
+        dropped: &Rc<Cell<Option<Duration>>>,
+    ) -> impl FnMut() -> Pin<Box<dyn Future<Output = ()>>> {
+        let (started, dropped) = (started.clone(), dropped.clone());
+
+        move || {
+            let guard = DropGuard(dropped.clone());
+            started.set(Some(get_time()));
+
+            Box::pin(async move {
+                let _guard = guard;
+                pending::<()>().await
+            })
+        }
+    }
+
+    #[test]
+    fn second_press_cancels_toggle() {
+        let mut sim = Sim::new(
+            Duration::ZERO,
+            ScriptedInput::new()
+                .connect(Duration::ZERO, 0, 1, 0, 0)
+                .press(ms(100), 0, 0)
+                .release(ms(200), 0, 0)
+                .press(ms(300), 0, 0),
+        );
+
+        let (started, dropped) = Default::default();
+        let mut func = callback(&started, &dropped);
+        let mut button = Joystick::new(0).get_button(0);
+
+        let mut future = Polled::new(run_toggle(&mut button, &mut func));
+
+        assert!(sim.run_until(&mut future, ms(1000)).is_some());
+        assert_eq!(started.get(), Some(ms(100)));
+        assert_eq!(dropped.get(), Some(ms(300)));
+    }
+
+    #[test]
+    fn on_released_fires_on_falling_edge() {
+        let mut sim = Sim::new(
+            Duration::ZERO,
+            ScriptedInput::new()
+                .connect(Duration::ZERO, 0, 1, 0, 0)
+                .press(ms(100), 0, 0)
+                .release(ms(300), 0, 0),
+        );
+
+        let (started, dropped) = Default::default();
+        let mut func = callback(&started, &dropped);
+        let mut button = Joystick::new(0).get_button(0);
+
+        let mut future = Polled::new(run_on_released(&mut button, &mut func));
+
+        assert!(sim.run_until(&mut future, ms(280)).is_none());
+        assert_eq!(started.get(), None);
+
+        assert!(sim.run_until(&mut future, ms(1000)).is_none());
+        assert_eq!(started.get(), Some(ms(300)));
+    }
+
+    #[test]
+    fn on_held_ignores_short_presses() {
+        let mut sim = Sim::new(
+            Duration::ZERO,
+            ScriptedInput::new()
+                .connect(Duration::ZERO, 0, 1, 0, 0)
+                .press(ms(100), 0, 0)
+                .release(ms(400), 0, 0)
+                .press(ms(600), 0, 0),
+        );
+
+        let (started, dropped) = Default::default();
+        let mut func = callback(&started, &dropped);
+        let mut held = Joystick::new(0).get_button(0).held_for(ms(490));
+
+        let mut future = Polled::new(run_on_pressed(&mut held, &mut func));
+
+        assert!(sim.run_until(&mut future, ms(1080)).is_none());
+        assert_eq!(started.get(), None);
+
+        assert!(sim.run_until(&mut future, ms(2000)).is_none());
+        assert_eq!(started.get(), Some(ms(1100)));
+    }
+}
@@ -1,18 +1,72 @@
-use std::mem::MaybeUninit;
+use std::{ffi::CStr, mem::MaybeUninit};
 
 use hal_sys::{
-    HAL_GetJoystickAxes, HAL_GetJoystickButtons, HAL_GetJoystickPOVs, HAL_JoystickAxes,
-    HAL_JoystickButtons, HAL_JoystickPOVs, HAL_SetJoystickOutputs,
+    HAL_GetJoystickDescriptor, HAL_JoystickAxes, HAL_JoystickButtons, HAL_JoystickDescriptor,
+    HAL_JoystickPOVs,
 };
+use tracing::{error, span, warn, Instrument, Level};
 
-use crate::error::HalError;
+use crate::{
+    error::{Error, HalError},
+    scheduler::spawn,
+};
 
 use super::{
     axis::{get_axis, Axis, AxisTarget},
     button::{Button, ButtonTarget},
     pov::{Pov, PovTarget},
+    reactor::{is_connected, latest_snapshot, wait_for_connection},
+    shaping::{AxisShaper, ShapedAxis, ShapedStick, StickShaper},
+    source::{set_joystick_outputs, JoystickData},
+    stick::{Stick, StickTarget},
 };
 
+/// The type of a joystick as reported by the driver station
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum JoystickType {
+    Unknown,
+    XInputUnknown,
+    XInputGamepad,
+    XInputWheel,
+    XInputArcadeStick,
+    XInputFlightStick,
+    XInputDancePad,
+    XInputGuitar,
+    XInputGuitar2,
+    XInputDrumKit,
+    XInputGuitar3,
+    XInputArcadePad,
+    HidJoystick,
+    HidGamepad,
+    HidDriving,
+    HidFlight,
+    Hid1stPerson,
+}
+
+impl JoystickType {
+    pub fn from_raw(raw: i32) -> Self {
+        match raw {
+            0 => Self::XInputUnknown,
+            1 => Self::XInputGamepad,
+            2 => Self::XInputWheel,
+            3 => Self::XInputArcadeStick,
+            4 => Self::XInputFlightStick,
+            5 => Self::XInputDancePad,
+            6 => Self::XInputGuitar,
+            7 => Self::XInputGuitar2,
+            8 => Self::XInputDrumKit,
+            11 => Self::XInputGuitar3,
+            19 => Self::XInputArcadePad,
+            20 => Self::HidJoystick,
+            21 => Self::HidGamepad,
+            22 => Self::HidDriving,
+            23 => Self::HidFlight,
+            24 => Self::Hid1stPerson,
+            _ => Self::Unknown,
+        }
+    }
+}
+
 #[derive(PartialEq, Clone, Debug, Copy)]
 pub struct Joystick {
     num: u32,
@@ -30,45 +84,147 @@ impl Joystick {
         }
     }
 
+    /// Create a new generic joystick. `num` is zero based
+    ///
+    /// Returns an error if `num` >= 6
+    pub fn try_new(num: u32) -> crate::error::Result<Self> {
+        if num >= 6 {
+            Err(Error::JoystickIndexOutOfRange(num))
+        } else {
+            Ok(Self { num })
+        }
+    }
+
     /// Get the joystick index
     pub fn get_num(&self) -> u32 {
         self.num
     }
 
-    pub(crate) fn get_button_data(&self) -> HAL_JoystickButtons {
-        let mut buttons = MaybeUninit::uninit();
+    pub(crate) fn get_descriptor_data(&self) -> crate::error::Result<HAL_JoystickDescriptor> {
+        let mut descriptor = MaybeUninit::uninit();
 
-        let status = unsafe { HAL_GetJoystickButtons(self.num as i32, buttons.as_mut_ptr()) };
+        let status = unsafe { HAL_GetJoystickDescriptor(self.num as i32, descriptor.as_mut_ptr()) };
 
         if status != 0 {
-            panic!("Something is very wrong with the HAL");
+            return Err(HalError::from_raw(status).into());
         }
 
-        unsafe { buttons.assume_init() }
+        Ok(unsafe { descriptor.assume_init() })
     }
 
-    pub(crate) fn get_axes_data(&self) -> HAL_JoystickAxes {
-        let mut axes = MaybeUninit::uninit();
+    /// Returns true if the joystick had any inputs on the last tick, which is how the driver
+    /// station reports a joystick plugged in at this index
+    pub fn is_connected(&self) -> bool {
+        is_connected(self.num)
+    }
 
-        let status = unsafe { HAL_GetJoystickAxes(self.num as i32, axes.as_mut_ptr()) };
+    /// Get the name of the joystick. This is empty if no joystick is connected
+    pub fn name(&self) -> crate::error::Result<String> {
+        let descriptor = self.get_descriptor_data()?;
 
-        if status != 0 {
-            panic!("Something is very wrong with the HAL");
-        }
+        // The HAL always null terminates the name
+        Ok(unsafe { CStr::from_ptr(descriptor.name.as_ptr()) }
+            .to_string_lossy()
+            .into_owned())
+    }
 
-        unsafe { axes.assume_init() }
+    /// Get the type of the joystick
+    pub fn joystick_type(&self) -> crate::error::Result<JoystickType> {
+        Ok(JoystickType::from_raw(
+            self.get_descriptor_data()?.type_ as i8 as i32,
+        ))
     }
 
-    pub(crate) fn get_pov_data(&self) -> HAL_JoystickPOVs {
-        let mut povs = MaybeUninit::uninit();
+    /// Returns true if the driver station considers this joystick to be an Xbox controller
+    pub fn is_xbox(&self) -> crate::error::Result<bool> {
+        Ok(self.get_descriptor_data()?.isXbox != 0)
+    }
 
-        let status = unsafe { HAL_GetJoystickPOVs(self.num as i32, povs.as_mut_ptr()) };
+    /// Get the number of axes on the joystick
+    pub fn axis_count(&self) -> crate::error::Result<u32> {
+        Ok(self.get_descriptor_data()?.axisCount.into())
+    }
 
-        if status != 0 {
-            panic!("Something is very wrong with the HAL");
-        }
+    /// Get the number of buttons on the joystick
+    pub fn button_count(&self) -> crate::error::Result<u32> {
+        Ok(self.get_descriptor_data()?.buttonCount.into())
+    }
+
+    /// Get the number of povs on the joystick
+    pub fn pov_count(&self) -> crate::error::Result<u32> {
+        Ok(self.get_descriptor_data()?.povCount.into())
+    }
+
+    /// Wait until a joystick is plugged in at this index. Returns immediately if one already is.
+    /// This is woken by the HID reactor on the tick the joystick is connected
+    pub async fn wait_for_connected(&self) {
+        wait_for_connection(self.num, true).await
+    }
+
+    /// Wait until the joystick at this index is unplugged. Returns immediately if there is no
+    /// joystick
+    pub async fn wait_for_disconnected(&self) {
+        wait_for_connection(self.num, false).await
+    }
+
+    /// Spawn a task that logs a warning whenever a joystick is connected at this index that is
+    /// not of the expected type. This catches controllers that were plugged into the wrong USB
+    /// port. Must be called from the robot thread, see [spawn]
+    pub fn expect_type(&self, expected: JoystickType) {
+        let joystick = *self;
+
+        spawn(
+            async move {
+                loop {
+                    joystick.wait_for_connected().await;
+
+                    match joystick.joystick_type() {
+                        Ok(actual) if actual != expected => {
+                            warn!(
+                                "Joystick {} ({}) is a {:?}, but a {:?} was expected",
+                                joystick.num,
+                                joystick.name().unwrap_or_default(),
+                                actual,
+                                expected
+                            );
+                        }
+                        Ok(_) => {}
+                        Err(err) => error!(
+                            "Could not check the type of joystick {}: {}",
+                            joystick.num, err
+                        ),
+                    }
+
+                    joystick.wait_for_disconnected().await;
+                }
+            }
+            .instrument(span!(
+                Level::TRACE,
+                "joystick type check",
+                joystick = self.num
+            )),
+        )
+        .detach();
+    }
 
-        unsafe { povs.assume_init() }
+    /// Get the state of every input on the joystick as read by the HID reactor on the last tick.
+    /// This is a disconnected joystick before the first tick
+    pub(crate) fn get_data(&self) -> JoystickData {
+        latest_snapshot()
+            .map(|(_, data)| data[self.num as usize])
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn get_button_data(&self) -> HAL_JoystickButtons {
+        self.get_data().buttons
+    }
+
+    pub(crate) fn get_axes_data(&self) -> HAL_JoystickAxes {
+        self.get_data().axes
+    }
+
+    pub(crate) fn get_pov_data(&self) -> HAL_JoystickPOVs {
+        self.get_data().povs
     }
 
     /// Get a trigger for the button at the given index (zero indexed). The trigger activates on
@@ -82,36 +238,64 @@ impl Joystick {
         Axis::new(*self, idx, target)
     }
 
+    /// Get a trigger for a pair of axes, such as a thumb stick, at the given indices (zero
+    /// indexed)
+    pub fn get_stick(&self, x_idx: u32, y_idx: u32, target: StickTarget) -> Stick {
+        Stick::new(*self, x_idx, y_idx, target)
+    }
+
     /// Get the value for the axis at the given index  (zero indexed). This returns [None] if the
     /// axis does not exist
     pub fn get_axis_value(&self, idx: u32) -> Option<f32> {
         get_axis(&self.get_axes_data(), idx)
     }
 
+    /// Get the axis at the given index (zero indexed) with an [AxisShaper] applied to it
+    pub fn shaped_axis(
+        &self,
+        idx: u32,
+        shaper: AxisShaper,
+    ) -> ShapedAxis<impl FnMut() -> Option<f32>> {
+        let joystick = *self;
+
+        ShapedAxis::new(move || joystick.get_axis_value(idx), shaper)
+    }
+
+    /// Get the pair of axes at the given indices (zero indexed) with a [StickShaper] applied to
+    /// them
+    pub fn shaped_stick(
+        &self,
+        x_idx: u32,
+        y_idx: u32,
+        shaper: StickShaper,
+    ) -> ShapedStick<impl FnMut() -> Option<f32>, impl FnMut() -> Option<f32>> {
+        let joystick = *self;
+
+        ShapedStick::new(
+            move || joystick.get_axis_value(x_idx),
+            move || joystick.get_axis_value(y_idx),
+            shaper,
+        )
+    }
+
     /// Get a trigger for the pov at the given index (zero indexed).
     pub fn get_pov(&self, idx: u32, target: PovTarget) -> Pov {
         Pov::new(*self, idx, target)
     }
 
+    /// Set the outputs and rumble of the joystick. The rumble strengths are clamped between 0 and
+    /// 1. This goes through the active [InputSource](super::source::InputSource)
     pub fn rumble(
         &self,
         outputs: i64,
         left: f32,
         right: f32,
     ) -> Result<(), crate::error::HalError> {
-        let status = unsafe {
-            HAL_SetJoystickOutputs(
-                self.num as i32,
-                outputs,
-                (left.clamp(0.0, 1.0) * 65535.0) as i32,
-                (right.clamp(0.0, 1.0) * 65535.0) as i32,
-            )
-        };
-
-        if status == 0 {
-            Ok(())
-        } else {
-            Err(HalError::from_raw(status))
-        }
+        set_joystick_outputs(
+            self.num,
+            outputs,
+            left.clamp(0.0, 1.0),
+            right.clamp(0.0, 1.0),
+        )
     }
 }
@@ -2,7 +2,9 @@ use hal_sys::HAL_JoystickPOVs;
 
 use super::{
     joystick::Joystick,
-    reactor::{add_trigger, remove_trigger, wait_for_released, wait_for_triggered},
+    reactor::{
+        add_trigger, is_active, remove_trigger, set_target, wait_for_released, wait_for_triggered,
+    },
     ReleaseTrigger, Trigger,
 };
 
@@ -14,16 +16,94 @@ pub(super) fn get_pov(povs: &HAL_JoystickPOVs, index: u32) -> Option<i16> {
     }
 }
 
+/// A direction of a pov. A pov reports an angle in 45 degree steps, or -1 when centered
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PovDirection {
+    Centered,
+    Up,
+    UpRight,
+    Right,
+    DownRight,
+    Down,
+    DownLeft,
+    Left,
+    UpLeft,
+}
+
+impl PovDirection {
+    /// Convert a raw pov angle into a direction, rounding to the nearest 45 degrees. Negative
+    /// values are centered
+    pub fn from_angle(angle: i16) -> Self {
+        if angle < 0 {
+            return Self::Centered;
+        }
+
+        match ((angle as i32 + 22) / 45) % 8 {
+            0 => Self::Up,
+            1 => Self::UpRight,
+            2 => Self::Right,
+            3 => Self::DownRight,
+            4 => Self::Down,
+            5 => Self::DownLeft,
+            6 => Self::Left,
+            _ => Self::UpLeft,
+        }
+    }
+
+    /// Get the raw angle of the direction, or -1 if centered
+    pub fn angle(&self) -> i16 {
+        match self {
+            Self::Centered => -1,
+            Self::Up => 0,
+            Self::UpRight => 45,
+            Self::Right => 90,
+            Self::DownRight => 135,
+            Self::Down => 180,
+            Self::DownLeft => 225,
+            Self::Left => 270,
+            Self::UpLeft => 315,
+        }
+    }
+}
+
 /// This defines when a [Pov] trigger activates
 #[derive(Copy, Clone, Debug)]
 pub enum PovTarget {
+    /// Activates when the pov is at exactly the given angle
     Raw(i16),
+    /// Activates when the pov is at any of the given angles
+    AnyOf(&'static [i16]),
+    /// Activates when the pov is pressed up, including the diagonals (315, 0 and 45)
+    Up,
+    /// Activates when the pov is pressed right, including the diagonals (45, 90 and 135)
+    Right,
+    /// Activates when the pov is pressed down, including the diagonals (135, 180 and 225)
+    Down,
+    /// Activates when the pov is pressed left, including the diagonals (225, 270 and 315)
+    Left,
+    /// Activates when the pov is pressed in any direction
+    AnyDirection,
+    /// Activates when the pov is not pressed
+    Centered,
+}
+
+impl From<i16> for PovTarget {
+    fn from(value: i16) -> Self {
+        Self::Raw(value)
+    }
 }
 
 impl PovTarget {
     pub(super) fn is_active(&self, value: i16) -> bool {
         match self {
             Self::Raw(desired) => value == *desired,
+            Self::AnyOf(desired) => desired.contains(&value),
+            Self::Up => matches!(value, 315 | 0 | 45),
+            Self::Right => matches!(value, 45 | 90 | 135),
+            Self::Down => matches!(value, 135 | 180 | 225),
+            Self::Left => matches!(value, 225 | 270 | 315),
+            Self::AnyDirection => value >= 0,
+            Self::Centered => value < 0,
         }
     }
 }
@@ -68,8 +148,14 @@ impl Pov {
         get_pov(&self.joystick.get_pov_data(), self.pov_index)
     }
 
+    /// Get the current direction of the pov. Returns [None] if the pov does not exist
+    pub fn direction(&self) -> Option<PovDirection> {
+        self.value().map(PovDirection::from_angle)
+    }
+
     /// Set the target of the trigger
     pub fn set_target(&mut self, target: PovTarget) {
+        set_target(self.reactor_idx, target.into());
         self.target = target;
     }
 }
@@ -87,4 +173,8 @@ impl ReleaseTrigger for Pov {
     async fn wait_for_release(&mut self) -> Result<(), ()> {
         wait_for_released(self.reactor_idx).await
     }
+
+    fn is_active(&self) -> Option<bool> {
+        is_active(self.reactor_idx)
+    }
 }
@@ -1,13 +1,12 @@
 use std::{
-    mem::MaybeUninit,
     ops::DerefMut,
     pin::Pin,
     sync::LazyLock,
     task::{Poll, Waker},
+    time::Duration,
 };
 
 use futures::Future;
-use hal_sys::{HAL_GetAllJoystickData, HAL_JoystickAxes, HAL_JoystickButtons, HAL_JoystickPOVs};
 use linkme::distributed_slice;
 use parking_lot::Mutex;
 use slab::Slab;
@@ -18,14 +17,21 @@ use super::{
     button::{get_button, ButtonTarget},
     joystick::Joystick,
     pov::{get_pov, PovTarget},
+    source::{get_all_joystick_data, JoystickData},
+    stick::{get_stick, StickTarget},
 };
-use crate::PERIODIC_CHECKS;
+use crate::{time::get_time, PERIODIC_CHECKS};
 
 #[derive(Debug)]
 pub enum Target {
     Button(ButtonTarget),
     Axis(AxisTarget),
     Pov(PovTarget),
+    /// A pair of axes. The x axis is the trigger's index
+    Stick {
+        y_index: u32,
+        target: StickTarget,
+    },
 }
 
 impl From<ButtonTarget> for Target {
@@ -40,6 +46,12 @@ impl From<AxisTarget> for Target {
     }
 }
 
+impl From<(u32, StickTarget)> for Target {
+    fn from((y_index, target): (u32, StickTarget)) -> Self {
+        Self::Stick { y_index, target }
+    }
+}
+
 impl From<PovTarget> for Target {
     fn from(value: PovTarget) -> Self {
         Self::Pov(value)
@@ -51,6 +63,7 @@ fn type_from_target(target: &Target) -> &'static str {
         Target::Button(_) => "button",
         Target::Axis(_) => "axis",
         Target::Pov(_) => "pov",
+        Target::Stick { .. } => "stick",
     }
 }
 
@@ -135,6 +148,25 @@ async fn wait_for_reactor(idx: usize, on_release: bool) -> Result<(), ()> {
     .await
 }
 
+/// Whether the trigger is currently active, or [None] if the joystick has not been read yet or
+/// the input is out of range
+pub fn is_active(idx: usize) -> Option<bool> {
+    let queue = QUEUE.lock();
+    let item = queue.get(idx).unwrap();
+
+    let state = match item.state {
+        // The trigger was added since the last tick, so check it against that tick's data
+        State::Unknown => evaluate(item, &latest_snapshot()?.1),
+        state => state,
+    };
+
+    match state {
+        State::Triggered => Some(true),
+        State::Release => Some(false),
+        State::OutOfRange | State::Unknown => None,
+    }
+}
+
 pub async fn wait_for_triggered(idx: usize) -> Result<(), ()> {
     wait_for_reactor(idx, false).await
 }
@@ -191,29 +223,80 @@ impl Future for ReactorFuture {
     }
 }
 
-fn get_all_joystick_data() -> (
-    [HAL_JoystickAxes; 6],
-    [HAL_JoystickPOVs; 6],
-    [HAL_JoystickButtons; 6],
-) {
-    let mut axis: MaybeUninit<[HAL_JoystickAxes; 6]> = MaybeUninit::uninit();
-    let mut povs: MaybeUninit<[HAL_JoystickPOVs; 6]> = MaybeUninit::uninit();
-    let mut buttons: MaybeUninit<[HAL_JoystickButtons; 6]> = MaybeUninit::uninit();
-
-    unsafe {
-        HAL_GetAllJoystickData(
-            axis.as_mut_ptr() as *mut HAL_JoystickAxes,
-            povs.as_mut_ptr() as *mut HAL_JoystickPOVs,
-            buttons.as_mut_ptr() as *mut HAL_JoystickButtons,
-        )
+/// The joystick data read on the last poll and how many polls have happened
+static SNAPSHOT: Mutex<Option<(u64, [JoystickData; 6])>> = Mutex::new(None);
+
+/// Get the joystick data read on the last poll along with a counter that increases every poll.
+/// Returns [None] before the first poll
+pub(super) fn latest_snapshot() -> Option<(u64, [JoystickData; 6])> {
+    *SNAPSHOT.lock()
+}
+
+/// Tasks waiting for each joystick to be connected or disconnected. Each waiting future owns one
+/// entry, which it removes when it is dropped
+static CONNECTION_WAKERS: Mutex<[Slab<Waker>; 6]> = Mutex::new([const { Slab::new() }; 6]);
+
+/// Returns true if the joystick had any inputs on the last poll. This is always false before the
+/// first poll
+pub(super) fn is_connected(num: u32) -> bool {
+    latest_snapshot().is_some_and(|(_, data)| data[num as usize].is_connected())
+}
+
+/// Wait until the joystick is connected, or disconnected if `connected` is false. Returns
+/// immediately if it already is
+pub(super) async fn wait_for_connection(num: u32, connected: bool) {
+    ConnectionFuture {
+        num,
+        connected,
+        key: None,
     }
+    .await
+}
 
-    unsafe {
-        (
-            axis.assume_init(),
-            povs.assume_init(),
-            buttons.assume_init(),
-        )
+#[derive(Debug)]
+struct ConnectionFuture {
+    num: u32,
+    connected: bool,
+    key: Option<usize>,
+}
+
+impl Future for ConnectionFuture {
+    type Output = ();
+
+    fn poll(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Self::Output> {
+        let inner = Pin::into_inner(self);
+
+        // Hold the lock while checking so a connection change can't happen before the waker is
+        // stored
+        let mut connection_wakers = CONNECTION_WAKERS.lock();
+
+        if is_connected(inner.num) == inner.connected {
+            return Poll::Ready(());
+        }
+
+        let wakers = &mut connection_wakers[inner.num as usize];
+
+        match inner.key.and_then(|key| wakers.get_mut(key)) {
+            Some(existing) => {
+                if !existing.will_wake(cx.waker()) {
+                    *existing = cx.waker().clone();
+                }
+            }
+            None => inner.key = Some(wakers.insert(cx.waker().clone())),
+        }
+
+        Poll::Pending
+    }
+}
+
+impl Drop for ConnectionFuture {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            CONNECTION_WAKERS.lock()[self.num as usize].remove(key);
+        }
     }
 }
 
@@ -221,52 +304,45 @@ static POLL_SPAN: LazyLock<Span> = LazyLock::new(|| span!(Level::TRACE, "hid pol
 
 #[distributed_slice(PERIODIC_CHECKS)]
 fn poll() {
+    update(get_time());
+}
+
+/// Read the input source once for the tick at `time` and wake any triggers whose state changed
+pub(crate) fn update(time: Duration) {
     let _span_guard = POLL_SPAN.enter();
     let mut queue = QUEUE.lock();
 
-    let data = get_all_joystick_data();
+    let data = get_all_joystick_data(time);
 
-    for (_, item) in queue.deref_mut() {
-        let _inner_span_guard = item.span.enter();
+    let previous = {
+        let mut snapshot = SNAPSHOT.lock();
+        let previous = snapshot.map(|(_, data)| data);
+        let tick = snapshot.map(|(tick, _)| tick + 1).unwrap_or(0);
+        *snapshot = Some((tick, data));
 
-        let new_state = match item.target {
-            Target::Button(target) => {
-                let buttons = &data.2[item.joystick.get_num() as usize];
-                if let Some(value) = get_button(buttons, item.idx) {
-                    if target.is_active(value) {
-                        State::Triggered
-                    } else {
-                        State::Release
-                    }
-                } else {
-                    State::OutOfRange
-                }
-            }
-            Target::Axis(target) => {
-                let axis = &data.0[item.joystick.get_num() as usize];
-                if let Some(value) = get_axis(axis, item.idx) {
-                    if target.is_active(value) {
-                        State::Triggered
-                    } else {
-                        State::Release
-                    }
-                } else {
-                    State::OutOfRange
-                }
-            }
-            Target::Pov(target) => {
-                let povs = &data.1[item.joystick.get_num() as usize];
-                if let Some(value) = get_pov(povs, item.idx) {
-                    if target.is_active(value) {
-                        State::Triggered
-                    } else {
-                        State::Release
-                    }
-                } else {
-                    State::OutOfRange
+        previous
+    };
+
+    {
+        let mut connection_wakers = CONNECTION_WAKERS.lock();
+
+        for (num, wakers) in connection_wakers.iter_mut().enumerate() {
+            let connected = data[num].is_connected();
+
+            if previous.map(|previous| previous[num].is_connected()) != Some(connected) {
+                trace!(joystick = num, connected, "Connection changed");
+
+                for (_, waker) in wakers.iter() {
+                    waker.wake_by_ref();
                 }
             }
-        };
+        }
+    }
+
+    for (_, item) in queue.deref_mut() {
+        let _inner_span_guard = item.span.enter();
+
+        let new_state = evaluate(item, &data);
 
         if new_state != item.state {
             trace!(old_state = ?item.state, ?new_state, "Changing state");
@@ -287,3 +363,107 @@ fn poll() {
         }
     }
 }
+
+/// Get the state of the trigger from the joystick data of a tick
+fn evaluate(item: &JoystickQueueItem, data: &[JoystickData; 6]) -> State {
+    match item.target {
+        Target::Button(target) => {
+            let buttons = &data[item.joystick.get_num() as usize].buttons;
+            if let Some(value) = get_button(buttons, item.idx) {
+                if target.is_active(value) {
+                    State::Triggered
+                } else {
+                    State::Release
+                }
+            } else {
+                State::OutOfRange
+            }
+        }
+        Target::Axis(target) => {
+            let axis = &data[item.joystick.get_num() as usize].axes;
+            if let Some(value) = get_axis(axis, item.idx) {
+                if target.is_active(value, item.state == State::Triggered) {
+                    State::Triggered
+                } else {
+                    State::Release
+                }
+            } else {
+                State::OutOfRange
+            }
+        }
+        Target::Stick { y_index, target } => {
+            let axes = &data[item.joystick.get_num() as usize].axes;
+            if let Some(value) = get_stick(axes, item.idx, y_index) {
+                if target.is_active(value, item.state == State::Triggered) {
+                    State::Triggered
+                } else {
+                    State::Release
+                }
+            } else {
+                State::OutOfRange
+            }
+        }
+        Target::Pov(target) => {
+            let povs = &data[item.joystick.get_num() as usize].povs;
+            if let Some(value) = get_pov(povs, item.idx) {
+                if target.is_active(value) {
+                    State::Triggered
+                } else {
+                    State::Release
+                }
+            } else {
+                State::OutOfRange
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::CONNECTION_WAKERS;
+    use crate::{
+        hid::{joystick::Joystick, source::ScriptedInput},
+        testing::{Polled, Sim},
+    };
+
+    #[test]
+    fn connection_waker_is_stored_once() {
+        let mut sim = Sim::new(
+            Duration::ZERO,
+            ScriptedInput::new().connect(Duration::from_millis(100), 2, 1, 0, 0),
+        );
+
+        let joystick = Joystick::new(2);
+        let mut future = Polled::new(joystick.wait_for_connected());
+
+        for _ in 0..5 {
+            assert!(future.poll().is_pending());
+        }
+
+        assert_eq!(CONNECTION_WAKERS.lock()[2].len(), 1);
+
+        assert!(sim.run_until(&mut future, Duration::from_secs(1)).is_some());
+        assert_eq!(sim.now(), Duration::from_millis(100));
+
+        drop(future);
+
+        assert!(CONNECTION_WAKERS.lock()[2].is_empty());
+    }
+
+    #[test]
+    fn dropped_waiter_is_removed() {
+        let _sim = Sim::new(Duration::ZERO, ScriptedInput::new());
+
+        let joystick = Joystick::new(3);
+        let mut future = Polled::new(joystick.wait_for_connected());
+
+        assert!(future.poll().is_pending());
+        assert_eq!(CONNECTION_WAKERS.lock()[3].len(), 1);
+
+        drop(future);
+
+        assert!(CONNECTION_WAKERS.lock()[3].is_empty());
+    }
+}
@@ -0,0 +1,454 @@
+use std::{cell::RefCell, time::Duration};
+
+use futures_concurrency::future::Race;
+use tracing::{trace, warn};
+
+use crate::{
+    ds::wait_for_disabled,
+    time::{delay, get_time},
+    yield_now,
+};
+
+use super::joystick::Joystick;
+
+/// One step of a [RumblePattern]. Strengths are between 0 and 1
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RumbleStep {
+    /// Hold the given strengths for the duration
+    Hold {
+        left: f32,
+        right: f32,
+        duration: Duration,
+    },
+    /// Linearly change both motors from one strength to another over the duration
+    Ramp {
+        from: f32,
+        to: f32,
+        duration: Duration,
+    },
+}
+
+/// A sequence of rumble steps that can be played with [Joystick::play_rumble]
+///
+/// # Example
+///
+/// ```rust
+/// // Let the driver know a game piece was picked up
+/// controller
+///     .play_rumble(&RumblePattern::double_pulse(1.0, Duration::from_millis(150)), 1)
+///     .await;
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RumblePattern {
+    steps: Vec<RumbleStep>,
+}
+
+impl RumblePattern {
+    /// Create an empty pattern
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A single pulse of both motors
+    pub fn pulse(strength: f32, duration: Duration) -> Self {
+        Self::new().rumble(strength, strength, duration)
+    }
+
+    /// Two pulses of both motors separated by a pause of the same length
+    pub fn double_pulse(strength: f32, duration: Duration) -> Self {
+        Self::pulse(strength, duration)
+            .pause(duration)
+            .then(Self::pulse(strength, duration))
+    }
+
+    /// Both motors linearly change from one strength to another
+    pub fn ramp(from: f32, to: f32, duration: Duration) -> Self {
+        Self {
+            steps: vec![RumbleStep::Ramp { from, to, duration }],
+        }
+    }
+
+    /// Add a step that holds the given strengths
+    pub fn rumble(mut self, left: f32, right: f32, duration: Duration) -> Self {
+        self.steps.push(RumbleStep::Hold {
+            left,
+            right,
+            duration,
+        });
+        self
+    }
+
+    /// Add a step with both motors off
+    pub fn pause(self, duration: Duration) -> Self {
+        self.rumble(0.0, 0.0, duration)
+    }
+
+    /// Play another pattern after this one
+    pub fn then(mut self, other: RumblePattern) -> Self {
+        self.steps.extend(other.steps);
+        self
+    }
+
+    /// Play this pattern the given number of times
+    pub fn repeat(self, times: usize) -> Self {
+        Self {
+            steps: self.steps.repeat(times),
+        }
+    }
+
+    pub fn steps(&self) -> &[RumbleStep] {
+        &self.steps
+    }
+}
+
+struct RumbleRequest {
+    id: u64,
+    priority: u32,
+    left: f32,
+    right: f32,
+}
+
+#[derive(Default)]
+struct RumbleState {
+    next_id: u64,
+    requests: Vec<RumbleRequest>,
+}
+
+thread_local! {
+    static RUMBLE_STATES: [RefCell<RumbleState>; 6] = Default::default();
+}
+
+/// Set the joystick output to the highest priority request. Newer requests win ties
+fn apply(joystick: Joystick) {
+    let (left, right) = RUMBLE_STATES.with(|states| {
+        states[joystick.get_num() as usize]
+            .borrow()
+            .requests
+            .iter()
+            .max_by_key(|request| (request.priority, request.id))
+            .map(|request| (request.left, request.right))
+            .unwrap_or((0.0, 0.0))
+    });
+
+    if let Err(err) = joystick.rumble(1, left, right) {
+        warn!("Could not set rumble: {}", err);
+    }
+}
+
+/// Removes the request from the arbitration list when dropped, so cancelled patterns stop
+/// rumbling
+struct RequestGuard {
+    joystick: Joystick,
+    id: u64,
+}
+
+impl RequestGuard {
+    fn new(joystick: Joystick, priority: u32) -> Self {
+        let id = RUMBLE_STATES.with(|states| {
+            let mut state = states[joystick.get_num() as usize].borrow_mut();
+            let id = state.next_id;
+            state.next_id += 1;
+            state.requests.push(RumbleRequest {
+                id,
+                priority,
+                left: 0.0,
+                right: 0.0,
+            });
+            id
+        });
+
+        Self { joystick, id }
+    }
+
+    fn set(&self, left: f32, right: f32) {
+        RUMBLE_STATES.with(|states| {
+            let mut state = states[self.joystick.get_num() as usize].borrow_mut();
+            if let Some(request) = state
+                .requests
+                .iter_mut()
+                .find(|request| request.id == self.id)
+            {
+                request.left = left;
+                request.right = right;
+            }
+        });
+
+        apply(self.joystick);
+    }
+}
+
+impl Drop for RequestGuard {
+    fn drop(&mut self) {
+        RUMBLE_STATES.with(|states| {
+            states[self.joystick.get_num() as usize]
+                .borrow_mut()
+                .requests
+                .retain(|request| request.id != self.id);
+        });
+
+        apply(self.joystick);
+    }
+}
+
+impl Joystick {
+    /// Play a rumble pattern. If multiple patterns are playing at the same time, only the one
+    /// with the highest priority is felt, and newer patterns win ties. The rumble stops when the
+    /// pattern finishes, this future is dropped, or the robot is disabled. This must be called
+    /// from the robot thread
+    pub async fn play_rumble(&self, pattern: &RumblePattern, priority: u32) {
+        let request = RequestGuard::new(*self, priority);
+
+        (
+            async {
+                for step in pattern.steps() {
+                    trace!(?step, "Rumble step");
+
+                    match *step {
+                        RumbleStep::Hold {
+                            left,
+                            right,
+                            duration,
+                        } => {
+                            request.set(left, right);
+                            delay(duration).await;
+                        }
+                        RumbleStep::Ramp { from, to, duration } => {
+                            let start = get_time();
+
+                            loop {
+                                let progress = (get_time() - start).as_secs_f32()
+                                    / duration.as_secs_f32().max(f32::EPSILON);
+
+                                if progress >= 1.0 {
+                                    break;
+                                }
+
+                                let strength = from + (to - from) * progress;
+                                request.set(strength, strength);
+
+                                yield_now().await;
+                            }
+                        }
+                    }
+                }
+            },
+            async {
+                wait_for_disabled().await;
+                trace!("Robot disabled, stopping rumble");
+            },
+        )
+            .race()
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{future::Future, time::Duration};
+
+    use super::RumblePattern;
+    use crate::{
+        ds::State,
+        hid::{
+            joystick::Joystick,
+            source::{RecordedRumble, ScriptedInput},
+        },
+        testing::{Polled, Sim},
+    };
+
+    const EPSILON: f32 = 1e-4;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    /// An enabled robot with joystick 0 connected
+    fn enabled() -> (Sim, RecordedRumble) {
+        let script = ScriptedInput::new().connect(Duration::ZERO, 0, 1, 0, 0);
+        let rumble = script.rumble();
+
+        let sim = Sim::new(Duration::ZERO, script);
+        sim.set_state(State::Teleop);
+
+        (sim, rumble)
+    }
+
+    /// Play the future every tick and record the left rumble strength after each poll, until the
+    /// future completes. Returns the strengths and the time the future completed at
+    fn record(
+        sim: &mut Sim,
+        rumble: &RecordedRumble,
+        future: impl Future<Output = ()>,
+    ) -> (Vec<f32>, Duration) {
+        let mut future = Polled::new(future);
+        let mut strengths = Vec::new();
+
+        loop {
+            let done = future.poll().is_ready();
+            strengths.push(rumble.get(0).0);
+
+            if done {
+                return (strengths, sim.now());
+            }
+
+            assert!(sim.now() < Duration::from_secs(5));
+            sim.step();
+        }
+    }
+
+    fn assert_strengths(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len(), "{actual:?}");
+
+        for (actual_strength, expected_strength) in actual.iter().zip(expected) {
+            assert!(
+                (actual_strength - expected_strength).abs() < EPSILON,
+                "expected {expected:?}, got {actual:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn pulse_timing() {
+        let (mut sim, rumble) = enabled();
+        let joystick = Joystick::new(0);
+
+        let (strengths, end) = record(
+            &mut sim,
+            &rumble,
+            joystick.play_rumble(&RumblePattern::pulse(0.8, ms(50)), 1),
+        );
+
+        assert_strengths(&strengths, &[0.8, 0.8, 0.8, 0.0]);
+        assert_eq!(end, ms(60));
+    }
+
+    #[test]
+    fn double_pulse_timing() {
+        let (mut sim, rumble) = enabled();
+        let joystick = Joystick::new(0);
+
+        let (strengths, end) = record(
+            &mut sim,
+            &rumble,
+            joystick.play_rumble(&RumblePattern::double_pulse(1.0, ms(50)), 1),
+        );
+
+        assert_strengths(
+            &strengths,
+            &[1.0, 1.0, 1.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 0.0],
+        );
+        assert_eq!(end, ms(180));
+    }
+
+    #[test]
+    fn ramp_timing() {
+        let (mut sim, rumble) = enabled();
+        let joystick = Joystick::new(0);
+
+        let (strengths, end) = record(
+            &mut sim,
+            &rumble,
+            joystick.play_rumble(&RumblePattern::ramp(0.0, 1.0, ms(100)), 1),
+        );
+
+        assert_strengths(&strengths, &[0.0, 0.2, 0.4, 0.6, 0.8, 0.0]);
+        assert_eq!(end, ms(100));
+    }
+
+    #[test]
+    fn repeat_timing() {
+        let (mut sim, rumble) = enabled();
+        let joystick = Joystick::new(0);
+
+        let pattern = RumblePattern::pulse(1.0, ms(30)).pause(ms(30)).repeat(2);
+        assert_eq!(pattern.steps().len(), 4);
+
+        let (strengths, end) = record(&mut sim, &rumble, joystick.play_rumble(&pattern, 1));
+
+        assert_strengths(&strengths, &[1.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 0.0]);
+        assert_eq!(end, ms(160));
+    }
+
+    #[test]
+    fn higher_priority_preempts() {
+        let (mut sim, rumble) = enabled();
+        let joystick = Joystick::new(0);
+
+        let low_pattern = RumblePattern::pulse(0.3, ms(990));
+        let high_pattern = RumblePattern::pulse(0.9, ms(190));
+
+        let mut low = Polled::new(joystick.play_rumble(&low_pattern, 1));
+        let mut high = Polled::new(joystick.play_rumble(&high_pattern, 2));
+
+        let (mut low_end, mut high_end) = (None, None);
+
+        while sim.now() <= ms(1100) {
+            if low.poll().is_ready() {
+                low_end = Some(sim.now());
+            }
+
+            // The higher priority pattern starts later
+            if sim.now() >= ms(100) && high.poll().is_ready() {
+                high_end = Some(sim.now());
+            }
+
+            let expected = if sim.now() >= ms(1000) {
+                0.0
+            } else if sim.now() >= ms(100) && sim.now() < ms(300) {
+                0.9
+            } else {
+                0.3
+            };
+
+            assert_eq!(rumble.get(0), (expected, expected), "at {:?}", sim.now());
+
+            sim.step();
+        }
+
+        assert_eq!(high_end, Some(ms(300)));
+        assert_eq!(low_end, Some(ms(1000)));
+    }
+
+    #[test]
+    fn lower_priority_resumes_when_cancelled() {
+        let (mut sim, rumble) = enabled();
+        let joystick = Joystick::new(0);
+
+        let low_pattern = RumblePattern::pulse(0.3, ms(990));
+        let high_pattern = RumblePattern::pulse(0.9, ms(990));
+
+        let mut low = Polled::new(joystick.play_rumble(&low_pattern, 1));
+        let mut high = Polled::new(joystick.play_rumble(&high_pattern, 2));
+
+        assert!(low.poll().is_pending());
+        assert!(high.poll().is_pending());
+        assert_eq!(rumble.get(0), (0.9, 0.9));
+
+        sim.step();
+        drop(high);
+
+        assert!(low.poll().is_pending());
+        assert_eq!(rumble.get(0), (0.3, 0.3));
+
+        drop(low);
+
+        assert_eq!(rumble.get(0), (0.0, 0.0));
+    }
+
+    #[test]
+    fn disabling_stops_rumble() {
+        let (mut sim, rumble) = enabled();
+        let joystick = Joystick::new(0);
+
+        let pattern = RumblePattern::pulse(1.0, ms(990));
+        let mut future = Polled::new(joystick.play_rumble(&pattern, 1));
+
+        assert!(sim.run_until(&mut future, ms(100)).is_none());
+        assert_eq!(rumble.get(0), (1.0, 1.0));
+
+        sim.set_state(State::Disabled);
+
+        assert!(future.poll().is_ready());
+        assert_eq!(rumble.get(0), (0.0, 0.0));
+    }
+}
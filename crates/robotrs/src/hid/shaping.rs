@@ -0,0 +1,413 @@
+use std::time::Duration;
+
+use math::{
+    filter::{Filter, SlewRateLimiter},
+    get_time,
+};
+
+/// The response curve applied to an axis after the deadband. All curves keep the sign of the
+/// input and map 1 to 1
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ResponseCurve {
+    /// Output is proportional to the input
+    Linear,
+    /// Output is the input squared, giving finer control near the center
+    Square,
+    /// Output is the input cubed, giving even finer control near the center
+    Cubic,
+    /// Output is the input raised to the given power
+    Power(f32),
+}
+
+impl ResponseCurve {
+    /// Apply the curve to a value between -1 and 1
+    pub fn apply(&self, value: f32) -> f32 {
+        match self {
+            ResponseCurve::Linear => value,
+            ResponseCurve::Square => value * value.abs(),
+            ResponseCurve::Cubic => value.powi(3),
+            ResponseCurve::Power(power) => value.abs().powf(*power).copysign(value),
+        }
+    }
+}
+
+/// Apply a deadband to a value, rescaling the result so the output starts at 0 at the edge of
+/// the deadband and still reaches 1 at full deflection
+pub fn scaled_deadband(value: f32, deadband: f32) -> f32 {
+    if value.abs() <= deadband {
+        0.0
+    } else {
+        (value.abs() - deadband) / (1.0 - deadband) * value.signum()
+    }
+}
+
+/// A configurable pipeline for a single axis. The steps are applied in this order: clamping to
+/// -1 to 1, scaled deadband, response curve, inversion, scaling, and then rate limiting.
+///
+/// # Example
+///
+/// ```rust
+/// let mut forward = ShapedAxis::new(
+///     move || controller.left_y(),
+///     AxisShaper::new()
+///         .deadband(0.1)
+///         .curve(ResponseCurve::Square)
+///         .inverted(true),
+/// );
+///
+/// drivetrain.drive(forward.get())?;
+/// ```
+pub struct AxisShaper {
+    deadband: f32,
+    curve: ResponseCurve,
+    inverted: bool,
+    scale: f32,
+    limiter: Option<SlewRateLimiter>,
+}
+
+impl Default for AxisShaper {
+    fn default() -> Self {
+        Self {
+            deadband: 0.0,
+            curve: ResponseCurve::Linear,
+            inverted: false,
+            scale: 1.0,
+            limiter: None,
+        }
+    }
+}
+
+impl AxisShaper {
+    /// Create a shaper that passes values through unchanged
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the deadband. See [scaled_deadband]
+    pub fn deadband(self, deadband: f32) -> Self {
+        Self {
+            deadband: deadband.clamp(0.0, 1.0 - f32::EPSILON),
+            ..self
+        }
+    }
+
+    /// Set the response curve
+    pub fn curve(self, curve: ResponseCurve) -> Self {
+        Self { curve, ..self }
+    }
+
+    /// Flip the sign of the output
+    pub fn inverted(self, inverted: bool) -> Self {
+        Self { inverted, ..self }
+    }
+
+    /// Multiply the output by a constant, for example to slow down a drivetrain
+    pub fn scale(self, scale: f32) -> Self {
+        Self { scale, ..self }
+    }
+
+    /// Limit how fast the output can change, in units per second
+    pub fn slew_rate(self, limit: f32) -> Self {
+        Self {
+            limiter: Some(SlewRateLimiter::new(limit)),
+            ..self
+        }
+    }
+
+    /// Run a raw value through the pipeline
+    pub fn apply(&mut self, value: f32) -> f32 {
+        self.apply_with_time(value, get_time())
+    }
+
+    /// Run a raw value through the pipeline at the given time. The time is only used by the rate
+    /// limiter
+    pub fn apply_with_time(&mut self, value: f32, time: Duration) -> f32 {
+        let value = scaled_deadband(value.clamp(-1.0, 1.0), self.deadband);
+        let value = self.curve.apply(value);
+        let value = if self.inverted { -value } else { value };
+        let value = value * self.scale;
+
+        if let Some(limiter) = &mut self.limiter {
+            limiter.apply_with_time(value, time)
+        } else {
+            value
+        }
+    }
+}
+
+/// An axis with an [AxisShaper] applied to it. The source can be anything that returns an axis
+/// value, such as the accessors generated by [define_axes](crate::define_axes)
+pub struct ShapedAxis<F> {
+    source: F,
+    shaper: AxisShaper,
+}
+
+impl<F: FnMut() -> Option<f32>> ShapedAxis<F> {
+    pub fn new(source: F, shaper: AxisShaper) -> Self {
+        Self { source, shaper }
+    }
+
+    /// Get the shaped value, returning [None] if the axis does not exist
+    pub fn value(&mut self) -> Option<f32> {
+        (self.source)().map(|value| self.shaper.apply(value))
+    }
+
+    /// Get the shaped value. A missing axis is treated as centered, so this returns 0
+    pub fn get(&mut self) -> f32 {
+        self.value().unwrap_or_else(|| self.shaper.apply(0.0))
+    }
+}
+
+/// A pipeline for a pair of axes, such as the x and y axes of a stick. The deadband is applied to
+/// the magnitude of the stick so that diagonal movement is not distorted.
+#[derive(Clone, Copy, Debug)]
+pub struct StickShaper {
+    deadband: f32,
+    curve: ResponseCurve,
+    invert_x: bool,
+    invert_y: bool,
+    scale: f32,
+}
+
+impl Default for StickShaper {
+    fn default() -> Self {
+        Self {
+            deadband: 0.0,
+            curve: ResponseCurve::Linear,
+            invert_x: false,
+            invert_y: false,
+            scale: 1.0,
+        }
+    }
+}
+
+impl StickShaper {
+    /// Create a shaper that passes values through unchanged
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the radial deadband
+    pub fn deadband(self, deadband: f32) -> Self {
+        Self {
+            deadband: deadband.clamp(0.0, 1.0 - f32::EPSILON),
+            ..self
+        }
+    }
+
+    /// Set the response curve. This is applied to the magnitude of the stick
+    pub fn curve(self, curve: ResponseCurve) -> Self {
+        Self { curve, ..self }
+    }
+
+    /// Flip the sign of each axis
+    pub fn inverted(self, invert_x: bool, invert_y: bool) -> Self {
+        Self {
+            invert_x,
+            invert_y,
+            ..self
+        }
+    }
+
+    /// Multiply the magnitude of the output by a constant
+    pub fn scale(self, scale: f32) -> Self {
+        Self { scale, ..self }
+    }
+
+    /// Run a raw pair of values through the pipeline
+    pub fn apply(&self, x: f32, y: f32) -> (f32, f32) {
+        let magnitude = x.hypot(y);
+
+        if magnitude <= self.deadband {
+            return (0.0, 0.0);
+        }
+
+        let shaped = self
+            .curve
+            .apply(scaled_deadband(magnitude.min(1.0), self.deadband))
+            * self.scale;
+
+        let x = x / magnitude * shaped;
+        let y = y / magnitude * shaped;
+
+        (
+            if self.invert_x { -x } else { x },
+            if self.invert_y { -y } else { y },
+        )
+    }
+}
+
+/// A pair of axes with a [StickShaper] applied to them
+pub struct ShapedStick<X, Y> {
+    x: X,
+    y: Y,
+    shaper: StickShaper,
+}
+
+impl<X: FnMut() -> Option<f32>, Y: FnMut() -> Option<f32>> ShapedStick<X, Y> {
+    pub fn new(x: X, y: Y, shaper: StickShaper) -> Self {
+        Self { x, y, shaper }
+    }
+
+    /// Get the shaped values, returning [None] if either axis does not exist
+    pub fn value(&mut self) -> Option<(f32, f32)> {
+        Some(self.shaper.apply((self.x)()?, (self.y)()?))
+    }
+
+    /// Get the shaped values. Missing axes are treated as centered
+    pub fn get(&mut self) -> (f32, f32) {
+        self.value().unwrap_or((0.0, 0.0))
+    }
+}
+
+/// Adds shaping to the axis accessors of a controller type, such as the ones generated by
+/// [define_axes](crate::define_axes)
+///
+/// # Example
+///
+/// ```rust
+/// let mut turn = controller.shaped_axis(XboxController::right_x, AxisShaper::new().deadband(0.1));
+/// ```
+pub trait ShapeAxes: Copy + 'static {
+    /// Shape the axis returned by `axis`
+    fn shaped_axis(
+        &self,
+        axis: fn(&Self) -> Option<f32>,
+        shaper: AxisShaper,
+    ) -> ShapedAxis<impl FnMut() -> Option<f32>> {
+        let controller = *self;
+
+        ShapedAxis::new(move || axis(&controller), shaper)
+    }
+
+    /// Shape a pair of axes
+    fn shaped_stick(
+        &self,
+        x: fn(&Self) -> Option<f32>,
+        y: fn(&Self) -> Option<f32>,
+        shaper: StickShaper,
+    ) -> ShapedStick<impl FnMut() -> Option<f32>, impl FnMut() -> Option<f32>> {
+        let controller = *self;
+
+        ShapedStick::new(move || x(&controller), move || y(&controller), shaper)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{scaled_deadband, AxisShaper, ResponseCurve, StickShaper};
+
+    const EPSILON: f32 = 1e-5;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < EPSILON,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn scaled_deadband_rescales() {
+        assert_eq!(scaled_deadband(0.05, 0.1), 0.0);
+        assert_eq!(scaled_deadband(0.1, 0.1), 0.0);
+        assert_eq!(scaled_deadband(-0.1, 0.1), 0.0);
+
+        assert_close(scaled_deadband(0.55, 0.1), 0.5);
+        assert_close(scaled_deadband(-0.55, 0.1), -0.5);
+        assert_close(scaled_deadband(1.0, 0.1), 1.0);
+        assert_close(scaled_deadband(-1.0, 0.1), -1.0);
+    }
+
+    #[test]
+    fn curves_keep_sign() {
+        assert_close(ResponseCurve::Square.apply(0.5), 0.25);
+        assert_close(ResponseCurve::Square.apply(-0.5), -0.25);
+        assert_close(ResponseCurve::Cubic.apply(0.5), 0.125);
+        assert_close(ResponseCurve::Cubic.apply(-0.5), -0.125);
+        assert_close(ResponseCurve::Power(1.5).apply(-0.25), -0.125);
+
+        for curve in [
+            ResponseCurve::Linear,
+            ResponseCurve::Square,
+            ResponseCurve::Cubic,
+            ResponseCurve::Power(2.5),
+        ] {
+            assert_close(curve.apply(0.0), 0.0);
+            assert_close(curve.apply(1.0), 1.0);
+            assert_close(curve.apply(-1.0), -1.0);
+        }
+    }
+
+    #[test]
+    fn axis_pipeline() {
+        let mut shaper = AxisShaper::new()
+            .deadband(0.1)
+            .curve(ResponseCurve::Square)
+            .inverted(true);
+
+        assert_eq!(shaper.apply(0.05), 0.0);
+        assert_close(shaper.apply(0.55), -0.25);
+        assert_close(shaper.apply(-0.55), 0.25);
+        // Values past full scale are clamped before shaping
+        assert_close(shaper.apply(2.0), -1.0);
+
+        let mut scaled = AxisShaper::new().scale(0.5);
+        assert_close(scaled.apply(-1.0), -0.5);
+    }
+
+    #[test]
+    fn axis_slew_rate_is_limited() {
+        let mut shaper = AxisShaper::new().slew_rate(2.0);
+        let start = Duration::from_secs(1000);
+
+        assert_eq!(shaper.apply_with_time(0.0, start), 0.0);
+        assert_close(
+            shaper.apply_with_time(1.0, start + Duration::from_millis(100)),
+            0.2,
+        );
+        assert_close(
+            shaper.apply_with_time(1.0, start + Duration::from_millis(200)),
+            0.4,
+        );
+        assert_close(
+            shaper.apply_with_time(0.3, start + Duration::from_millis(300)),
+            0.3,
+        );
+    }
+
+    #[test]
+    fn stick_deadband_is_radial() {
+        let shaper = StickShaper::new().deadband(0.2);
+
+        assert_eq!(shaper.apply(0.1, 0.1), (0.0, 0.0));
+
+        // Each axis is inside the deadband on its own, but the stick is not
+        let (x, y) = shaper.apply(0.15, 0.15);
+        assert!(x > 0.0);
+        assert_close(x, y);
+
+        // The magnitude is rescaled and the direction is kept
+        let (x, y) = shaper.apply(0.3, -0.4);
+        assert_close(x.hypot(y), 0.375);
+        assert_close(x / y, 0.3 / -0.4);
+
+        let (x, y) = shaper.apply(1.0, 1.0);
+        assert_close(x, std::f32::consts::FRAC_1_SQRT_2);
+        assert_close(y, std::f32::consts::FRAC_1_SQRT_2);
+    }
+
+    #[test]
+    fn stick_curve_and_inversion() {
+        let shaper = StickShaper::new()
+            .curve(ResponseCurve::Square)
+            .inverted(true, false)
+            .scale(0.5);
+
+        let (x, y) = shaper.apply(0.3, 0.4);
+        assert_close(x.hypot(y), 0.125);
+        assert!(x < 0.0 && y > 0.0);
+        assert_close(x / y, -0.3 / 0.4);
+    }
+}
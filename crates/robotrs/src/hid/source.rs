@@ -0,0 +1,651 @@
+use std::{mem::MaybeUninit, sync::Arc, time::Duration};
+
+use hal_sys::{
+    HAL_GetAllJoystickData, HAL_JoystickAxes, HAL_JoystickButtons, HAL_JoystickPOVs,
+    HAL_SetJoystickOutputs,
+};
+use parking_lot::Mutex;
+use tracing::{debug, trace};
+
+use crate::error::HalError;
+
+/// The state of every input on a single joystick
+#[derive(Clone, Copy, Debug)]
+pub struct JoystickData {
+    pub axes: HAL_JoystickAxes,
+    pub povs: HAL_JoystickPOVs,
+    pub buttons: HAL_JoystickButtons,
+}
+
+impl Default for JoystickData {
+    /// A disconnected joystick with no inputs
+    fn default() -> Self {
+        Self {
+            axes: HAL_JoystickAxes {
+                count: 0,
+                axes: [0.0; 12],
+                raw: [0; 12],
+            },
+            povs: HAL_JoystickPOVs {
+                count: 0,
+                povs: [-1; 12],
+            },
+            buttons: HAL_JoystickButtons {
+                buttons: 0,
+                count: 0,
+            },
+        }
+    }
+}
+
+impl PartialEq for JoystickData {
+    fn eq(&self, other: &Self) -> bool {
+        self.axes.count == other.axes.count
+            && self.axes.axes == other.axes.axes
+            && self.povs.count == other.povs.count
+            && self.povs.povs == other.povs.povs
+            && self.buttons.count == other.buttons.count
+            && self.buttons.buttons == other.buttons.buttons
+    }
+}
+
+impl JoystickData {
+    /// Returns true if the joystick has any inputs. The driver station reports a disconnected
+    /// joystick as one with no inputs
+    pub fn is_connected(&self) -> bool {
+        self.buttons.count > 0 || self.axes.count > 0 || self.povs.count > 0
+    }
+
+    /// Set the number of each kind of input, as if a joystick with that layout was plugged in
+    pub fn connect(&mut self, buttons: u8, axes: i16, povs: i16) {
+        self.buttons.count = buttons.min(32);
+        self.axes.count = axes.min(12);
+        self.povs.count = povs.min(12);
+    }
+
+    /// Set the value of a button (zero indexed), adding it if the joystick has fewer buttons
+    pub fn set_button(&mut self, idx: u32, pressed: bool) {
+        if idx >= 32 {
+            return;
+        }
+
+        self.buttons.count = self.buttons.count.max(idx as u8 + 1);
+
+        if pressed {
+            self.buttons.buttons |= 1 << idx;
+        } else {
+            self.buttons.buttons &= !(1 << idx);
+        }
+    }
+
+    /// Set the value of an axis (zero indexed), adding it if the joystick has fewer axes
+    pub fn set_axis(&mut self, idx: u32, value: f32) {
+        if idx >= 12 {
+            return;
+        }
+
+        self.axes.count = self.axes.count.max(idx as i16 + 1);
+        self.axes.axes[idx as usize] = value.clamp(-1.0, 1.0);
+    }
+
+    /// Set the angle of a pov (zero indexed) in degrees, or -1 if centered, adding it if the
+    /// joystick has fewer povs
+    pub fn set_pov(&mut self, idx: u32, value: i16) {
+        if idx >= 12 {
+            return;
+        }
+
+        self.povs.count = self.povs.count.max(idx as i16 + 1);
+        self.povs.povs[idx as usize] = value;
+    }
+}
+
+/// Somewhere the HID reactor and joystick accessors get their values from. By default this is
+/// the driver station through the HAL, but it can be replaced with [set_input_source] to test
+/// bindings off of the robot. Joystick descriptors (names and types) always come from the HAL.
+pub trait InputSource: Send {
+    /// Get the current state of all six joysticks. This is called exactly once per tick by the
+    /// HID reactor, and `time` is the time of that tick as given by
+    /// [get_time](crate::time::get_time). Joystick
+    /// accessors read the values from that tick instead of calling this again
+    fn get_data(&mut self, time: Duration) -> [JoystickData; 6];
+
+    /// Set the outputs and rumble of a joystick. The rumble strengths are between 0 and 1. By
+    /// default this sends them to the driver station
+    fn set_outputs(
+        &mut self,
+        joystick: u32,
+        outputs: i64,
+        left: f32,
+        right: f32,
+    ) -> Result<(), HalError> {
+        HalSource.set_outputs(joystick, outputs, left, right)
+    }
+}
+
+/// Reads the joystick values from the driver station
+pub struct HalSource;
+
+impl InputSource for HalSource {
+    fn get_data(&mut self, _time: Duration) -> [JoystickData; 6] {
+        let mut axes: MaybeUninit<[HAL_JoystickAxes; 6]> = MaybeUninit::uninit();
+        let mut povs: MaybeUninit<[HAL_JoystickPOVs; 6]> = MaybeUninit::uninit();
+        let mut buttons: MaybeUninit<[HAL_JoystickButtons; 6]> = MaybeUninit::uninit();
+
+        unsafe {
+            HAL_GetAllJoystickData(
+                axes.as_mut_ptr() as *mut HAL_JoystickAxes,
+                povs.as_mut_ptr() as *mut HAL_JoystickPOVs,
+                buttons.as_mut_ptr() as *mut HAL_JoystickButtons,
+            )
+        }
+
+        let (axes, povs, buttons) = unsafe {
+            (
+                axes.assume_init(),
+                povs.assume_init(),
+                buttons.assume_init(),
+            )
+        };
+
+        std::array::from_fn(|idx| JoystickData {
+            axes: axes[idx],
+            povs: povs[idx],
+            buttons: buttons[idx],
+        })
+    }
+
+    fn set_outputs(
+        &mut self,
+        joystick: u32,
+        outputs: i64,
+        left: f32,
+        right: f32,
+    ) -> Result<(), HalError> {
+        let status = unsafe {
+            HAL_SetJoystickOutputs(
+                joystick as i32,
+                outputs,
+                (left * 65535.0) as i32,
+                (right * 65535.0) as i32,
+            )
+        };
+
+        if status == 0 {
+            Ok(())
+        } else {
+            Err(HalError::from_raw(status))
+        }
+    }
+}
+
+static SOURCE: Mutex<Option<Box<dyn InputSource>>> = Mutex::new(None);
+
+/// Replace the driver station as the source of joystick values
+pub fn set_input_source(source: impl InputSource + 'static) {
+    debug!("Replacing joystick input source");
+    *SOURCE.lock() = Some(Box::new(source));
+}
+
+/// Go back to reading joystick values from the driver station
+pub fn reset_input_source() {
+    debug!("Resetting joystick input source");
+    *SOURCE.lock() = None;
+}
+
+/// Get the current state of all joysticks from the active source
+pub(super) fn get_all_joystick_data(time: Duration) -> [JoystickData; 6] {
+    match SOURCE.lock().as_mut() {
+        Some(source) => source.get_data(time),
+        None => HalSource.get_data(time),
+    }
+}
+
+/// Set the outputs and rumble of a joystick through the active source
+pub(super) fn set_joystick_outputs(
+    joystick: u32,
+    outputs: i64,
+    left: f32,
+    right: f32,
+) -> Result<(), HalError> {
+    match SOURCE.lock().as_mut() {
+        Some(source) => source.set_outputs(joystick, outputs, left, right),
+        None => HalSource.set_outputs(joystick, outputs, left, right),
+    }
+}
+
+/// A single change in a [ScriptedInput]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InputEvent {
+    Connect {
+        joystick: u32,
+        buttons: u8,
+        axes: i16,
+        povs: i16,
+    },
+    Disconnect {
+        joystick: u32,
+    },
+    Button {
+        joystick: u32,
+        idx: u32,
+        pressed: bool,
+    },
+    Axis {
+        joystick: u32,
+        idx: u32,
+        value: f32,
+    },
+    Pov {
+        joystick: u32,
+        idx: u32,
+        value: i16,
+    },
+}
+
+impl InputEvent {
+    fn apply(&self, data: &mut [JoystickData; 6]) {
+        match *self {
+            InputEvent::Connect {
+                joystick,
+                buttons,
+                axes,
+                povs,
+            } => data[joystick as usize].connect(buttons, axes, povs),
+            InputEvent::Disconnect { joystick } => data[joystick as usize] = Default::default(),
+            InputEvent::Button {
+                joystick,
+                idx,
+                pressed,
+            } => data[joystick as usize].set_button(idx, pressed),
+            InputEvent::Axis {
+                joystick,
+                idx,
+                value,
+            } => data[joystick as usize].set_axis(idx, value),
+            InputEvent::Pov {
+                joystick,
+                idx,
+                value,
+            } => data[joystick as usize].set_pov(idx, value),
+        }
+    }
+}
+
+/// An [InputSource] that plays back a timeline of events. Times are measured from the first time
+/// the source is read, which is usually the first reactor tick after [set_input_source]. Rumble
+/// set on its joysticks is recorded instead of being sent to the driver station, see
+/// [ScriptedInput::rumble].
+///
+/// # Example
+///
+/// ```rust
+/// set_input_source(
+///     ScriptedInput::new()
+///         .connect(Duration::ZERO, 0, 10, 6, 1)
+///         .press(Duration::from_millis(1200), 0, 0)
+///         .release(Duration::from_millis(1500), 0, 0),
+/// );
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ScriptedInput {
+    events: Vec<(Duration, InputEvent)>,
+    next: usize,
+    start: Option<Duration>,
+    state: [JoystickData; 6],
+    rumble: RecordedRumble,
+}
+
+/// The rumble most recently set on each joystick of a [ScriptedInput]
+#[derive(Clone, Debug, Default)]
+pub struct RecordedRumble {
+    strengths: Arc<Mutex<[(f32, f32); 6]>>,
+}
+
+impl RecordedRumble {
+    /// Get the left and right rumble strengths of a joystick
+    pub fn get(&self, joystick: u32) -> (f32, f32) {
+        self.strengths.lock()[joystick as usize]
+    }
+}
+
+impl ScriptedInput {
+    /// Create a script where every joystick is disconnected
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an event at the given time. Events at the same time are applied in the order they
+    /// were added
+    pub fn event(mut self, time: Duration, event: InputEvent) -> Self {
+        let idx = self
+            .events
+            .partition_point(|(event_time, _)| *event_time <= time);
+        self.events.insert(idx, (time, event));
+        self
+    }
+
+    /// Connect a joystick with the given number of buttons, axes and povs
+    pub fn connect(self, time: Duration, joystick: u32, buttons: u8, axes: i16, povs: i16) -> Self {
+        self.event(
+            time,
+            InputEvent::Connect {
+                joystick,
+                buttons,
+                axes,
+                povs,
+            },
+        )
+    }
+
+    /// Disconnect a joystick, clearing all of its inputs
+    pub fn disconnect(self, time: Duration, joystick: u32) -> Self {
+        self.event(time, InputEvent::Disconnect { joystick })
+    }
+
+    /// Press a button
+    pub fn press(self, time: Duration, joystick: u32, idx: u32) -> Self {
+        self.event(
+            time,
+            InputEvent::Button {
+                joystick,
+                idx,
+                pressed: true,
+            },
+        )
+    }
+
+    /// Release a button
+    pub fn release(self, time: Duration, joystick: u32, idx: u32) -> Self {
+        self.event(
+            time,
+            InputEvent::Button {
+                joystick,
+                idx,
+                pressed: false,
+            },
+        )
+    }
+
+    /// Move an axis to a value
+    pub fn axis(self, time: Duration, joystick: u32, idx: u32, value: f32) -> Self {
+        self.event(
+            time,
+            InputEvent::Axis {
+                joystick,
+                idx,
+                value,
+            },
+        )
+    }
+
+    /// Move a pov to an angle in degrees, or -1 to center it
+    pub fn pov(self, time: Duration, joystick: u32, idx: u32, value: i16) -> Self {
+        self.event(
+            time,
+            InputEvent::Pov {
+                joystick,
+                idx,
+                value,
+            },
+        )
+    }
+
+    /// Returns true once every event has been applied
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.events.len()
+    }
+
+    /// Get a handle to the recorded rumble. This stays valid after the script is passed to
+    /// [set_input_source]
+    pub fn rumble(&self) -> RecordedRumble {
+        self.rumble.clone()
+    }
+}
+
+impl InputSource for ScriptedInput {
+    fn get_data(&mut self, time: Duration) -> [JoystickData; 6] {
+        let elapsed = time.saturating_sub(*self.start.get_or_insert(time));
+
+        while let Some((time, event)) = self.events.get(self.next) {
+            if *time > elapsed {
+                break;
+            }
+
+            trace!(?time, ?event, "Applying scripted input");
+            event.apply(&mut self.state);
+            self.next += 1;
+        }
+
+        self.state
+    }
+
+    fn set_outputs(
+        &mut self,
+        joystick: u32,
+        _outputs: i64,
+        left: f32,
+        right: f32,
+    ) -> Result<(), HalError> {
+        self.rumble.strengths.lock()[joystick as usize] = (left, right);
+
+        Ok(())
+    }
+}
+
+/// The time since the recording started and the state of every joystick
+pub type Frame = (Duration, [JoystickData; 6]);
+
+/// The joystick values recorded by an [InputRecorder]. Each frame is stored with the time since
+/// the recording started, and a frame is only stored when something changes.
+#[derive(Clone, Default)]
+pub struct Recording {
+    frames: Arc<Mutex<Vec<Frame>>>,
+}
+
+impl Recording {
+    /// Get a copy of the recorded frames
+    pub fn frames(&self) -> Vec<Frame> {
+        self.frames.lock().clone()
+    }
+
+    /// Create a source that replays this recording
+    pub fn playback(&self) -> Playback {
+        Playback {
+            frames: self.frames(),
+            next: 0,
+            start: None,
+            state: Default::default(),
+        }
+    }
+}
+
+/// Wraps another [InputSource] and records every change in its values
+///
+/// # Example
+///
+/// ```rust
+/// let recorder = InputRecorder::new(HalSource);
+/// let recording = recorder.recording();
+/// set_input_source(recorder);
+///
+/// // later, off of the robot
+/// set_input_source(recording.playback());
+/// ```
+pub struct InputRecorder<S> {
+    source: S,
+    recording: Recording,
+    start: Option<Duration>,
+}
+
+impl<S: InputSource> InputRecorder<S> {
+    pub fn new(source: S) -> Self {
+        Self {
+            source,
+            recording: Default::default(),
+            start: None,
+        }
+    }
+
+    /// Get a handle to the recording. This stays valid after the recorder is passed to
+    /// [set_input_source]
+    pub fn recording(&self) -> Recording {
+        self.recording.clone()
+    }
+}
+
+impl<S: InputSource> InputSource for InputRecorder<S> {
+    fn get_data(&mut self, time: Duration) -> [JoystickData; 6] {
+        let data = self.source.get_data(time);
+
+        let elapsed = time.saturating_sub(*self.start.get_or_insert(time));
+
+        let mut frames = self.recording.frames.lock();
+
+        if frames.last().map(|(_, last)| *last != data).unwrap_or(true) {
+            frames.push((elapsed, data));
+        }
+
+        data
+    }
+
+    fn set_outputs(
+        &mut self,
+        joystick: u32,
+        outputs: i64,
+        left: f32,
+        right: f32,
+    ) -> Result<(), HalError> {
+        self.source.set_outputs(joystick, outputs, left, right)
+    }
+}
+
+/// An [InputSource] that replays a [Recording]. Times are measured from the first time the
+/// source is read
+pub struct Playback {
+    frames: Vec<Frame>,
+    next: usize,
+    start: Option<Duration>,
+    state: [JoystickData; 6],
+}
+
+impl Playback {
+    /// Returns true once every frame has been played
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.frames.len()
+    }
+}
+
+impl InputSource for Playback {
+    fn get_data(&mut self, time: Duration) -> [JoystickData; 6] {
+        let elapsed = time.saturating_sub(*self.start.get_or_insert(time));
+
+        while let Some((time, data)) = self.frames.get(self.next) {
+            if *time > elapsed {
+                break;
+            }
+
+            self.state = *data;
+            self.next += 1;
+        }
+
+        self.state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::Cell,
+        rc::Rc,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use futures::future::pending;
+
+    use super::{InputSource, JoystickData, ScriptedInput};
+    use crate::{
+        hid::{ext::run_while_pressed, joystick::Joystick},
+        testing::{DropGuard, Polled, Sim},
+        time::get_time,
+    };
+
+    #[test]
+    fn scripted_press_runs_while_pressed() {
+        // The script is timed from the first tick, not from zero
+        let start = Duration::from_secs(5);
+        let mut sim = Sim::new(
+            start,
+            ScriptedInput::new()
+                .connect(Duration::ZERO, 0, 10, 6, 1)
+                .press(Duration::from_millis(1200), 0, 0)
+                .release(Duration::from_millis(1500), 0, 0),
+        );
+
+        let started = Rc::new(Cell::new(None));
+        let cancelled = Rc::new(Cell::new(None));
+
+        let mut button = Joystick::new(0).get_button(0);
+        let mut func = || {
+            let guard = DropGuard(cancelled.clone());
+
+            started.set(Some(get_time()));
+
+            async move {
+                let _guard = guard;
+                pending::<()>().await
+            }
+        };
+
+        let mut future = Polled::new(run_while_pressed(&mut button, &mut func));
+
+        assert!(sim
+            .run_until(&mut future, start + Duration::from_secs(3))
+            .is_some());
+        assert_eq!(started.get(), Some(start + Duration::from_millis(1200)));
+        assert_eq!(cancelled.get(), Some(start + Duration::from_millis(1500)));
+    }
+
+    /// Counts how many times it is read
+    struct CountingSource(Arc<AtomicUsize>);
+
+    impl InputSource for CountingSource {
+        fn get_data(&mut self, _time: Duration) -> [JoystickData; 6] {
+            self.0.fetch_add(1, Ordering::Relaxed);
+
+            let mut data: [JoystickData; 6] = Default::default();
+            data[1].set_button(3, true);
+            data[1].set_axis(0, 0.5);
+
+            data
+        }
+    }
+
+    #[test]
+    fn source_is_read_once_per_tick() {
+        let reads = Arc::new(AtomicUsize::new(0));
+        let mut sim = Sim::new(Duration::ZERO, CountingSource(reads.clone()));
+
+        let joystick = Joystick::new(1);
+
+        for tick in 0..3 {
+            if tick > 0 {
+                sim.step();
+            }
+
+            for _ in 0..5 {
+                assert_eq!(joystick.get_button(3).value(), Some(true));
+                assert_eq!(joystick.get_axis_value(0), Some(0.5));
+                assert!(joystick.is_connected());
+            }
+        }
+
+        drop(sim);
+
+        assert_eq!(reads.load(Ordering::Relaxed), 3);
+    }
+}
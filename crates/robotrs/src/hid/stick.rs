@@ -0,0 +1,156 @@
+use hal_sys::HAL_JoystickAxes;
+
+use super::{
+    axis::get_axis,
+    joystick::Joystick,
+    reactor::{
+        add_trigger, is_active, remove_trigger, set_target, wait_for_released, wait_for_triggered,
+    },
+    ReleaseTrigger, Trigger,
+};
+
+pub(super) fn get_stick(data: &HAL_JoystickAxes, x_index: u32, y_index: u32) -> Option<(f32, f32)> {
+    Some((get_axis(data, x_index)?, get_axis(data, y_index)?))
+}
+
+/// Get the distance of a stick from the center
+pub fn stick_magnitude(x: f32, y: f32) -> f32 {
+    x.hypot(y)
+}
+
+/// Get the angle of a stick in degrees. This uses the same convention as a pov, so 0 is up, 90
+/// is right and the angle increases clockwise up to 360
+pub fn stick_angle(x: f32, y: f32) -> f32 {
+    // Pushing a stick up makes the y axis negative
+    x.atan2(-y).to_degrees().rem_euclid(360.0)
+}
+
+/// A target that defines when a [Stick] should trigger. A stick is a pair of axes, and these
+/// targets look at both at once
+#[derive(Copy, Clone, Debug)]
+pub enum StickTarget {
+    /// Activates when the stick moves farther than the given distance from the center
+    Away(f32),
+    /// Activates when the stick moves farther than `activate` from the center and releases when
+    /// it moves closer than `release`
+    AwayHysteresis { activate: f32, release: f32 },
+    /// Activates when the stick is farther than `deadband` from the center and its angle (see
+    /// [stick_angle]) is within `width / 2` degrees of `center`
+    Sector {
+        center: f32,
+        width: f32,
+        deadband: f32,
+    },
+}
+
+impl StickTarget {
+    /// Whether the target is active. `active` is whether it was active on the last tick, which
+    /// is used for hysteresis
+    pub(super) fn is_active(&self, (x, y): (f32, f32), active: bool) -> bool {
+        let magnitude = stick_magnitude(x, y);
+
+        match *self {
+            StickTarget::Away(dist) => magnitude > dist,
+            StickTarget::AwayHysteresis { activate, release } => {
+                if active {
+                    magnitude >= release
+                } else {
+                    magnitude > activate
+                }
+            }
+            StickTarget::Sector {
+                center,
+                width,
+                deadband,
+            } => {
+                let difference = (stick_angle(x, y) - center + 180.0).rem_euclid(360.0) - 180.0;
+
+                magnitude > deadband && difference.abs() <= width / 2.0
+            }
+        }
+    }
+}
+
+/// A trigger for a pair of axes, such as the x and y axes of a thumb stick
+pub struct Stick {
+    joystick: Joystick,
+    x_index: u32,
+    y_index: u32,
+    target: StickTarget,
+    reactor_idx: usize,
+}
+
+impl Clone for Stick {
+    fn clone(&self) -> Self {
+        Self {
+            joystick: self.joystick,
+            x_index: self.x_index,
+            y_index: self.y_index,
+            target: self.target,
+            reactor_idx: add_trigger(
+                &self.joystick,
+                self.x_index,
+                (self.y_index, self.target).into(),
+            ),
+        }
+    }
+}
+
+impl Drop for Stick {
+    fn drop(&mut self) {
+        remove_trigger(self.reactor_idx);
+    }
+}
+
+impl Stick {
+    pub(super) fn new(joystick: Joystick, x_index: u32, y_index: u32, target: StickTarget) -> Self {
+        Self {
+            reactor_idx: add_trigger(&joystick, x_index, (y_index, target).into()),
+            joystick,
+            x_index,
+            y_index,
+            target,
+        }
+    }
+
+    /// Get the x and y values of the stick. Returns [None] if either axis does not exist
+    pub fn value(&self) -> Option<(f32, f32)> {
+        get_stick(&self.joystick.get_axes_data(), self.x_index, self.y_index)
+    }
+
+    /// Get the distance of the stick from the center. Returns [None] if either axis does not
+    /// exist
+    pub fn magnitude(&self) -> Option<f32> {
+        self.value().map(|(x, y)| stick_magnitude(x, y))
+    }
+
+    /// Get the angle of the stick, see [stick_angle]. Returns [None] if either axis does not exist
+    pub fn angle(&self) -> Option<f32> {
+        self.value().map(|(x, y)| stick_angle(x, y))
+    }
+
+    /// Change the target of the trigger
+    pub fn set_target(&mut self, target: StickTarget) {
+        set_target(self.reactor_idx, (self.y_index, target).into());
+        self.target = target;
+    }
+}
+
+impl Trigger for Stick {
+    type Error = ();
+    type Output = ();
+
+    async fn wait_for_trigger(&mut self) -> Result<(), ()> {
+        wait_for_triggered(self.reactor_idx).await
+    }
+}
+
+impl ReleaseTrigger for Stick {
+    async fn wait_for_release(&mut self) -> Result<(), ()> {
+        wait_for_released(self.reactor_idx).await
+    }
+
+    fn is_active(&self) -> Option<bool> {
+        is_active(self.reactor_idx)
+    }
+}
@@ -0,0 +1,104 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::Stream;
+
+use super::{
+    axis::get_axis, button::get_button, joystick::Joystick, pov::get_pov, reactor::latest_snapshot,
+    source::JoystickData,
+};
+use crate::queue_waker;
+
+/// A stream of the values of a single input, read from the same snapshot the HID reactor uses.
+/// By default this yields once every tick, and [InputStream::changes] makes it only yield when
+/// the value changes. Each item is [None] if the input does not exist.
+///
+/// # Example
+///
+/// ```rust
+/// let mut turn = controller.joystick().axis_stream(4).changes();
+///
+/// while let Some(value) = turn.next().await {
+///     drivetrain.turn(value.unwrap_or(0.0))?;
+/// }
+/// ```
+pub struct InputStream<T> {
+    joystick: Joystick,
+    idx: u32,
+    read: fn(&JoystickData, u32) -> Option<T>,
+    last_tick: Option<u64>,
+    last_value: Option<Option<T>>,
+    only_changes: bool,
+}
+
+impl<T: Copy + PartialEq> InputStream<T> {
+    fn new(joystick: Joystick, idx: u32, read: fn(&JoystickData, u32) -> Option<T>) -> Self {
+        Self {
+            joystick,
+            idx,
+            read,
+            last_tick: None,
+            last_value: None,
+            only_changes: false,
+        }
+    }
+
+    /// Only yield when the value is different from the last value yielded. The first value is
+    /// always yielded
+    pub fn changes(self) -> Self {
+        Self {
+            only_changes: true,
+            ..self
+        }
+    }
+}
+
+impl<T: Copy + PartialEq + Unpin> Stream for InputStream<T> {
+    type Item = Option<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let inner = Pin::into_inner(self);
+
+        let Some((tick, data)) = latest_snapshot() else {
+            queue_waker(cx.waker().clone());
+            return Poll::Pending;
+        };
+
+        if inner.last_tick == Some(tick) {
+            queue_waker(cx.waker().clone());
+            return Poll::Pending;
+        }
+
+        inner.last_tick = Some(tick);
+
+        let value = (inner.read)(&data[inner.joystick.get_num() as usize], inner.idx);
+
+        if inner.only_changes && inner.last_value == Some(value) {
+            queue_waker(cx.waker().clone());
+            return Poll::Pending;
+        }
+
+        inner.last_value = Some(value);
+
+        Poll::Ready(Some(value))
+    }
+}
+
+impl Joystick {
+    /// Get a stream of the value of the axis at the given index (zero indexed)
+    pub fn axis_stream(&self, idx: u32) -> InputStream<f32> {
+        InputStream::new(*self, idx, |data, idx| get_axis(&data.axes, idx))
+    }
+
+    /// Get a stream of the value of the button at the given index (zero indexed)
+    pub fn button_stream(&self, idx: u32) -> InputStream<bool> {
+        InputStream::new(*self, idx, |data, idx| get_button(&data.buttons, idx))
+    }
+
+    /// Get a stream of the angle of the pov at the given index (zero indexed)
+    pub fn pov_stream(&self, idx: u32) -> InputStream<i16> {
+        InputStream::new(*self, idx, |data, idx| get_pov(&data.povs, idx))
+    }
+}
@@ -20,6 +20,8 @@ pub mod hid;
 pub mod motor;
 pub mod robot;
 pub mod scheduler;
+#[cfg(test)]
+pub(crate) mod testing;
 pub mod time;
 pub(crate) mod waker;
 
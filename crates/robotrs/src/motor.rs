@@ -1,5 +1,7 @@
 use impl_trait_for_tuples::impl_for_tuples;
 
+use math::units::Voltage;
+
 use crate::control::ControlSafe;
 
 /// What a motor should do when is is not being actively driven.
@@ -26,7 +28,9 @@ pub trait MotorController: ControlSafe {
         self.set_percent_raw(value.clamp(-1.0, 1.0))
     }
 
-    fn set_voltage(&mut self, value: f32) -> Result<(), Self::Error>;
+    /// Set the voltage applied to the motor. This is compensated for the battery voltage when the
+    /// motor controller supports it
+    fn set_voltage(&mut self, value: Voltage) -> Result<(), Self::Error>;
 
     fn set_inverted(&mut self, is_inverted: bool) -> Result<(), Self::Error> {
         let _ = is_inverted;
@@ -46,7 +50,7 @@ impl MotorController for Tuple {
         Ok(())
     }
 
-    fn set_voltage(&mut self, value: f32) -> Result<(), Self::Error> {
+    fn set_voltage(&mut self, value: Voltage) -> Result<(), Self::Error> {
         for_tuples!( #( Tuple.set_voltage(value)?; )* );
 
         Ok(())
@@ -4,7 +4,7 @@ use std::{
     io::Write,
     sync::OnceLock,
     thread::{self, ThreadId},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use anyhow::anyhow;
@@ -14,7 +14,13 @@ use futures::{Future, FutureExt, TryFutureExt};
 use tracing::{debug, error, info, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
-use crate::{ds, robot::AsyncRobot, status_to_result, time::RawNotifier, PERIODIC_CHECKS};
+use crate::{
+    ds,
+    robot::AsyncRobot,
+    status_to_result,
+    time::{precise_wakeups_enabled, RawNotifier},
+    PERIODIC_CHECKS,
+};
 
 use hal_sys::*;
 
@@ -191,6 +197,21 @@ impl<R: AsyncRobot> RobotScheduler<R> {
         }
     }
 
+    /// Wait for the next tick. With precise wakeups enabled, tasks that are woken while waiting
+    /// are run right away instead of on the next tick
+    fn sleep(&self) {
+        if !precise_wakeups_enabled() {
+            thread::sleep(PERIOD);
+            return;
+        }
+
+        let deadline = Instant::now() + PERIOD;
+
+        while let Ok(task) = self.task_receiver.recv_deadline(deadline) {
+            task.run();
+        }
+    }
+
     /// This is the main entry function. It starts the robot and schedules all the tasks as well
     /// as sending out the proper DS messages that are required for startup.
     pub fn start_robot<F: Fn() -> anyhow::Result<R> + Send + 'static>(robot: F) -> ! {
@@ -261,7 +282,7 @@ impl<R: AsyncRobot> RobotScheduler<R> {
             loop {
                 scheduler.tick();
 
-                thread::sleep(PERIOD);
+                scheduler.sleep();
 
                 // notifier = notifier
                 //     .block_until_alarm()
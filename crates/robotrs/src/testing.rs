@@ -0,0 +1,149 @@
+//! Helpers for tests that drive the reactors with a scripted clock and input source
+
+use std::{
+    cell::Cell,
+    future::Future,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures::task::noop_waker;
+use parking_lot::{Mutex, MutexGuard};
+
+use crate::{
+    ds::{update_state, State},
+    hid::{
+        reactor,
+        source::{reset_input_source, set_input_source, InputSource},
+    },
+    time::{self, get_time, set_test_time},
+};
+
+/// The reactors and input source are global, so tests that use them can't run at the same time
+static LOCK: Mutex<()> = Mutex::new(());
+
+/// How often the reactors run, the same as the robot loop
+pub(crate) const PERIOD: Duration = Duration::from_millis(20);
+
+/// Runs the HID and time reactors every [PERIOD] with a scripted clock and input source, like
+/// the robot loop does. The driver station and FPGA clock are used again when this is dropped
+pub(crate) struct Sim {
+    time: Duration,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl Sim {
+    /// Start the clock at `start` and read the joysticks from `source`. This runs the first tick
+    pub(crate) fn new(start: Duration, source: impl InputSource + 'static) -> Self {
+        let lock = LOCK.lock();
+
+        set_input_source(source);
+
+        let sim = Self {
+            time: start,
+            _lock: lock,
+        };
+
+        sim.run_reactors();
+
+        sim
+    }
+
+    pub(crate) fn now(&self) -> Duration {
+        self.time
+    }
+
+    /// Change the robot state, as if the driver station changed it. The robot starts out disabled
+    pub(crate) fn set_state(&self, state: State) {
+        update_state(state);
+    }
+
+    /// Move the clock forward by one period and run the reactors
+    pub(crate) fn step(&mut self) {
+        self.step_to(self.time + PERIOD);
+    }
+
+    /// Move the clock forward to `time` and run the reactors, as if the robot loop was blocked
+    /// until then
+    pub(crate) fn step_to(&mut self, time: Duration) {
+        assert!(time >= self.time, "The clock can't go backwards");
+
+        self.time = time;
+        self.run_reactors();
+    }
+
+    /// Poll the future once every tick until it completes, stepping the clock until it reaches
+    /// `end`. Returns the output if the future completed
+    pub(crate) fn run_until<F: Future>(
+        &mut self,
+        future: &mut Polled<F>,
+        end: Duration,
+    ) -> Option<F::Output> {
+        loop {
+            if let Poll::Ready(output) = future.poll() {
+                return Some(output);
+            }
+
+            if self.time >= end {
+                return None;
+            }
+
+            self.step();
+        }
+    }
+
+    fn run_reactors(&self) {
+        set_test_time(Some(self.time));
+        reactor::update(self.time);
+        time::reactor::wake_expired();
+        crate::poll();
+    }
+}
+
+impl Drop for Sim {
+    fn drop(&mut self) {
+        reset_input_source();
+        set_test_time(None);
+        update_state(State::Disabled);
+    }
+}
+
+/// A future that is polled by hand. The waker does nothing since [Sim] polls every tick
+pub(crate) struct Polled<F: Future> {
+    future: Pin<Box<F>>,
+    done: bool,
+}
+
+impl<F: Future> Polled<F> {
+    pub(crate) fn new(future: F) -> Self {
+        Self {
+            future: Box::pin(future),
+            done: false,
+        }
+    }
+
+    /// Poll the future once. This stays pending after the future has completed
+    pub(crate) fn poll(&mut self) -> Poll<F::Output> {
+        if self.done {
+            return Poll::Pending;
+        }
+
+        let waker = noop_waker();
+        let poll = self.future.as_mut().poll(&mut Context::from_waker(&waker));
+
+        self.done = poll.is_ready();
+
+        poll
+    }
+}
+
+/// Records the time it was dropped at
+pub(crate) struct DropGuard(pub(crate) Rc<Cell<Option<Duration>>>);
+
+impl Drop for DropGuard {
+    fn drop(&mut self) {
+        self.0.set(Some(get_time()));
+    }
+}
@@ -1,8 +1,11 @@
 use std::time::Duration;
 
+#[cfg(test)]
+use std::cell::Cell;
+
 use hal_sys::{
-    HAL_CleanNotifier, HAL_GetFPGATime, HAL_InitializeNotifier, HAL_SetNotifierThreadPriority,
-    HAL_UpdateNotifierAlarm, HAL_WaitForNotifierAlarm,
+    HAL_CancelNotifierAlarm, HAL_CleanNotifier, HAL_GetFPGATime, HAL_InitializeNotifier,
+    HAL_SetNotifierThreadPriority, HAL_UpdateNotifierAlarm, HAL_WaitForNotifierAlarm,
 };
 
 use crate::{
@@ -11,13 +14,39 @@ use crate::{
 };
 
 mod alarm;
+mod instant;
+mod interval;
 mod periodic;
-mod reactor;
+pub(crate) mod reactor;
+mod timeout;
+mod timer;
 
 pub use alarm::Alarm;
+pub use instant::FpgaInstant;
+pub use interval::{interval, interval_at, Interval, MissedTickBehavior, Tick};
 pub use periodic::Periodic;
+pub use reactor::{enable_precise_wakeups, precise_wakeups_enabled};
+pub use timeout::{deadline, timeout, TimeoutError};
+pub use timer::Timer;
+
+#[cfg(test)]
+thread_local! {
+    /// Replaces the FPGA clock in tests. See [crate::testing::Sim]
+    static TEST_TIME: Cell<Option<Duration>> = const { Cell::new(None) };
+}
+
+/// Set the time returned by [get_time] on this thread, or go back to the FPGA clock
+#[cfg(test)]
+pub(crate) fn set_test_time(time: Option<Duration>) {
+    TEST_TIME.set(time);
+}
 
 pub fn get_time() -> Duration {
+    #[cfg(test)]
+    if let Some(time) = TEST_TIME.get() {
+        return time;
+    }
+
     // Possibly use a custom instant implementation?
     Duration::from_micros(
         unsafe { status_to_result!(HAL_GetFPGATime()) }.expect("Could not get FPGA time"),
@@ -28,6 +57,11 @@ pub fn delay(duration: Duration) -> Alarm {
     Alarm::new(duration)
 }
 
+/// Wait until the given instant. This completes immediately if the instant has passed
+pub fn delay_until(instant: FpgaInstant) -> Alarm {
+    Alarm::until(instant)
+}
+
 pub struct RawNotifier {
     handle: i32,
 }
@@ -54,16 +88,42 @@ impl RawNotifier {
         Ok(())
     }
 
-    pub fn block_until_alarm(self) -> Result<Self> {
+    /// Set the alarm to go off at the given instant
+    pub fn set_deadline(&self, deadline: FpgaInstant) -> Result<()> {
+        unsafe {
+            status_to_result!(HAL_UpdateNotifierAlarm(
+                self.handle,
+                deadline.as_duration().as_micros() as u64
+            ))
+        }?;
+
+        Ok(())
+    }
+
+    /// Stop the alarm from going off until the time is set again
+    pub fn cancel(&self) -> Result<()> {
+        unsafe { status_to_result!(HAL_CancelNotifierAlarm(self.handle)) }?;
+
+        Ok(())
+    }
+
+    /// Block until the alarm goes off
+    pub fn wait(&self) -> Result<()> {
         let elapsed = unsafe { status_to_result!(HAL_WaitForNotifierAlarm(self.handle)) }?;
 
         if elapsed == 0 {
             Err(Error::NotifierStopped)
         } else {
-            Ok(self)
+            Ok(())
         }
     }
 
+    pub fn block_until_alarm(self) -> Result<Self> {
+        self.wait()?;
+
+        Ok(self)
+    }
+
     pub fn set_thread_priority() -> Result<()> {
         unsafe { status_to_result!(HAL_SetNotifierThreadPriority(1, 40)) }?;
 
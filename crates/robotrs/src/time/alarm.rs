@@ -2,11 +2,18 @@ use std::{convert::Infallible, future::Future, pin::Pin, task::Poll, time::Durat
 
 use crate::hid::Trigger;
 
-use super::get_time;
+use super::{
+    get_time,
+    instant::FpgaInstant,
+    reactor::{is_expired, TimerHandle},
+};
 
+/// A future that completes after a duration or at an instant. The alarm is removed from the
+/// time reactor when it is dropped
 pub struct Alarm {
-    pub(super) end_time: Option<Duration>,
-    pub(super) duration: Duration,
+    end_time: Option<Duration>,
+    duration: Duration,
+    timer: TimerHandle,
 }
 
 impl Alarm {
@@ -14,6 +21,16 @@ impl Alarm {
         Self {
             end_time: None,
             duration,
+            timer: TimerHandle::new(),
+        }
+    }
+
+    /// Create an alarm that completes at the given instant
+    pub fn until(instant: FpgaInstant) -> Self {
+        Self {
+            end_time: Some(instant.as_duration()),
+            duration: Duration::ZERO, // not used when the end time is known
+            timer: TimerHandle::new(),
         }
     }
 
@@ -27,7 +44,7 @@ impl Alarm {
             time
         };
 
-        if get_time() > end_time {
+        if is_expired(end_time, get_time()) {
             None
         } else {
             Some(end_time)
@@ -42,10 +59,10 @@ impl Future for Alarm {
         self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<Self::Output> {
-        let time = Pin::into_inner(self).poll();
+        let alarm = Pin::into_inner(self);
 
-        if let Some(end_time) = time {
-            super::reactor::add_time(end_time, cx.waker().clone());
+        if let Some(end_time) = alarm.poll() {
+            alarm.timer.register(end_time, cx.waker());
             Poll::Pending
         } else {
             Poll::Ready(())
@@ -0,0 +1,101 @@
+use std::{
+    ops::{Add, AddAssign, Sub, SubAssign},
+    time::Duration,
+};
+
+use super::get_time;
+
+/// A point in time measured by the FPGA clock, which starts when the FPGA boots. This is like
+/// [std::time::Instant], but it matches the timestamps used by the HAL and the driver station.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FpgaInstant(Duration);
+
+impl FpgaInstant {
+    /// Get the current time
+    pub fn now() -> Self {
+        Self(get_time())
+    }
+
+    /// Create an instant from the time since the FPGA booted
+    pub const fn from_duration(since_boot: Duration) -> Self {
+        Self(since_boot)
+    }
+
+    /// Get the time since the FPGA booted
+    pub const fn as_duration(&self) -> Duration {
+        self.0
+    }
+
+    /// Get the time that has passed since this instant. This is zero if the instant is in the
+    /// future
+    pub fn elapsed(&self) -> Duration {
+        Self::now().saturating_duration_since(*self)
+    }
+
+    /// Get the time from `earlier` to this instant, or [None] if `earlier` is after this instant
+    pub fn checked_duration_since(&self, earlier: FpgaInstant) -> Option<Duration> {
+        self.0.checked_sub(earlier.0)
+    }
+
+    /// Get the time from `earlier` to this instant, or zero if `earlier` is after this instant
+    pub fn saturating_duration_since(&self, earlier: FpgaInstant) -> Duration {
+        self.0.saturating_sub(earlier.0)
+    }
+
+    /// Get the time from `earlier` to this instant. This panics if `earlier` is after this
+    /// instant
+    pub fn duration_since(&self, earlier: FpgaInstant) -> Duration {
+        self.checked_duration_since(earlier)
+            .expect("The earlier instant was after this instant")
+    }
+
+    pub fn checked_add(&self, duration: Duration) -> Option<FpgaInstant> {
+        self.0.checked_add(duration).map(Self)
+    }
+
+    pub fn checked_sub(&self, duration: Duration) -> Option<FpgaInstant> {
+        self.0.checked_sub(duration).map(Self)
+    }
+}
+
+impl From<FpgaInstant> for Duration {
+    fn from(value: FpgaInstant) -> Self {
+        value.0
+    }
+}
+
+impl Add<Duration> for FpgaInstant {
+    type Output = FpgaInstant;
+
+    fn add(self, rhs: Duration) -> Self::Output {
+        Self(self.0 + rhs)
+    }
+}
+
+impl AddAssign<Duration> for FpgaInstant {
+    fn add_assign(&mut self, rhs: Duration) {
+        self.0 += rhs;
+    }
+}
+
+impl Sub<Duration> for FpgaInstant {
+    type Output = FpgaInstant;
+
+    fn sub(self, rhs: Duration) -> Self::Output {
+        Self(self.0 - rhs)
+    }
+}
+
+impl SubAssign<Duration> for FpgaInstant {
+    fn sub_assign(&mut self, rhs: Duration) {
+        self.0 -= rhs;
+    }
+}
+
+impl Sub<FpgaInstant> for FpgaInstant {
+    type Output = Duration;
+
+    fn sub(self, rhs: FpgaInstant) -> Self::Output {
+        self.duration_since(rhs)
+    }
+}
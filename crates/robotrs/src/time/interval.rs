@@ -0,0 +1,292 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures::{Stream, StreamExt};
+use tracing::trace;
+
+use super::{alarm::Alarm, instant::FpgaInstant};
+
+/// What an [Interval] does when a tick is late by more than a whole period, for example because
+/// the robot thread was blocked
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum MissedTickBehavior {
+    /// Fire the missed ticks as fast as possible until the interval has caught up. This is how
+    /// [Periodic](super::Periodic) behaves
+    #[default]
+    Burst,
+    /// Schedule the next tick one period after the late tick, shifting every later tick
+    Delay,
+    /// Drop the missed ticks and wait for the next tick on the original schedule
+    Skip,
+}
+
+/// A single tick of an [Interval]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Tick {
+    /// When the tick was scheduled to happen
+    pub scheduled: FpgaInstant,
+    /// When the tick actually happened
+    pub now: FpgaInstant,
+    /// The measured time since the last tick, or since the interval was created for the first
+    /// tick. Use this instead of the period when integrating
+    pub dt: Duration,
+    /// How many whole periods this tick was late by
+    pub missed: u32,
+}
+
+/// A stream that yields a [Tick] every period. Created with [interval]
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let mut ticks =
+///     interval(Duration::from_millis(10)).missed_tick_behavior(MissedTickBehavior::Skip);
+///
+/// loop {
+///     let tick = ticks.tick().await;
+///     position += velocity * tick.dt.as_secs_f32();
+/// }
+/// ```
+pub struct Interval {
+    period: Duration,
+    next: FpgaInstant,
+    last: FpgaInstant,
+    behavior: MissedTickBehavior,
+    overruns: u64,
+    alarm: Alarm,
+}
+
+/// Create an [Interval] whose first tick is one period from now
+pub fn interval(period: Duration) -> Interval {
+    let now = FpgaInstant::now();
+
+    interval_at(now + period, period)
+}
+
+/// Create an [Interval] whose first tick is at `start`
+pub fn interval_at(start: FpgaInstant, period: Duration) -> Interval {
+    assert!(
+        !period.is_zero(),
+        "The period of an interval must not be zero"
+    );
+
+    Interval {
+        period,
+        next: start,
+        last: FpgaInstant::now(),
+        behavior: MissedTickBehavior::default(),
+        overruns: 0,
+        alarm: Alarm::until(start),
+    }
+}
+
+impl Interval {
+    /// Set what happens when ticks are missed
+    pub fn missed_tick_behavior(self, behavior: MissedTickBehavior) -> Self {
+        Self { behavior, ..self }
+    }
+
+    pub fn set_missed_tick_behavior(&mut self, behavior: MissedTickBehavior) {
+        self.behavior = behavior;
+    }
+
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// Get the number of times the interval fell behind by at least a whole period. The catch-up
+    /// ticks fired by [MissedTickBehavior::Burst] after falling behind are not counted again
+    pub fn overruns(&self) -> u64 {
+        self.overruns
+    }
+
+    /// Schedule the next tick one period from now
+    pub fn reset(&mut self) {
+        self.schedule(FpgaInstant::now() + self.period);
+    }
+
+    /// Wait for the next tick
+    pub async fn tick(&mut self) -> Tick {
+        self.next()
+            .await
+            .unwrap_or_else(|| unreachable!("Intervals never end"))
+    }
+
+    fn schedule(&mut self, next: FpgaInstant) {
+        self.next = next;
+        self.alarm = Alarm::until(next);
+    }
+}
+
+impl Stream for Interval {
+    type Item = Tick;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let inner = Pin::into_inner(self);
+
+        if Future::poll(Pin::new(&mut inner.alarm), cx).is_pending() {
+            return Poll::Pending;
+        }
+
+        let now = FpgaInstant::now();
+        let scheduled = inner.next;
+
+        let missed =
+            (now.saturating_duration_since(scheduled).as_nanos() / inner.period.as_nanos()) as u32;
+
+        // A tick that was already due when the last tick happened is catching up from that
+        // tick's overrun
+        let catching_up = scheduled <= inner.last;
+
+        if missed > 0 && !catching_up {
+            trace!(missed, ?inner.behavior, "Interval tick was late");
+            inner.overruns += 1;
+        }
+
+        let next = match inner.behavior {
+            MissedTickBehavior::Burst => scheduled + inner.period,
+            MissedTickBehavior::Delay if missed > 0 => now + inner.period,
+            MissedTickBehavior::Delay => scheduled + inner.period,
+            MissedTickBehavior::Skip => scheduled + inner.period * (missed + 1),
+        };
+
+        inner.schedule(next);
+
+        let dt = now.saturating_duration_since(inner.last);
+        inner.last = now;
+
+        Poll::Ready(Some(Tick {
+            scheduled,
+            now,
+            dt,
+            missed,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        task::{Context, Poll},
+        time::Duration,
+    };
+
+    use futures::{task::noop_waker, StreamExt};
+
+    use super::{interval, Interval, MissedTickBehavior, Tick};
+    use crate::{hid::source::ScriptedInput, testing::Sim};
+
+    const START: Duration = Duration::from_secs(1);
+
+    /// The time `ms` milliseconds after the interval was created
+    fn at(ms: u64) -> Duration {
+        START + Duration::from_millis(ms)
+    }
+
+    fn poll_tick(interval: &mut Interval) -> Option<Tick> {
+        let waker = noop_waker();
+
+        match interval.poll_next_unpin(&mut Context::from_waker(&waker)) {
+            Poll::Ready(tick) => tick,
+            Poll::Pending => None,
+        }
+    }
+
+    /// Check a tick's scheduled time and how many periods it was late by
+    fn assert_tick(tick: Option<Tick>, scheduled: Duration, missed: u32) {
+        let tick = tick.expect("The interval should have ticked");
+
+        assert_eq!(tick.scheduled.as_duration(), scheduled);
+        assert_eq!(tick.missed, missed);
+    }
+
+    /// Create an interval with a 10 ms period, tick it once on time, then stall until 25 ms after
+    /// the second tick was due
+    fn stalled(sim: &mut Sim, behavior: MissedTickBehavior) -> Interval {
+        let mut ticks = interval(Duration::from_millis(10)).missed_tick_behavior(behavior);
+
+        sim.step_to(at(11));
+        assert_tick(poll_tick(&mut ticks), at(10), 0);
+
+        sim.step_to(at(45));
+        assert_tick(poll_tick(&mut ticks), at(20), 2);
+        assert_eq!(ticks.overruns(), 1);
+
+        ticks
+    }
+
+    #[test]
+    fn dt_is_measured() {
+        let mut sim = Sim::new(START, ScriptedInput::new());
+        let mut ticks = interval(Duration::from_millis(10));
+
+        sim.step_to(at(5));
+        assert!(poll_tick(&mut ticks).is_none());
+
+        sim.step_to(at(11));
+        let tick = poll_tick(&mut ticks).unwrap();
+        assert_eq!(tick.now.as_duration(), at(11));
+        assert_eq!(tick.dt, Duration::from_millis(11));
+
+        sim.step_to(at(23));
+        let tick = poll_tick(&mut ticks).unwrap();
+        assert_eq!(tick.scheduled.as_duration(), at(20));
+        assert_eq!(tick.dt, Duration::from_millis(12));
+        assert_eq!(ticks.overruns(), 0);
+    }
+
+    #[test]
+    fn burst_catches_up() {
+        let mut sim = Sim::new(START, ScriptedInput::new());
+        let mut ticks = stalled(&mut sim, MissedTickBehavior::Burst);
+
+        // The missed ticks fire right away, but only the first late tick is an overrun
+        assert_tick(poll_tick(&mut ticks), at(30), 1);
+        assert_tick(poll_tick(&mut ticks), at(40), 0);
+        assert!(poll_tick(&mut ticks).is_none());
+        assert_eq!(ticks.overruns(), 1);
+
+        sim.step_to(at(51));
+        assert_tick(poll_tick(&mut ticks), at(50), 0);
+
+        // Falling behind again is another overrun
+        sim.step_to(at(75));
+        assert_tick(poll_tick(&mut ticks), at(60), 1);
+        assert_tick(poll_tick(&mut ticks), at(70), 0);
+        assert_eq!(ticks.overruns(), 2);
+    }
+
+    #[test]
+    fn delay_shifts_schedule() {
+        let mut sim = Sim::new(START, ScriptedInput::new());
+        let mut ticks = stalled(&mut sim, MissedTickBehavior::Delay);
+
+        assert!(poll_tick(&mut ticks).is_none());
+
+        sim.step_to(at(54));
+        assert!(poll_tick(&mut ticks).is_none());
+
+        sim.step_to(at(56));
+        assert_tick(poll_tick(&mut ticks), at(55), 0);
+        assert_eq!(ticks.overruns(), 1);
+    }
+
+    #[test]
+    fn skip_keeps_schedule() {
+        let mut sim = Sim::new(START, ScriptedInput::new());
+        let mut ticks = stalled(&mut sim, MissedTickBehavior::Skip);
+
+        assert!(poll_tick(&mut ticks).is_none());
+
+        sim.step_to(at(51));
+        let tick = poll_tick(&mut ticks).unwrap();
+        assert_eq!(tick.scheduled.as_duration(), at(50));
+        assert_eq!(tick.missed, 0);
+        assert_eq!(tick.dt, Duration::from_millis(6));
+        assert_eq!(ticks.overruns(), 1);
+    }
+}
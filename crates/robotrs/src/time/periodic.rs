@@ -1,7 +1,9 @@
 use std::time::Duration;
 
-use super::{alarm::Alarm, get_time};
+use super::{alarm::Alarm, get_time, instant::FpgaInstant};
 
+/// Waits for a fixed period. If a wait starts late, the missed periods complete immediately. See
+/// [Interval](super::Interval) for other ways of handling missed periods and measured time steps
 pub struct Periodic {
     period: Duration,
     end_time: Duration,
@@ -16,11 +18,7 @@ impl Periodic {
     }
 
     pub async fn wait(&mut self) {
-        Alarm {
-            end_time: Some(self.end_time),
-            duration: self.period, // not actually used
-        }
-        .await;
+        Alarm::until(FpgaInstant::from_duration(self.end_time)).await;
 
         self.end_time += self.period;
     }
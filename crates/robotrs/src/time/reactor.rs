@@ -1,43 +1,159 @@
-use std::{collections::BinaryHeap, sync::LazyLock, task::Waker, time::Duration};
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        LazyLock, OnceLock,
+    },
+    task::Waker,
+    thread,
+    time::Duration,
+};
 
 use linkme::distributed_slice;
 use parking_lot::Mutex;
-use tracing::{span, trace, Level, Span};
+use tracing::{debug, error, span, trace, Level, Span};
 
-use crate::PERIODIC_CHECKS;
+use crate::{error::Result, PERIODIC_CHECKS};
 
-use super::get_time;
+use super::{get_time, instant::FpgaInstant, RawNotifier};
 
-/// The Ord implementation is reversed, which is needed for the heap
-struct TimeItem {
-    time: Duration,
-    waker: Waker,
+/// Registered timers keyed by their deadline and a unique id, so the earliest timer is first
+static TIMERS: Mutex<BTreeMap<(Duration, u64), Waker>> = Mutex::new(BTreeMap::new());
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// A timer registered with the reactor. The timer is removed from the reactor when the handle is
+/// dropped, and registering again replaces the previous registration instead of adding another
+#[derive(Debug, Default)]
+pub struct TimerHandle {
+    key: Option<(Duration, u64)>,
 }
 
-impl PartialEq for TimeItem {
-    fn eq(&self, other: &Self) -> bool {
-        other.time.eq(&self.time)
+impl TimerHandle {
+    pub const fn new() -> Self {
+        Self { key: None }
+    }
+
+    /// Wake `waker` once the FPGA time reaches `time`
+    pub fn register(&mut self, time: Duration, waker: &Waker) {
+        let mut timers = TIMERS.lock();
+
+        if let Some(key) = self.key {
+            if key.0 == time {
+                if let Some(existing) = timers.get_mut(&key) {
+                    if !existing.will_wake(waker) {
+                        *existing = waker.clone();
+                    }
+
+                    return;
+                }
+            } else {
+                timers.remove(&key);
+            }
+        }
+
+        let key = (time, NEXT_ID.fetch_add(1, Ordering::Relaxed));
+        timers.insert(key, waker.clone());
+        self.key = Some(key);
+
+        let earliest = timers.first_key_value().map(|(first, _)| *first) == Some(key);
+
+        drop(timers);
+
+        if earliest {
+            reschedule_notifier(time);
+        }
+    }
+}
+
+impl Drop for TimerHandle {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            TIMERS.lock().remove(&key);
+        }
     }
 }
 
-impl Eq for TimeItem {}
+/// Whether the FPGA time has reached a deadline. Alarms and the reactor both use this, so a timer
+/// is woken on the same tick its alarm is ready
+pub(super) fn is_expired(deadline: Duration, now: Duration) -> bool {
+    now >= deadline
+}
+
+/// The notifier used for precise wakeups, if they are enabled
+static NOTIFIER: OnceLock<RawNotifier> = OnceLock::new();
 
-impl PartialOrd for TimeItem {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
+/// Returns true if [enable_precise_wakeups] has been called successfully
+pub fn precise_wakeups_enabled() -> bool {
+    NOTIFIER.get().is_some()
+}
+
+/// Wake timers at their exact deadline using a HAL notifier instead of on the next 20 ms tick.
+/// Woken tasks are run as soon as they are woken, between ticks. This starts a background thread
+/// and only needs to be called once.
+pub fn enable_precise_wakeups() -> Result<()> {
+    if precise_wakeups_enabled() {
+        return Ok(());
     }
+
+    let notifier = RawNotifier::new(Duration::ZERO)?;
+    notifier.cancel()?;
+
+    if NOTIFIER.set(notifier).is_err() {
+        // Another thread enabled precise wakeups first
+        return Ok(());
+    }
+
+    let notifier = NOTIFIER.get().unwrap_or_else(|| unreachable!());
+
+    thread::spawn(move || {
+        debug!("Starting precise wakeup thread");
+
+        loop {
+            if let Some(first) = earliest() {
+                if let Err(err) = notifier.set_deadline(FpgaInstant::from_duration(first)) {
+                    error!("Could not update the precise wakeup notifier: {}", err);
+                }
+            }
+
+            if let Err(err) = notifier.wait() {
+                error!("Stopping precise wakeups: {}", err);
+                return;
+            }
+
+            wake_expired();
+        }
+    });
+
+    Ok(())
+}
+
+fn earliest() -> Option<Duration> {
+    TIMERS.lock().first_key_value().map(|((time, _), _)| *time)
 }
 
-impl Ord for TimeItem {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        other.time.cmp(&self.time)
+fn reschedule_notifier(time: Duration) {
+    if let Some(notifier) = NOTIFIER.get() {
+        if let Err(err) = notifier.set_deadline(FpgaInstant::from_duration(time)) {
+            error!("Could not update the precise wakeup notifier: {}", err);
+        }
     }
 }
 
-static QUEUE: Mutex<BinaryHeap<TimeItem>> = Mutex::new(BinaryHeap::new());
+pub(crate) fn wake_expired() {
+    let mut timers = TIMERS.lock();
+
+    let time = get_time();
+
+    while let Some(entry) = timers.first_entry() {
+        if !is_expired(entry.key().0, time) {
+            break;
+        }
 
-pub fn add_time(time: Duration, waker: Waker) {
-    QUEUE.lock().push(TimeItem { time, waker });
+        trace!("Waking item from time reactor");
+
+        entry.remove().wake();
+    }
 }
 
 static POLL_SPAN: LazyLock<Span> = LazyLock::new(|| span!(Level::TRACE, "time poll"));
@@ -45,17 +161,140 @@ static POLL_SPAN: LazyLock<Span> = LazyLock::new(|| span!(Level::TRACE, "time po
 #[distributed_slice(PERIODIC_CHECKS)]
 fn poll() {
     let _span_guard = POLL_SPAN.enter();
-    let mut queue = QUEUE.lock();
 
-    let time = get_time();
+    wake_expired();
+}
 
-    while let Some(item) = queue.peek() {
-        if item.time > time {
-            break;
+#[cfg(test)]
+mod tests {
+    use std::{
+        future::Future,
+        pin::pin,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        task::{Context, Poll, Wake, Waker},
+        time::Duration,
+    };
+
+    use futures::task::noop_waker;
+
+    use super::{TimerHandle, TIMERS};
+    use crate::{
+        hid::source::ScriptedInput,
+        testing::Sim,
+        time::{alarm::Alarm, instant::FpgaInstant},
+    };
+
+    const START: Duration = Duration::from_secs(1);
+
+    fn at(ms: u64) -> Duration {
+        START + Duration::from_millis(ms)
+    }
+
+    /// Counts how many times it was woken
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::Relaxed);
         }
+    }
 
-        trace!("Waking item from time reactor");
+    fn is_registered(handle: &TimerHandle) -> bool {
+        handle
+            .key
+            .is_some_and(|key| TIMERS.lock().contains_key(&key))
+    }
+
+    #[test]
+    fn register_same_deadline_keeps_one_entry() {
+        let _sim = Sim::new(START, ScriptedInput::new());
+        let before = TIMERS.lock().len();
+
+        let waker = noop_waker();
+        let mut handle = TimerHandle::new();
+
+        handle.register(at(10), &waker);
+        let key = handle.key;
+
+        for _ in 0..3 {
+            handle.register(at(10), &waker);
+        }
+
+        assert_eq!(handle.key, key);
+        assert_eq!(TIMERS.lock().len(), before + 1);
+    }
+
+    #[test]
+    fn deadline_change_removes_old_key() {
+        let _sim = Sim::new(START, ScriptedInput::new());
+        let before = TIMERS.lock().len();
+
+        let waker = noop_waker();
+        let mut handle = TimerHandle::new();
+
+        handle.register(at(10), &waker);
+        let old_key = handle.key.unwrap();
+
+        handle.register(at(20), &waker);
+
+        assert!(!TIMERS.lock().contains_key(&old_key));
+        assert_eq!(handle.key.unwrap().0, at(20));
+        assert!(is_registered(&handle));
+        assert_eq!(TIMERS.lock().len(), before + 1);
+    }
+
+    #[test]
+    fn drop_removes_entry() {
+        let _sim = Sim::new(START, ScriptedInput::new());
+        let before = TIMERS.lock().len();
+
+        let mut handle = TimerHandle::new();
+        handle.register(at(10), &noop_waker());
+        let key = handle.key.unwrap();
+
+        drop(handle);
+
+        assert!(!TIMERS.lock().contains_key(&key));
+        assert_eq!(TIMERS.lock().len(), before);
+    }
+
+    #[test]
+    fn wake_expired_removes_only_expired() {
+        let mut sim = Sim::new(START, ScriptedInput::new());
+
+        let waker = noop_waker();
+        let mut early = TimerHandle::new();
+        let mut late = TimerHandle::new();
+
+        early.register(at(10), &waker);
+        late.register(at(30), &waker);
+
+        sim.step_to(at(20));
+        assert!(!is_registered(&early));
+        assert!(is_registered(&late));
+
+        // A timer is woken once the time reaches its deadline
+        sim.step_to(at(30));
+        assert!(!is_registered(&late));
+    }
+
+    #[test]
+    fn alarm_is_ready_when_woken() {
+        let mut sim = Sim::new(START, ScriptedInput::new());
+
+        let wakes = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let waker = Waker::from(wakes.clone());
+        let mut cx = Context::from_waker(&waker);
+        let mut alarm = pin!(Alarm::until(FpgaInstant::from_duration(at(30))));
+
+        sim.step_to(at(29));
+        assert_eq!(alarm.as_mut().poll(&mut cx), Poll::Pending);
 
-        queue.pop().unwrap_or_else(|| unreachable!()).waker.wake();
+        sim.step_to(at(30));
+        assert_eq!(wakes.0.load(Ordering::Relaxed), 1);
+        assert_eq!(alarm.as_mut().poll(&mut cx), Poll::Ready(()));
     }
 }
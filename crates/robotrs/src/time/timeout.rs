@@ -0,0 +1,37 @@
+use std::{future::Future, time::Duration};
+
+use futures_concurrency::future::Race;
+
+use super::{delay_until, instant::FpgaInstant};
+
+/// The error returned when a future passed to [timeout] or [deadline] does not complete in time
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("The future did not complete before the deadline")]
+pub struct TimeoutError {
+    /// When the future was cancelled
+    pub deadline: FpgaInstant,
+}
+
+/// Run a future, cancelling it if it does not complete within `duration`
+///
+/// # Example
+///
+/// ```rust,ignore
+/// match timeout(Duration::from_secs(3), arm.raise()).await {
+///     Ok(res) => res?,
+///     Err(_) => warn!("The arm did not reach the top"),
+/// }
+/// ```
+pub async fn timeout<F: Future>(duration: Duration, fut: F) -> Result<F::Output, TimeoutError> {
+    deadline(FpgaInstant::now() + duration, fut).await
+}
+
+/// Run a future, cancelling it if it has not completed by `instant`
+pub async fn deadline<F: Future>(instant: FpgaInstant, fut: F) -> Result<F::Output, TimeoutError> {
+    (async { Ok(fut.await) }, async {
+        delay_until(instant).await;
+        Err(TimeoutError { deadline: instant })
+    })
+        .race()
+        .await
+}
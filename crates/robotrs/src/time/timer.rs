@@ -0,0 +1,94 @@
+use std::time::Duration;
+
+use super::instant::FpgaInstant;
+
+/// A stopwatch that measures time while it is running, like the WPILib `Timer`. A new timer is
+/// stopped with nothing elapsed.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let mut timer = Timer::new();
+/// timer.start();
+///
+/// while !timer.has_elapsed(Duration::from_secs(2)) {
+///     drivetrain.drive(0.5)?;
+///     yield_now().await;
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Timer {
+    /// The time elapsed before the timer was last started
+    accumulated: Duration,
+    /// When the timer was started, or [None] if it is stopped
+    started_at: Option<FpgaInstant>,
+}
+
+impl Timer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start the timer. This does nothing if it is already running
+    pub fn start(&mut self) {
+        if self.started_at.is_none() {
+            self.started_at = Some(FpgaInstant::now());
+        }
+    }
+
+    /// Stop the timer, keeping the elapsed time
+    pub fn stop(&mut self) {
+        self.accumulated = self.elapsed();
+        self.started_at = None;
+    }
+
+    /// Set the elapsed time to zero. The timer keeps running if it was running
+    pub fn reset(&mut self) {
+        self.accumulated = Duration::ZERO;
+
+        if self.started_at.is_some() {
+            self.started_at = Some(FpgaInstant::now());
+        }
+    }
+
+    /// Reset and start the timer
+    pub fn restart(&mut self) {
+        self.reset();
+        self.start();
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.started_at.is_some()
+    }
+
+    /// Get the total time the timer has been running since it was last reset
+    pub fn elapsed(&self) -> Duration {
+        self.accumulated
+            + self
+                .started_at
+                .map(|started_at| started_at.elapsed())
+                .unwrap_or_default()
+    }
+
+    /// Returns true if at least `duration` has elapsed
+    pub fn has_elapsed(&self, duration: Duration) -> bool {
+        self.elapsed() >= duration
+    }
+
+    /// If `period` has elapsed, take it off of the elapsed time and return true. This can be used
+    /// to run something periodically without drifting
+    pub fn advance_if_elapsed(&mut self, period: Duration) -> bool {
+        if !self.has_elapsed(period) {
+            return false;
+        }
+
+        let from_accumulated = period.min(self.accumulated);
+        self.accumulated -= from_accumulated;
+
+        if let Some(started_at) = &mut self.started_at {
+            *started_at += period - from_accumulated;
+        }
+
+        true
+    }
+}
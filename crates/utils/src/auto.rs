@@ -0,0 +1,346 @@
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    sync::atomic::{AtomicI64, Ordering},
+};
+
+use futures::{future::LocalBoxFuture, Future, FutureExt};
+use nt::{Instance, Publisher, Subscriber};
+use robotrs::{
+    command::{Command, ToFuture},
+    math::geometry::Pose2d,
+    scheduler::spawn,
+    yield_now,
+};
+use tracing::{debug, info, span, warn, Instrument, Level};
+
+static NEXT_INSTANCE: AtomicI64 = AtomicI64::new(0);
+
+type RoutineFactory<T> = Box<dyn Fn(&'static T) -> LocalBoxFuture<'static, anyhow::Result<()>>>;
+
+/// A named autonomous routine. `T` is the type passed to the routine when it is started, which
+/// is usually the robot.
+pub struct AutoRoutine<T: 'static> {
+    name: String,
+    starting_pose: Option<Pose2d>,
+    factory: RoutineFactory<T>,
+}
+
+impl<T: 'static> AutoRoutine<T> {
+    /// Create a routine that runs the future returned by `factory`
+    pub fn new<F, Fut>(name: impl Into<String>, factory: F) -> Self
+    where
+        F: Fn(&'static T) -> Fut + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + 'static,
+    {
+        Self {
+            name: name.into(),
+            starting_pose: None,
+            factory: Box::new(move |ctx| factory(ctx).boxed_local()),
+        }
+    }
+
+    /// Create a routine that runs the [Command] returned by `factory`
+    pub fn from_command<F, C>(name: impl Into<String>, factory: F) -> Self
+    where
+        F: Fn(&'static T) -> C + 'static,
+        C: Command + Unpin + 'static,
+    {
+        Self::new(name, move |ctx| factory(ctx).to_future())
+    }
+
+    /// Set the pose the robot should be placed at before this routine starts
+    pub fn starting_pose(self, pose: Pose2d) -> Self {
+        Self {
+            starting_pose: Some(pose),
+            ..self
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_starting_pose(&self) -> Option<Pose2d> {
+        self.starting_pose
+    }
+
+    /// Create the future for this routine
+    pub fn start(&self, ctx: &'static T) -> impl Future<Output = anyhow::Result<()>> {
+        (self.factory)(ctx)
+    }
+}
+
+/// The NetworkTables topics used by a `SendableChooser`
+struct ChooserTopics {
+    #[allow(dead_code)] // These are kept so the publishers stay alive
+    type_name: Publisher<'static, String>,
+    #[allow(dead_code)]
+    name: Publisher<'static, String>,
+    #[allow(dead_code)]
+    controllable: Publisher<'static, bool>,
+    #[allow(dead_code)]
+    instance: Publisher<'static, i64>,
+    options: Publisher<'static, Vec<String>>,
+    default: Publisher<'static, String>,
+    active: Publisher<'static, String>,
+    selected: Subscriber<'static, String>,
+}
+
+/// The names of the registered routines and which one is the default
+#[derive(Debug, Default)]
+struct Options {
+    names: Vec<String>,
+    default: Option<usize>,
+}
+
+impl Options {
+    fn add(&mut self, name: &str, default: bool) {
+        if default {
+            self.default = Some(self.names.len());
+        }
+
+        self.names.push(name.to_string());
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|option| option == name)
+    }
+
+    /// Get the index of the routine that runs when `selected` is picked on the dashboard, falling
+    /// back to the default if the selection is empty or unknown
+    fn resolve(&self, selected: &str) -> Option<usize> {
+        self.position(selected).or(self.default)
+    }
+
+    fn default_name(&self) -> Option<&str> {
+        self.default.map(|idx| self.names[idx].as_str())
+    }
+}
+
+/// The state shared between a chooser and the task that publishes the active routine
+struct ChooserState {
+    topics: ChooserTopics,
+    options: RefCell<Options>,
+}
+
+impl ChooserState {
+    /// Get the name of the routine that will run with the current selection
+    fn active(&self) -> String {
+        let options = self.options.borrow();
+
+        options
+            .resolve(&self.topics.selected.get())
+            .map(|idx| options.names[idx].clone())
+            .unwrap_or_default()
+    }
+}
+
+/// A registry of autonomous routines that is published to NetworkTables as a `SendableChooser`,
+/// so the driver can pick a routine from the dashboard.
+///
+/// # Example
+///
+/// ```rust
+/// let autos = AutoChooser::new("Auto")
+///     .with_default(AutoRoutine::new("Score", |robot: &'static Robot| robot.score()))
+///     .with(AutoRoutine::new("Drive", |robot: &'static Robot| robot.drive_out()));
+///
+/// // in AsyncRobot::get_auto_future
+/// self.autos.run(self).await
+/// ```
+pub struct AutoChooser<T: 'static> {
+    routines: Vec<AutoRoutine<T>>,
+    state: Rc<ChooserState>,
+}
+
+impl<T: 'static> AutoChooser<T> {
+    /// Create a new chooser published under `/SmartDashboard/{name}` on the default
+    /// NetworkTables instance. This must be called from the robot thread, see
+    /// [robotrs::scheduler::spawn]. The routine that will run is published as the active option
+    /// until the chooser is dropped.
+    pub fn new(name: &str) -> Self {
+        let instance = Instance::default_instance();
+        let topic = |key: &str| instance.topic(&format!("/SmartDashboard/{}/{}", name, key));
+
+        let topics = ChooserTopics {
+            type_name: topic(".type").publish(Default::default()),
+            name: topic(".name").publish(Default::default()),
+            controllable: topic(".controllable").publish(Default::default()),
+            instance: topic(".instance").publish(Default::default()),
+            options: topic("options").publish(Default::default()),
+            default: topic("default").publish(Default::default()),
+            active: topic("active").publish(Default::default()),
+            selected: topic("selected").subscribe(Default::default()),
+        };
+
+        topics.type_name.set("String Chooser".to_string());
+        topics.name.set(name.to_string());
+        topics.controllable.set(true);
+        topics
+            .instance
+            .set(NEXT_INSTANCE.fetch_add(1, Ordering::Relaxed));
+        topics.options.set(Vec::new());
+        topics.default.set(String::new());
+        topics.active.set(String::new());
+
+        let state = Rc::new(ChooserState {
+            topics,
+            options: RefCell::new(Options::default()),
+        });
+
+        let weak_state = Rc::downgrade(&state);
+
+        spawn(
+            async move {
+                let mut last_active = String::new();
+
+                // Stop once the chooser has been dropped
+                while let Some(state) = weak_state.upgrade() {
+                    let active = state.active();
+
+                    if active != last_active {
+                        debug!(active, "Active auto changed");
+                        state.topics.active.set(active.clone());
+                        last_active = active;
+                    }
+
+                    drop(state);
+
+                    yield_now().await;
+                }
+            }
+            .instrument(span!(Level::TRACE, "auto chooser", name)),
+        )
+        .detach();
+
+        Self {
+            routines: Vec::new(),
+            state,
+        }
+    }
+
+    /// Register a routine as an option
+    pub fn with(self, routine: AutoRoutine<T>) -> Self {
+        self.add(routine, false)
+    }
+
+    /// Register a routine and select it when the dashboard has not picked anything
+    pub fn with_default(self, routine: AutoRoutine<T>) -> Self {
+        self.add(routine, true)
+    }
+
+    fn add(mut self, routine: AutoRoutine<T>, default: bool) -> Self {
+        let mut options = self.state.options.borrow_mut();
+
+        options.add(routine.get_name(), default);
+
+        let topics = &self.state.topics;
+
+        topics.options.set(options.names.clone());
+        topics
+            .default
+            .set(options.default_name().unwrap_or_default().to_string());
+
+        drop(options);
+        self.routines.push(routine);
+
+        self
+    }
+
+    /// Get all of the registered routines
+    pub fn routines(&self) -> &[AutoRoutine<T>] {
+        &self.routines
+    }
+
+    /// Get the routine currently selected on the dashboard, falling back to the default routine
+    /// if the selection is empty or unknown
+    pub fn selected(&self) -> Option<&AutoRoutine<T>> {
+        let selected = self.state.topics.selected.get();
+        let options = self.state.options.borrow();
+
+        if !selected.is_empty() && options.position(&selected).is_none() {
+            warn!("Selected auto {} does not exist, using default", selected);
+        }
+
+        options.resolve(&selected).map(|idx| &self.routines[idx])
+    }
+
+    /// Run the selected routine. If no routine is selected and there is no default, this
+    /// completes immediately
+    pub async fn run(&self, ctx: &'static T) -> anyhow::Result<()> {
+        let Some(routine) = self.selected() else {
+            warn!("No auto selected");
+            return Ok(());
+        };
+
+        info!("Running auto {}", routine.get_name());
+
+        routine.start(ctx).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+
+    use super::*;
+
+    fn options(names: &[&str], default: Option<&str>) -> Options {
+        let mut options = Options::default();
+
+        for name in names {
+            options.add(name, Some(*name) == default);
+        }
+
+        options
+    }
+
+    #[test]
+    fn options_are_registered_in_order() {
+        let options = options(&["Score", "Drive", "Nothing"], Some("Drive"));
+
+        assert_eq!(options.names, ["Score", "Drive", "Nothing"]);
+        assert_eq!(options.default_name(), Some("Drive"));
+        assert_eq!(options.resolve("Nothing"), Some(2));
+    }
+
+    #[test]
+    fn empty_selection_uses_default() {
+        let options = options(&["Score", "Drive"], Some("Drive"));
+
+        assert_eq!(options.resolve(""), Some(1));
+    }
+
+    #[test]
+    fn unknown_selection_uses_default() {
+        let options = options(&["Score", "Drive"], Some("Score"));
+
+        assert_eq!(options.resolve("Old auto"), Some(0));
+    }
+
+    #[test]
+    fn no_default() {
+        let options = options(&["Score", "Drive"], None);
+
+        assert_eq!(options.default_name(), None);
+        assert_eq!(options.resolve(""), None);
+        assert_eq!(options.resolve("Old auto"), None);
+        assert_eq!(options.resolve("Drive"), Some(1));
+    }
+
+    #[test]
+    fn routine_runs_with_context() {
+        static CONTEXT: i32 = 3;
+
+        let routine = AutoRoutine::new("Test", |ctx: &'static i32| async move {
+            anyhow::ensure!(*ctx == 3, "Wrong context");
+            Ok(())
+        })
+        .starting_pose(Pose2d::default());
+
+        assert_eq!(routine.get_name(), "Test");
+        assert_eq!(routine.get_starting_pose(), Some(Pose2d::default()));
+        assert!(block_on(routine.start(&CONTEXT)).is_ok());
+    }
+}
@@ -1,5 +1,6 @@
 #![feature(try_blocks)]
 
+pub mod auto;
 pub mod error;
 pub mod mechanism;
 pub mod subsystem;
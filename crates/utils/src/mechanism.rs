@@ -2,7 +2,10 @@ use flume::{Receiver, Sender};
 use futures::channel::oneshot;
 use futures_concurrency::future::Race;
 use robotrs::{
-    control::ControlSafe, math::Controller, motor::MotorController, scheduler, yield_now,
+    control::ControlSafe,
+    math::{units::Voltage, AtGoal, Controller},
+    motor::MotorController,
+    scheduler, yield_now,
 };
 use std::fmt::Debug;
 use tracing::{debug, error, instrument, span, trace, warn, Instrument, Level};
@@ -31,6 +34,40 @@ impl<I: 'static, E: Debug + 'static> Mechanism<I, E> {
         Supply: FnMut() -> Result<I, E> + 'static,
         Consume: FnMut(MechanismState<O>) -> Result<(), E> + 'static,
         Check: FnMut(&I, &I) -> bool + 'static,
+    >(
+        controller: C,
+        supplier: Supply,
+        consumer: Consume,
+        mut at_setpoint: Check,
+    ) -> Self {
+        Self::spawn(controller, supplier, consumer, move |_, current, target| {
+            at_setpoint(current, target)
+        })
+    }
+
+    /// Create a mechanism that is at its setpoint when the controller reports that it is at its
+    /// goal, such as a [ProfiledPid](robotrs::math::profile::ProfiledPid)
+    pub fn with_controller_goal<
+        O,
+        C: Controller<State = I, Output = O> + AtGoal + 'static,
+        Supply: FnMut() -> Result<I, E> + 'static,
+        Consume: FnMut(MechanismState<O>) -> Result<(), E> + 'static,
+    >(
+        controller: C,
+        supplier: Supply,
+        consumer: Consume,
+    ) -> Self {
+        Self::spawn(controller, supplier, consumer, |controller, _, _| {
+            controller.at_goal()
+        })
+    }
+
+    fn spawn<
+        O,
+        C: Controller<State = I, Output = O> + 'static,
+        Supply: FnMut() -> Result<I, E> + 'static,
+        Consume: FnMut(MechanismState<O>) -> Result<(), E> + 'static,
+        Check: FnMut(&C, &I, &I) -> bool + 'static,
     >(
         mut controller: C,
         mut supplier: Supply,
@@ -66,7 +103,7 @@ impl<I: 'static, E: Debug + 'static> Mechanism<I, E> {
                                         controller.calculate(&current_state, &request.state),
                                     ))?;
 
-                                    if at_setpoint(&current_state, &request.state) {
+                                    if at_setpoint(&controller, &current_state, &request.state) {
                                         if let Some(response) = response.take() {
                                             trace!("At setpoint, alerting origin");
                                             if response.send(()).is_err() {
@@ -165,7 +202,7 @@ impl<I: 'static, E: Debug + 'static> ControlSafe for Mechanism<I, E> {
 }
 
 pub trait MechanismMotor: MotorController {
-    fn set_mechanism_state(&mut self, state: MechanismState<f32>) -> Result<(), Self::Error> {
+    fn set_mechanism_state(&mut self, state: MechanismState<Voltage>) -> Result<(), Self::Error> {
         match state {
             MechanismState::Value(voltage) => self.set_voltage(voltage),
             MechanismState::Stop => {